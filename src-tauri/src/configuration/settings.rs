@@ -1,20 +1,48 @@
-use std::fs;
+use std::{fs, path::Path};
 
-use log::warn;
+use log::{info, warn};
 use serde::{Deserialize, Serialize};
 
 use crate::{minecraft::java::JavaDetails, get_config_dir};
 
 
 const SETTINGS_FILE_NAME: &str = "settings.json";
+const DEFAULT_TRASH_RETENTION_DAYS: u32 = 30;
+/// A proxy for the CurseForge API that doesn't require an API key
+const DEFAULT_CURSEFORGE_API_URL: &str = "https://api.curse.tools/v1/cf";
 
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct AppSettings {
     pub instance_size: u16,
-    pub instance_path: Option<String>,
-    pub icon_path: Option<String>,
-    pub java_settings: Vec<JavaDetails>
+    /// The folders that instances are gathered from, new instances go into the first enabled one
+    #[serde(default)]
+    pub instance_roots: Vec<InstanceRoot>,
+    /// Replaced by `instance_roots`, only read to migrate older settings
+    #[serde(default, skip_serializing)]
+    instance_path: Option<String>,
+    /// Replaced by `InstanceRoot::icon_path`, only read to migrate older settings
+    #[serde(default, skip_serializing)]
+    icon_path: Option<String>,
+    pub java_settings: Vec<JavaDetails>,
+    /// Base url of a CurseForge compatible API, used to resolve modpack files
+    #[serde(default = "default_curseforge_api_url")]
+    pub curseforge_api_url: String,
+    /// Only needed if `curseforge_api_url` points to the official API
+    #[serde(default)]
+    pub curseforge_api_key: Option<String>,
+    /// Deleted instances are purged from the trash after this many days
+    #[serde(default = "default_trash_retention_days")]
+    pub trash_retention_days: u32
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct InstanceRoot {
+    pub label: String,
+    pub path: String,
+    pub enabled: bool,
+    /// Where the MultiMC and CurseForge icons of this root are, MultiMC keeps them next to the instance folder
+    pub icon_path: Option<String>
 }
 
 #[tauri::command]
@@ -36,8 +64,13 @@ impl AppSettings {
         }
 
         let file = fs::read_to_string(path).expect("Failed to read settings file!");
-        match serde_json::from_str(&file) {
-            Ok(settings) => settings,
+        match serde_json::from_str::<Self>(&file) {
+            Ok(mut settings) => {
+                if settings.migrate() {
+                    settings.set();
+                }
+                settings
+            },
             Err(err) => {
                 warn!("Failed to parse settings: {err}, resetting them!");
                 Self::generate()
@@ -45,10 +78,34 @@ impl AppSettings {
         }
     }
 
-    pub fn set(self) {
+    pub fn set(&self) {
         let path = get_config_dir().join(SETTINGS_FILE_NAME);
 
-        fs::write(path, serde_json::to_string_pretty(&self).unwrap()).expect("Failed to write to settings file!");
+        fs::write(path, serde_json::to_string_pretty(self).unwrap()).expect("Failed to write to settings file!");
+    }
+
+    /// The enabled root new instances are created in
+    pub fn get_primary_root(&self) -> Option<&InstanceRoot> {
+        self.instance_roots.iter().find(|root| root.enabled)
+    }
+
+    /// The root that the instance at `instance_path` lives in
+    pub fn get_root(&self, instance_path: &Path) -> Option<&InstanceRoot> {
+        let parent = instance_path.parent()?;
+        self.instance_roots.iter().find(|root| Path::new(&root.path) == parent)
+    }
+
+    /// Moves the single instance and icon path of older versions into `instance_roots`, returns whether anything changed
+    fn migrate(&mut self) -> bool {
+        let icon_path = self.icon_path.take();
+        match self.instance_path.take() {
+            Some(path) => {
+                info!("Migrating the instance path {path} to an instance root");
+                self.instance_roots.push(InstanceRoot::new(path, icon_path));
+                true
+            },
+            None => icon_path.is_some()
+        }
     }
 
     fn generate() -> Self {
@@ -56,9 +113,13 @@ impl AppSettings {
 
         let defaults = AppSettings {
             instance_size: 16,
+            instance_roots: Vec::new(),
             instance_path: None,
             icon_path: None,
             java_settings: Vec::new(),
+            curseforge_api_url: default_curseforge_api_url(),
+            curseforge_api_key: None,
+            trash_retention_days: default_trash_retention_days()
         };
 
         if let Some(parent) = path.parent() {
@@ -70,4 +131,22 @@ impl AppSettings {
         fs::write(path, serde_json::to_string_pretty(&defaults).unwrap()).expect("Failed to write to settings file!");
         defaults
     }
-}
\ No newline at end of file
+}
+
+impl InstanceRoot {
+    /// Labels the root after the folder containing it, which usually is the launcher's data folder
+    pub fn new(path: String, icon_path: Option<String>) -> Self {
+        let label = Path::new(&path).parent()
+            .and_then(Path::file_name)
+            .map_or(path.to_string(), |name| name.to_string_lossy().to_string());
+        Self { label, path, enabled: true, icon_path }
+    }
+}
+
+fn default_curseforge_api_url() -> String {
+    DEFAULT_CURSEFORGE_API_URL.to_string()
+}
+
+fn default_trash_retention_days() -> u32 {
+    DEFAULT_TRASH_RETENTION_DAYS
+}
@@ -4,14 +4,16 @@
     windows_subsystem = "windows"
 )]
 
-use std::{fs::{self, create_dir_all}, path::{Path, PathBuf}, io::Cursor};
+use std::{collections::HashMap, fs::{self, create_dir_all}, path::{Path, PathBuf}, io::{Cursor, Read}};
 use log::debug;
 use reqwest::Client;
 use serde::Serialize;
 use sha1_smol::Sha1;
+use sha2::{Digest, Sha512};
 use simple_logger::SimpleLogger;
 use tauri::{AppHandle, Manager, api::path::{data_dir, config_dir}};
-use minecraft::{launching, java, authentication::auth};
+use minecraft::{launching, java, java_runtimes, authentication::auth};
+use zip::{ZipArchive, result::ZipError};
 
 pub mod minecraft {
     pub mod launching {
@@ -19,6 +21,9 @@ pub mod minecraft {
         pub mod manifests;
         pub mod libraries;
         pub mod mc_structs;
+        pub mod overrides;
+        pub mod monitor;
+        pub mod smoke_test;
     }
     pub mod modloaders {
         pub mod modloaders;
@@ -31,12 +36,54 @@ pub mod minecraft {
         pub mod errors;
         pub mod curseforge;
         pub mod multimc;
+        pub mod multimc_components;
+        pub mod yamcl;
+        pub mod modrinth;
+        pub mod atlauncher;
+        pub mod gdlauncher;
+        pub mod technic;
+        pub mod management;
+        pub mod trash;
+        pub mod groups;
+        pub mod watcher;
+        pub mod cache;
+        pub mod metadata;
+    }
+    pub mod modpacks {
+        pub mod modpacks;
+        pub mod errors;
+        pub mod mrpack;
+        pub mod curseforge;
+        pub mod export;
+    }
+    pub mod mods {
+        pub mod mods;
+        pub mod errors;
+        pub mod metadata;
+        pub mod profiles;
+        pub mod versions;
+        pub mod checker;
+        pub mod bisect;
+    }
+    pub mod packs {
+        pub mod packs;
+        pub mod errors;
+        pub mod formats;
+        pub mod options;
+    }
+    pub mod worlds {
+        pub mod worlds;
+        pub mod errors;
+        pub mod backups;
+        pub mod settings;
     }
     pub mod authentication { 
         pub mod auth;
         pub mod auth_structs;
     }
     pub mod java;
+    pub mod java_runtimes;
+    pub mod nbt;
 }
 pub mod configuration {
     pub mod accounts;
@@ -46,19 +93,72 @@ pub mod configuration {
 
 
 fn main() {
+    // Modpack builds get checked on build machines, which don't need a window
+    let args: Vec<String> = std::env::args().collect();
+    let smoke_test = args.iter().position(|arg| arg == "--smoke-test");
+
+    // Logs go to stderr, the smoke test prints its result to stdout.
+    // Build logs are often public, so the smoke test only logs what went wrong.
     SimpleLogger::new()
-        .with_level(log::LevelFilter::Debug)
+        .with_level(if smoke_test.is_some() { log::LevelFilter::Warn } else { log::LevelFilter::Debug })
         .env()
         .init()
         .expect("Failed to initialize logger!");
 
+    if let Some(index) = smoke_test {
+        std::process::exit(launching::smoke_test::run_cli(&args[index + 1..]));
+    }
+
     tauri::Builder::default()
+        .manage(launching::launching::RunningInstances::default())
+        .manage(minecraft::instances::watcher::InstanceWatcher::default())
+        .manage(minecraft::mods::bisect::RunningBisects::default())
         .invoke_handler(tauri::generate_handler![
             unlock_icons,
             file_exists,
             minecraft::instances::instances::get_instances,
+            minecraft::instances::yamcl::create_instance,
+            minecraft::instances::management::clone_instance,
+            minecraft::instances::management::rename_instance,
+            minecraft::instances::management::delete_instance,
+            minecraft::instances::trash::get_trash,
+            minecraft::instances::trash::restore_instance,
+            minecraft::instances::groups::set_instance_group,
+            minecraft::instances::groups::set_instance_tags,
+            minecraft::instances::groups::set_instance_favorite,
+            minecraft::mods::mods::list_mods,
+            minecraft::mods::mods::set_mod_enabled,
+            minecraft::mods::profiles::get_mod_profiles,
+            minecraft::mods::profiles::save_mod_profile,
+            minecraft::mods::profiles::delete_mod_profile,
+            minecraft::mods::profiles::apply_mod_profile,
+            minecraft::mods::checker::check_mods,
+            minecraft::mods::bisect::bisect_mods,
+            minecraft::mods::bisect::cancel_bisect,
+            launching::smoke_test::smoke_test,
+            minecraft::packs::packs::list_resource_packs,
+            minecraft::packs::packs::set_resource_packs,
+            minecraft::packs::packs::list_shader_packs,
+            minecraft::packs::packs::list_data_packs,
+            minecraft::packs::packs::import_pack,
+            minecraft::worlds::worlds::list_worlds,
+            minecraft::worlds::backups::list_backups,
+            minecraft::worlds::backups::backup_world,
+            minecraft::worlds::backups::restore_backup,
+            minecraft::worlds::backups::delete_backup,
+            minecraft::worlds::backups::get_backup_settings,
+            minecraft::worlds::backups::save_backup_settings,
+            minecraft::worlds::settings::get_game_rules,
+            minecraft::worlds::settings::edit_world,
+            minecraft::worlds::settings::list_players,
+            minecraft::worlds::settings::reset_player,
+            minecraft::modpacks::mrpack::import_mrpack,
+            minecraft::modpacks::curseforge::import_cf_pack,
+            minecraft::modpacks::export::export_instance,
             launching::launching::launch_instance,
             java::get_java_version,
+            java::get_memory_suggestion,
+            java_runtimes::install_java_runtime,
             configuration::accounts::get_selected_index,
             configuration::accounts::set_selected_index,
             configuration::accounts::remove_account,
@@ -97,6 +197,56 @@ pub async fn download_file_checked(client: &Client, checksum: Option<&String>, p
     }
 }
 
+/// Hashes a downloaded file is expected to have, each of them is optional
+#[derive(Debug, Clone, Default)]
+pub struct FileHashes {
+    pub sha1: Option<String>,
+    pub sha512: Option<String>
+}
+
+impl FileHashes {
+    pub fn matches(&self, contents: &[u8]) -> bool {
+        self.sha1.as_ref().map_or(true, |sha1| &Sha1::from(contents).digest().to_string() == sha1)
+        && self.sha512.as_ref().map_or(true, |sha512| &to_hex(&Sha512::digest(contents)) == sha512)
+    }
+}
+
+/// Like [`download_file_checked`], but tries each of `urls` until one of them returns a file matching `hashes`.
+/// Failures are returned instead of panicking, since the urls usually come from user provided files.
+pub async fn download_file_verified(client: &Client, hashes: &FileHashes, path: &Path, urls: &[String]) -> Result<(), String> {
+    if fs::read(path).map_or(false, |contents| hashes.matches(&contents)) {
+        debug!("Skipped downloading {}", path.to_string_lossy());
+        return Ok(())
+    }
+
+    let mut error = format!("No download url for {}", path.to_string_lossy());
+    for url in urls {
+        debug!("Downloading to {} from {url}", path.to_string_lossy());
+        let response = match client.get(url).send().await.and_then(|response| response.error_for_status()) {
+            Ok(response) => response,
+            Err(err) => { error = format!("Request to {url} failed: {err}"); continue }
+        };
+        let contents = match response.bytes().await {
+            Ok(contents) => contents,
+            Err(err) => { error = format!("Failed to download {url}: {err}"); continue }
+        };
+        if !hashes.matches(&contents) {
+            error = format!("The file from {url} does not match its checksum");
+            continue
+        }
+
+        if let Some(parent_path) = path.parent() {
+            create_dir_all(parent_path).map_err(|err| format!("Failed to create directories {parent_path:?}: {err}"))?;
+        }
+        return fs::write(path, &contents).map_err(|err| format!("Failed to write to {path:?}: {err}"))
+    }
+    Err(error)
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
 async fn download_file(client: &Client, path: &PathBuf, url: &String) {
     debug!("Downloading to {} from {url}", path.to_string_lossy());
     let response = client.get(url).send().await.unwrap();
@@ -128,6 +278,30 @@ pub fn maven_identifier_to_path(identifier: &str) -> String {
     format!("{path}/{raw_name}/{version_path}/{raw_name}-{version}.{extension}")
 }
 
+/// Reads the files with the paths `names` inside of the jar at `jar_path`, those the jar doesn't contain are left out.
+/// Names ending with a `/` read all files in that folder.
+pub fn read_jar_files(jar_path: &Path, names: &[&str]) -> std::io::Result<HashMap<String, Vec<u8>>> {
+    let mut archive = ZipArchive::new(fs::File::open(jar_path)?)?;
+    let paths: Vec<String> = names.iter().flat_map(|name| if name.ends_with('/') {
+        archive.file_names().filter(|path| path.starts_with(name) && !path.ends_with('/')).map(str::to_string).collect()
+    } else {
+        vec![name.to_string()]
+    }).collect();
+
+    let mut files = HashMap::new();
+    for path in paths {
+        let mut file = match archive.by_name(&path) {
+            Ok(file) => file,
+            Err(ZipError::FileNotFound) => continue,
+            Err(err) => return Err(err.into())
+        };
+        let mut contents = Vec::new();
+        file.read_to_end(&mut contents)?;
+        files.insert(path, contents);
+    }
+    Ok(files)
+}
+
 #[derive(Debug, Clone)]
 pub struct Notifier {
     notif_id: String,
@@ -146,6 +320,7 @@ pub struct Notif {
 #[serde(rename_all = "camelCase")]
 pub enum NotificationState {
     Running,
+    Warning,
     Error,
     Success
 }
@@ -181,5 +356,122 @@ pub fn get_client_jar_dir() -> PathBuf { get_data_dir().join("client_jars") }
 pub fn get_library_dir() -> PathBuf { get_data_dir().join("libraries") }
 pub fn get_assets_dir() -> PathBuf { get_data_dir().join("assets") }
 pub fn get_log4j_dir() -> PathBuf { get_data_dir().join("log4j_configs") }
+pub fn get_runtimes_dir() -> PathBuf { get_data_dir().join("runtimes") }
+pub fn get_trash_dir() -> PathBuf { get_data_dir().join("trash") }
+pub fn get_mod_icons_dir() -> PathBuf { get_data_dir().join("mod_icons") }
+pub fn get_pack_icons_dir() -> PathBuf { get_data_dir().join("pack_icons") }
+pub fn get_world_backups_dir() -> PathBuf { get_data_dir().join("world_backups") }
+
+pub fn get_forge_cache_dir() -> PathBuf { get_data_dir().join("forge_cache") }
+pub fn get_mmc_meta_dir() -> PathBuf { get_data_dir().join("mmc_meta") }
+
+#[cfg(test)]
+pub mod tests {
+    use std::{collections::HashMap, fs, io::{BufRead, BufReader, Write}, net::TcpListener, path::PathBuf, thread};
+
+    use reqwest::Client;
+    use sha1_smol::Sha1;
+    use sha2::{Digest, Sha512};
+    use tauri::async_runtime::block_on;
+
+    use super::{download_file_verified, to_hex, FileHashes};
+
+    /// Serves `files` by their path over HTTP on a free local port, other paths answer with 404. Returns the base url.
+    pub fn serve_files(files: HashMap<String, Vec<u8>>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        thread::spawn(move || {
+            for mut stream in listener.incoming().flatten() {
+                let mut reader = BufReader::new(&stream);
+                let mut request = String::new();
+                let _ = reader.read_line(&mut request);
+                // Skips the headers, which end with an empty line
+                let mut header = String::new();
+                while reader.read_line(&mut header).map_or(false, |read| read > 2) {
+                    header.clear();
+                }
+
+                let path = request.split_whitespace().nth(1).unwrap_or_default();
+                let _ = match files.get(path) {
+                    Some(body) => write!(stream, "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n", body.len())
+                        .and_then(|_| stream.write_all(body)),
+                    None => write!(stream, "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n")
+                };
+            }
+        });
+        url
+    }
+
+    /// An empty folder in the temp directory, unique to `name`
+    pub fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("yamcl-test-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    pub fn hashes_of(contents: &[u8]) -> FileHashes {
+        FileHashes {
+            sha1: Some(Sha1::from(contents).digest().to_string()),
+            sha512: Some(to_hex(&Sha512::digest(contents)))
+        }
+    }
+
+    fn fixtures() -> String {
+        serve_files(HashMap::from([
+            ("/file.jar".to_string(), b"the real file".to_vec()),
+            ("/tampered.jar".to_string(), b"something else".to_vec())
+        ]))
+    }
+
+    #[test]
+    fn downloads_matching_file() {
+        let url = fixtures();
+        let path = test_dir("download-matching").join("mods/file.jar");
+        let result = block_on(download_file_verified(&Client::new(), &hashes_of(b"the real file"), &path, &[format!("{url}/file.jar")]));
+        assert_eq!(result, Ok(()));
+        assert_eq!(fs::read(&path).unwrap(), b"the real file");
+    }
+
+    #[test]
+    fn rejects_hash_mismatch() {
+        let url = fixtures();
+        let path = test_dir("download-mismatch").join("file.jar");
+        let result = block_on(download_file_verified(&Client::new(), &hashes_of(b"the real file"), &path, &[format!("{url}/tampered.jar")]));
+        assert!(result.unwrap_err().contains("does not match its checksum"));
+        assert!(!path.exists());
+    }
 
-pub fn get_forge_cache_dir() -> PathBuf { get_data_dir().join("forge_cache") }
\ No newline at end of file
+    #[test]
+    fn falls_back_to_next_url() {
+        let url = fixtures();
+        let path = test_dir("download-fallback").join("file.jar");
+        let urls = [format!("{url}/missing.jar"), format!("{url}/tampered.jar"), format!("{url}/file.jar")];
+        let result = block_on(download_file_verified(&Client::new(), &hashes_of(b"the real file"), &path, &urls));
+        assert_eq!(result, Ok(()));
+        assert_eq!(fs::read(&path).unwrap(), b"the real file");
+    }
+
+    #[test]
+    fn fails_when_all_urls_fail() {
+        let url = fixtures();
+        let path = test_dir("download-all-fail").join("file.jar");
+        let urls = [format!("{url}/tampered.jar"), format!("{url}/missing.jar")];
+        let result = block_on(download_file_verified(&Client::new(), &hashes_of(b"the real file"), &path, &urls));
+        assert!(result.unwrap_err().contains("missing.jar"));
+        assert!(!path.exists());
+
+        let result = block_on(download_file_verified(&Client::new(), &hashes_of(b"the real file"), &path, &[]));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn keeps_existing_matching_file() {
+        let dir = test_dir("download-existing");
+        let path = dir.join("file.jar");
+        fs::write(&path, b"the real file").unwrap();
+        // Nothing is listening there, so this only succeeds without a request
+        let result = block_on(download_file_verified(&Client::new(), &hashes_of(b"the real file"), &path, &["http://127.0.0.1:9/file.jar".to_string()]));
+        assert_eq!(result, Ok(()));
+    }
+}
@@ -0,0 +1,66 @@
+use std::path::Path;
+
+use chrono::{DateTime, NaiveDateTime};
+use serde::{Serialize, Deserialize};
+use serde_json::Value;
+use tokio::fs;
+
+use super::{errors::InstanceGatherError, instances::{IResult, InstanceType}};
+
+pub const ATL_INSTANCE_FILENAME: &str = "instance.json";
+
+
+// Handling the "instance.json" file of ATLauncher
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ATLInstance {
+    /// The Minecraft version, the file extends the Mojang version manifest
+    pub id: String,
+    pub launcher: ATLLauncher
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ATLLauncher {
+    pub name: String,
+    pub pack: Option<String>,
+    pub version: Option<String>,
+    pub loader_version: Option<ATLLoaderVersion>,
+    pub last_played: Option<Value>
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ATLLoaderVersion {
+    pub version: String,
+    #[serde(rename = "type")]
+    pub typ: String
+}
+
+
+impl ATLInstance {
+    pub async fn get(instance_path: &Path) -> IResult<Self> {
+        let path = instance_path.join(ATL_INSTANCE_FILENAME);
+        let instance_file = fs::read(&path).await.map_err(
+            |err| InstanceGatherError::FileReadFailed(path.clone(), err)
+        )?;
+
+        serde_json::from_slice(&instance_file).map_err(
+            |err| InstanceGatherError::ParseFailedJson(InstanceType::ATLauncher, path, err)
+        )
+    }
+
+    /// Depending on the ATLauncher version, the last played time is either a timestamp in milliseconds or a date string
+    pub fn get_last_played(&self) -> Option<NaiveDateTime> {
+        match self.launcher.last_played.as_ref()? {
+            Value::Number(millis) => millis.as_i64()
+                .and_then(NaiveDateTime::from_timestamp_millis)
+                .filter(|time| time.timestamp() > 10),
+            Value::String(time) => DateTime::parse_from_rfc3339(time).ok().map(|time| time.naive_utc()),
+            _ => None
+        }
+    }
+
+    /// Checks whether an `instance.json` belongs to ATLauncher, since GDLauncher uses the same file name
+    pub fn is_atlauncher(contents: &Value) -> bool {
+        contents.get("launcher").is_some()
+    }
+}
@@ -0,0 +1,115 @@
+use std::{collections::HashMap, fs, path::{Path, PathBuf}, time::UNIX_EPOCH};
+
+use log::{*};
+use serde::{Serialize, Deserialize};
+
+use crate::get_data_dir;
+
+use super::{
+    errors::InstanceGatherError,
+    instances::{SimpleInstance, INSTANCE_FILES, META_FILENAME},
+    technic::{TechnicInstalledPacks, TECHNIC_VERSION_FILE}
+};
+
+const CACHE_FILENAME: &str = "instance_cache.json";
+
+
+/// The instances of the last scan, as they were parsed before being attached to their root.
+/// An entry is only used while the modification times of its instance files are the same.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ScanCache {
+    entries: HashMap<PathBuf, CachedInstance>
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedInstance {
+    stamp: InstanceStamp,
+    pub instance: SimpleInstance
+}
+
+/// Modification times of the `INSTANCE_FILES` of an instance, in milliseconds since the epoch
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InstanceStamp {
+    /// Taken before parsing, so that changes made while parsing invalidate the entry
+    files: Vec<Option<u64>>,
+    /// The file of the launcher outside of the instance folder the instance is also read from, like `installedPacks` of Technic
+    launcher_file: Option<u64>,
+    /// Taken after parsing instead, since parsing creates the metadata of new instances
+    metadata: Option<u64>
+}
+
+
+impl ScanCache {
+    pub fn get() -> Self {
+        let path = get_data_dir().join(CACHE_FILENAME);
+        let contents = match fs::read(&path) {
+            Ok(contents) => contents,
+            Err(_) => return Self::default()
+        };
+
+        serde_json::from_slice(&contents).unwrap_or_else(|err| {
+            warn!("Discarding the instance cache: {}", InstanceGatherError::ParseFailedMeta(path, err));
+            Self::default()
+        })
+    }
+
+    /// Failing to save the cache only makes the next scan slower, so errors are just logged
+    pub fn save(&self) {
+        let path = get_data_dir().join(CACHE_FILENAME);
+        if let Err(err) = fs::write(&path, serde_json::to_string(self).unwrap(/* this cannot fail */)) {
+            warn!("{}", InstanceGatherError::FileWriteFailed(path, err));
+        }
+    }
+
+    /// The cached instance at `path`, if it hasn't changed since it was cached
+    pub fn get_valid(&self, path: &Path, stamp: &InstanceStamp) -> Option<&SimpleInstance> {
+        self.entries.get(path)
+            .filter(|entry| &entry.stamp == stamp)
+            .map(|entry| &entry.instance)
+    }
+}
+
+impl FromIterator<CachedInstance> for ScanCache {
+    fn from_iter<T: IntoIterator<Item = CachedInstance>>(iter: T) -> Self {
+        Self {
+            entries: iter.into_iter().map(|entry| (entry.instance.instance_path.clone(), entry)).collect()
+        }
+    }
+}
+
+impl CachedInstance {
+    pub fn new(stamp: InstanceStamp, instance: SimpleInstance) -> Self {
+        Self { stamp, instance }
+    }
+}
+
+impl InstanceStamp {
+    /// `None` if the folder contains none of the files of a launcher, which are then not cached.
+    /// Those are profiles that only exist in the database of the Modrinth App, which are read from it on every scan instead.
+    pub fn get(instance_path: &Path) -> Option<Self> {
+        let files: Vec<Option<u64>> = INSTANCE_FILES.iter()
+            .filter(|file| **file != META_FILENAME)
+            .map(|file| get_modified(&instance_path.join(file)))
+            .collect();
+        if files.iter().all(Option::is_none) {
+            return None
+        }
+
+        let launcher_file = if instance_path.join(TECHNIC_VERSION_FILE).is_file() {
+            TechnicInstalledPacks::get_path(instance_path).and_then(|path| get_modified(&path))
+        } else {
+            None
+        };
+        Some(Self { files, launcher_file, metadata: get_modified(&instance_path.join(META_FILENAME)) })
+    }
+
+    /// Takes the time of the metadata again once the instance at `instance_path` was parsed
+    pub fn with_metadata(self, instance_path: &Path) -> Self {
+        Self { metadata: get_modified(&instance_path.join(META_FILENAME)), ..self }
+    }
+}
+
+fn get_modified(path: &Path) -> Option<u64> {
+    let modified = fs::metadata(path).and_then(|meta| meta.modified()).ok()?;
+    modified.duration_since(UNIX_EPOCH).ok().map(|time| time.as_millis() as u64)
+}
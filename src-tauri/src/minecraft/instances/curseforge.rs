@@ -5,9 +5,9 @@ use reqwest::Client;
 use serde::{Serialize, Deserialize};
 use tokio::fs;
 
-use crate::{configuration::settings::AppSettings, download_file_checked};
+use crate::download_file_checked;
 
-use super::{errors::InstanceGatherError, instances::{IResult, META_FILENAME, InstanceType}};
+use super::{errors::InstanceGatherError, metadata::InstanceMetadata, instances::{IResult, InstanceType}};
 
 
 // Handling the "minecraftinstance.json" file
@@ -55,12 +55,28 @@ impl CFInstance {
         )
     }
 
-    async fn download_icon(instance_path: &PathBuf) -> IResult<Option<String>> {
+    /// The icon saved in `meta`, downloading it into `icon_dir` first if that hasn't been tried yet
+    pub async fn get_saved_icon(instance_path: &Path, meta: &mut InstanceMetadata, icon_dir: Option<&str>) -> IResult<String> {
+        if meta.saved_icon.is_none() && icon_dir.is_some() {
+            match Self::download_icon(instance_path, icon_dir).await {
+                Ok(icon) => {
+                    // Packs without an icon use the default one, so they aren't checked again
+                    meta.saved_icon = Some(icon.unwrap_or_else(|| "default_instance.png".into()));
+                    meta.save(instance_path).await?;
+                },
+                // Tried again during the next scan
+                Err(err) => warn!("{err}")
+            }
+        }
+        Ok(meta.saved_icon.clone().unwrap_or_else(|| "default_instance.png".into()))
+    }
+
+    async fn download_icon(instance_path: &Path, icon_dir: Option<&str>) -> IResult<Option<String>> {
         let instance = Self::get(instance_path).await?;
 
-        if let Some(path) = AppSettings::get().icon_path {
-            let file = PathBuf::from_str(&path).map_err(
-                |err| InstanceGatherError::IconPathParseFailed(path, err)
+        if let Some(path) = icon_dir {
+            let file = PathBuf::from_str(path).map_err(
+                |err| InstanceGatherError::IconPathParseFailed(path.to_string(), err)
             )?.join(format!("curseforge_{}", fastrand::u32(..)));
     
             if let Some(pack) = instance.installed_modpack {
@@ -90,64 +106,3 @@ impl CFInstance {
         } else { Ok(None) }
     }
 }
-
-// Handling our metadata ("yamcl-data.json" file)
-#[derive(Clone, Debug, Serialize, Deserialize)]
-pub struct CFMetadata {
-    pub instance_id: u32,
-    pub saved_icon: String
-}
-
-impl CFMetadata {
-    pub async fn get(instance_path: &PathBuf) -> IResult<Self> {
-        let path = instance_path.join(META_FILENAME);
-
-        match fs::read(&path).await {
-            Ok(contents) => {
-                let result = match serde_json::from_slice(&contents) {
-                    Ok(parsed) => Ok(parsed),
-                    Err(err) => {
-                        warn!("{}", InstanceGatherError::ParseFailedMeta(path, err));
-                        Ok(Self::generate(instance_path).await?)
-                    },
-                };
-        
-                if result.as_ref().is_ok_and(
-                    |meta| PathBuf::from_str(&meta.saved_icon).map_or(
-                        true, |icon| !icon.exists()
-                    )
-                ) {
-                    Self::generate(instance_path).await
-                } else {
-                    result
-                }
-            },
-            Err(err) => {
-                warn!("{}", InstanceGatherError::FileReadFailed(path, err));
-                Self::generate(instance_path).await
-            }
-        }
-    }
-
-    async fn generate(instance_path: &PathBuf) -> IResult<Self> {
-        let path = instance_path.join(META_FILENAME);
-
-        let meta = CFMetadata {
-            instance_id: fastrand::u32(..),
-            saved_icon: match CFInstance::download_icon(instance_path).await {
-                Ok(Some(icon)) => icon,
-                Ok(None) => "default_instance.png".to_string(),
-                Err(err) => {
-                    warn!("{err}");
-                    "default_instance.png".to_string()
-                }
-            }
-        };
-
-        fs::write(&path, serde_json::to_string_pretty(&meta).unwrap(/* this cannot fail */)).await.map_err(
-            |err| InstanceGatherError::FileWriteFailed(path, err)
-        )?;
-
-        Ok(meta)
-    }
-}
\ No newline at end of file
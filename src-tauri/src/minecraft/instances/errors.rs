@@ -13,7 +13,7 @@ use super::instances::InstanceType;
 pub enum InstanceGatherError {
     #[error("Unknown error occured")]
     Unknown,
-    #[error("Instance directory path is unset! Head to the settings to change it.")]
+    #[error("No instance folder is enabled! Head to the settings to add one.")]
     PathUnset,
     #[error("Failed to whitelist path {0} for the asset protocol!")]
     PathUnlockFailed(String, #[source] tauri::Error),
@@ -29,11 +29,33 @@ pub enum InstanceGatherError {
     FileReadFailed(PathBuf, #[source] io::Error),
     #[error("Failed to write to file at {0:?}: {1}")]
     FileWriteFailed(PathBuf, #[source] io::Error),
+    #[error("Failed to create directory at {0:?}: {1}")]
+    DirectoryCreateFailed(PathBuf, #[source] io::Error),
+    #[error("{0:?} is not a valid instance name!")]
+    InvalidName(String),
+    #[error("Failed to copy {0:?} to {1:?}: {2}")]
+    FileCopyFailed(PathBuf, PathBuf, #[source] io::Error),
+    #[error("Failed to delete {0:?}: {1}")]
+    FileDeleteFailed(PathBuf, #[source] io::Error),
+    #[error("{0} is currently running!")]
+    InstanceRunning(String),
+    #[error("{0:?} instances do not support {1}!")]
+    Unsupported(InstanceType, String),
+    #[error("There is no instance with the id {0} in the trash!")]
+    TrashEntryNotFound(String),
+    #[error("Scanning crashed: {0}")]
+    ScanFailed(String),
+    #[error("Failed to watch {0} for changes: {1}")]
+    WatchFailed(String, #[source] notify_debouncer_mini::notify::Error),
 
     #[error("Failed to parse {0:?} instance at {1:?}: {2}")]
     ParseFailedIni(InstanceType, PathBuf, #[source] serde_ini::de::Error),
     #[error("Failed to parse {0:?} instance at {1:?}: {2}")]
     ParseFailedJson(InstanceType, PathBuf, #[source] serde_json::Error),
+    #[error("Failed to read database at {0:?}: {1}")]
+    DatabaseReadFailed(PathBuf, #[source] rusqlite::Error),
+    #[error("Failed to write to database at {0:?}: {1}")]
+    DatabaseWriteFailed(PathBuf, #[source] rusqlite::Error),
     #[error("Failed to parse json file at {0:?}: {1}")]
     ParseFailedMeta(PathBuf, #[source] serde_json::Error),
     #[error("Failed to parse last played string {0:?}: {1}")]
@@ -43,6 +65,8 @@ pub enum InstanceGatherError {
 
     #[error("Minecraft version could not be found in mmc-pack.json of {0}")]
     MinecraftNotFound(PathBuf),
+    #[error("Failed to download the component {0} from the meta server: {1}")]
+    ComponentDownloadFailed(String, String),
 }
 
 
@@ -0,0 +1,136 @@
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, NaiveDateTime};
+use serde::{Serialize, Deserialize};
+use serde_json::Value;
+use tokio::fs;
+
+use super::{errors::InstanceGatherError, instances::{IResult, InstanceType}};
+
+/// Used by the legacy GDLauncher
+pub const GDL_CONFIG_FILENAME: &str = "config.json";
+/// Used by GDLauncher Carbon
+pub const GDL_INSTANCE_FILENAME: &str = "instance.json";
+
+
+// Handling the "config.json" file of the legacy GDLauncher
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GDLConfig {
+    pub loader: GDLLoader,
+    pub last_played: Option<i64>,
+    pub background: Option<String>
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GDLLoader {
+    pub loader_type: String,
+    pub loader_version: Option<String>,
+    pub mc_version: String
+}
+
+// Handling the "instance.json" file of GDLauncher Carbon
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GDLCarbonInstance {
+    pub name: String,
+    pub icon: Option<String>,
+    pub last_played: Option<String>,
+    pub game_configuration: GDLGameConfiguration
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GDLGameConfiguration {
+    pub version: Value
+}
+
+/// The parts of both GDLauncher formats yamcl cares about
+#[derive(Debug, Clone)]
+pub struct GDLInstance {
+    pub name: String,
+    pub icon: Option<PathBuf>,
+    pub mc_version: String,
+    pub loader: Option<(String, String)>,
+    pub last_played: Option<NaiveDateTime>,
+    pub minecraft_path: PathBuf
+}
+
+
+impl GDLInstance {
+    pub async fn get(instance_path: &Path) -> IResult<Self> {
+        if instance_path.join(GDL_INSTANCE_FILENAME).is_file() {
+            Self::get_carbon(instance_path).await
+        } else {
+            Self::get_legacy(instance_path).await
+        }
+    }
+
+    async fn get_legacy(instance_path: &Path) -> IResult<Self> {
+        let path = instance_path.join(GDL_CONFIG_FILENAME);
+        let config_file = fs::read(&path).await.map_err(
+            |err| InstanceGatherError::FileReadFailed(path.clone(), err)
+        )?;
+
+        let config: GDLConfig = serde_json::from_slice(&config_file).map_err(
+            |err| InstanceGatherError::ParseFailedJson(InstanceType::GDLauncher, path, err)
+        )?;
+
+        Ok(Self {
+            name: instance_path.file_name().map_or("Unknown".into(), |name| name.to_string_lossy().to_string()),
+            icon: config.background.map(|icon| instance_path.join(icon)),
+            mc_version: config.loader.mc_version,
+            loader: config.loader.loader_version.map(|version| (config.loader.loader_type, version)),
+            last_played: config.last_played
+                .and_then(NaiveDateTime::from_timestamp_millis)
+                .filter(|time| time.timestamp() > 10),
+            minecraft_path: instance_path.to_path_buf()
+        })
+    }
+
+    async fn get_carbon(instance_path: &Path) -> IResult<Self> {
+        let path = instance_path.join(GDL_INSTANCE_FILENAME);
+        let instance_file = fs::read(&path).await.map_err(
+            |err| InstanceGatherError::FileReadFailed(path.clone(), err)
+        )?;
+
+        let instance: GDLCarbonInstance = serde_json::from_slice(&instance_file).map_err(
+            |err| InstanceGatherError::ParseFailedJson(InstanceType::GDLauncher, path.clone(), err)
+        )?;
+
+        // {"Standard": {"release": "1.20.1", "modloaders": [{"type_": "forge", "version": "47.2.0"}]}}
+        let standard = instance.game_configuration.version.get("Standard");
+        let mc_version = standard
+            .and_then(|version| version.get("release"))
+            .and_then(Value::as_str)
+            .ok_or(InstanceGatherError::MinecraftNotFound(path))?
+            .to_string();
+        let loader = standard
+            .and_then(|version| version.get("modloaders"))
+            .and_then(|loaders| loaders.get(0))
+            .and_then(|loader| Some((
+                loader.get("type_")?.as_str()?.to_string(),
+                loader.get("version")?.as_str()?.to_string()
+            )));
+
+        Ok(Self {
+            name: instance.name,
+            icon: instance.icon.map(|icon| instance_path.join(icon)),
+            mc_version,
+            loader,
+            last_played: instance.last_played
+                .and_then(|time| DateTime::parse_from_rfc3339(&time).ok())
+                .map(|time| time.naive_utc()),
+            minecraft_path: instance_path.join("instance")
+        })
+    }
+
+    /// Checks whether an `instance.json` belongs to GDLauncher Carbon, since ATLauncher uses the same file name
+    pub fn is_gdlauncher(contents: &Value) -> bool {
+        contents.get("game_configuration").is_some()
+    }
+
+    /// `config.json` is a common name, only treat it as a GDLauncher instance if it describes a loader
+    pub fn is_legacy_config(contents: &Value) -> bool {
+        contents.get("loader").and_then(|loader| loader.get("mcVersion")).is_some()
+    }
+}
@@ -0,0 +1,143 @@
+use std::{collections::{HashMap, HashSet}, path::Path};
+
+use log::{*};
+use serde::{Serialize, Deserialize};
+use tokio::fs;
+
+use super::{errors::InstanceGatherError, metadata::InstanceMetadata, instances::{IResult, InstanceType, SimpleInstance}};
+
+pub const INSTGROUPS_FILENAME: &str = "instgroups.json";
+
+
+/// Organization of an instance, stored as part of its `InstanceMetadata`
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InstanceUserData {
+    pub group: Option<String>,
+    pub tags: Vec<String>,
+    pub favorite: bool
+}
+
+// Handling the "instgroups.json" file of MultiMC/Prism, which lives in the instance directory
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InstGroups {
+    pub format_version: String,
+    pub groups: HashMap<String, InstGroup>
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct InstGroup {
+    #[serde(default)]
+    pub hidden: bool,
+    /// The folder names of the instances
+    pub instances: Vec<String>
+}
+
+
+#[tauri::command(async)]
+pub async fn set_instance_group(instance: SimpleInstance, group: Option<String>) -> IResult<()> {
+    let group = group.map(|group| group.trim().to_string()).filter(|group| !group.is_empty());
+    let mut meta = InstanceMetadata::get(&instance.instance_path).await?;
+    meta.user_data.group = group.clone();
+    meta.save(&instance.instance_path).await?;
+
+    // Prism only knows about its own instances
+    if instance.instance_type == InstanceType::MultiMC {
+        if let (Some(dir), Some(folder)) = (instance.instance_path.parent(), instance.instance_path.file_name()) {
+            let mut groups = InstGroups::get(dir).await?.unwrap_or_default();
+            groups.set_group(&folder.to_string_lossy(), group);
+            groups.save(dir).await?;
+        }
+    }
+    Ok(())
+}
+
+#[tauri::command(async)]
+pub async fn set_instance_tags(instance: SimpleInstance, tags: Vec<String>) -> IResult<()> {
+    let mut meta = InstanceMetadata::get(&instance.instance_path).await?;
+    let mut seen = HashSet::new();
+    meta.user_data.tags = tags.into_iter()
+        .map(|tag| tag.trim().to_string())
+        .filter(|tag| !tag.is_empty() && seen.insert(tag.to_string()))
+        .collect();
+    meta.save(&instance.instance_path).await
+}
+
+#[tauri::command(async)]
+pub async fn set_instance_favorite(instance: SimpleInstance, favorite: bool) -> IResult<()> {
+    let mut meta = InstanceMetadata::get(&instance.instance_path).await?;
+    meta.user_data.favorite = favorite;
+    meta.save(&instance.instance_path).await
+}
+
+
+impl Default for InstGroups {
+    fn default() -> Self {
+        Self { format_version: "1".into(), groups: HashMap::new() }
+    }
+}
+
+impl InstGroups {
+    /// Reads "instgroups.json" from `instances_dir`, `None` if there is none
+    pub async fn get(instances_dir: &Path) -> IResult<Option<Self>> {
+        let path = instances_dir.join(INSTGROUPS_FILENAME);
+        if !path.is_file() {
+            return Ok(None)
+        }
+        let contents = fs::read(&path).await.map_err(
+            |err| InstanceGatherError::FileReadFailed(path.clone(), err)
+        )?;
+
+        serde_json::from_slice(&contents).map(Some).map_err(
+            |err| InstanceGatherError::ParseFailedJson(InstanceType::MultiMC, path, err)
+        )
+    }
+
+    pub async fn save(&self, instances_dir: &Path) -> IResult<()> {
+        let path = instances_dir.join(INSTGROUPS_FILENAME);
+
+        fs::write(&path, serde_json::to_string_pretty(self).unwrap(/* this cannot fail */)).await.map_err(
+            |err| InstanceGatherError::FileWriteFailed(path, err)
+        )
+    }
+
+    pub fn get_group(&self, folder: &str) -> Option<String> {
+        self.groups.iter()
+            .find(|(_, group)| group.instances.iter().any(|instance| instance == folder))
+            .map(|(name, _)| name.to_string())
+    }
+
+    /// Moves the instance in `folder` into `group`, or out of all groups for `None`
+    pub fn set_group(&mut self, folder: &str, group: Option<String>) {
+        for existing in self.groups.values_mut() {
+            existing.instances.retain(|instance| instance != folder);
+        }
+        if let Some(group) = group {
+            self.groups.entry(group).or_default().instances.push(folder.to_string());
+        }
+    }
+
+    /// Takes over the group Prism has for `instance`, since it may have been changed there
+    pub async fn apply(&self, instance: &mut SimpleInstance) {
+        let group = match instance.instance_path.file_name() {
+            Some(folder) => self.get_group(&folder.to_string_lossy()),
+            None => return
+        };
+
+        if group != instance.user_data.group {
+            debug!("Importing group {group:?} of {}", instance.name);
+            instance.user_data.group = group.clone();
+            let result = match InstanceMetadata::get(&instance.instance_path).await {
+                Ok(mut meta) => {
+                    meta.user_data.group = group;
+                    meta.save(&instance.instance_path).await
+                },
+                Err(err) => Err(err)
+            };
+            if let Err(err) = result {
+                warn!("Failed to save the group of {}: {err}", instance.name);
+            }
+        }
+    }
+}
@@ -1,20 +1,46 @@
-use std::{path::PathBuf, cmp::Ordering};
+use std::{path::{Path, PathBuf}, cmp::Ordering, sync::Arc};
 
 use tauri::{AppHandle, Manager};
-use tokio::{fs, task::JoinSet, time::Instant};
+use tokio::{fs, time::Instant};
 
 use chrono::NaiveDateTime;
 use log::{*};
 use serde::{Deserialize, Serialize};
 
-use crate::{configuration::settings::AppSettings, minecraft::modloaders::modloaders::ModLoaders};
+use crate::{configuration::settings::{AppSettings, InstanceRoot}, minecraft::modloaders::modloaders::ModLoaders};
 
-use super::{errors::InstanceGatherError, multimc::{MMCPack, MMCConfig, MMCMetadata}, curseforge::{CFInstance, CFMetadata}};
+use super::{
+    errors::InstanceGatherError,
+    groups::{InstGroups, InstanceUserData},
+    multimc::{MMCPack, MMCConfig},
+    curseforge::CFInstance,
+    metadata::InstanceMetadata,
+    yamcl::{YamclInstance, YAMCL_INSTANCE_FILENAME},
+    modrinth::{MRDatabase, MRProfile, MODRINTH_PROFILE_FILENAME},
+    atlauncher::{ATLInstance, ATL_INSTANCE_FILENAME},
+    gdlauncher::{GDLInstance, GDL_CONFIG_FILENAME, GDL_INSTANCE_FILENAME},
+    technic::{TechnicVersion, TechnicInstalledPacks, TECHNIC_VERSION_FILE},
+    trash::Trash,
+    cache::{ScanCache, CachedInstance, InstanceStamp},
+    watcher::InstanceWatcher
+};
 
 // Instance Gather Result
 pub type IResult<T> = core::result::Result<T, InstanceGatherError>;
 
 pub const META_FILENAME: &str = "yamcl-data.json";
+/// Files (relative to the instance folder) that instances are parsed from
+pub const INSTANCE_FILES: [&str; 9] = [
+    YAMCL_INSTANCE_FILENAME,
+    META_FILENAME,
+    "minecraftinstance.json",
+    "instance.cfg",
+    "mmc-pack.json",
+    MODRINTH_PROFILE_FILENAME,
+    ATL_INSTANCE_FILENAME,
+    GDL_CONFIG_FILENAME,
+    TECHNIC_VERSION_FILE
+];
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SimpleInstance {
@@ -22,11 +48,16 @@ pub struct SimpleInstance {
     pub icon_path: String,
     pub minecraft_path: PathBuf,
     pub instance_path: PathBuf,
-    pub id: u32,
+    pub id: String,
     pub mc_version: String,
     pub modloader: ModLoader,
     pub last_played: Option<NaiveDateTime>,
-    pub instance_type: InstanceType
+    pub instance_type: InstanceType,
+    #[serde(flatten)]
+    pub user_data: InstanceUserData,
+    /// Label of the instance root this was found in
+    #[serde(default)]
+    pub root: String
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -38,67 +69,80 @@ pub struct ModLoader {
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum InstanceType {
     CurseForge,
-    MultiMC
+    MultiMC,
+    Yamcl,
+    Modrinth,
+    ATLauncher,
+    GDLauncher,
+    Technic
 }
 
 
 
+/// Result of `get_instances`, instances that could not be read don't prevent the others from being listed
+#[derive(Debug, Default, Serialize)]
+pub struct InstanceList {
+    pub instances: Vec<SimpleInstance>,
+    pub errors: Vec<BrokenInstance>
+}
+
+/// An instance folder (or a whole instance root) that could not be read
+#[derive(Debug, Serialize)]
+pub struct BrokenInstance {
+    pub path: PathBuf,
+    pub error: InstanceGatherError
+}
+
+
 #[tauri::command(async)]
-pub async fn get_instances(app_handle: AppHandle) -> IResult<Vec<SimpleInstance>> {
+pub async fn get_instances(force_rescan: Option<bool>, app_handle: AppHandle) -> IResult<InstanceList> {
     let time_start = Instant::now();
+    Trash::purge_expired();
 
-    let settings = AppSettings::get();
-    let dir = settings.instance_path.ok_or(InstanceGatherError::PathUnset)?;
-    if let Some(icon_path) = &settings.icon_path {
-        app_handle.asset_protocol_scope().allow_directory(icon_path, false).map_err(
-            |err| InstanceGatherError::PathUnlockFailed(icon_path.to_string(), err)
-        )?;
-    }
-    
-    let mut paths = fs::read_dir(&dir).await.or(Err(InstanceGatherError::DirectoryReadFailed(dir)))?;
-
-    let mut instances = Vec::new();
-
-    let mut tasks = JoinSet::new();
-
-    while let Ok(Some(path)) = paths.next_entry().await {
-        if path.file_type().await.map_err(
-            |err| InstanceGatherError::FileTypeFailed(path.path(), err)
-        )?.is_dir() {
-            tasks.spawn(async move {
-                let p = &path.path();
-                trace!("Scanning folder {p:?}");
-
-                if p.join("minecraftinstance.json").is_file() {
-                    trace!("Found minecraftinstance.json in {p:?}");
-                    Some(SimpleInstance::get_from_cf(&path.path()).await)
-                } else if p.join("instance.cfg").is_file() {
-                    trace!("Found instance.cfg in {p:?}");
-                    Some(SimpleInstance::get_from_mmc(&path.path()).await)
-                } else {
-                    info!("The folder at {p:?} does not contain a recognized minecraft instance!");
-                    None
-                }
-            });
-        }
+    let roots: Vec<InstanceRoot> = AppSettings::get().instance_roots.into_iter().filter(|root| root.enabled).collect();
+    if roots.is_empty() {
+        return Err(InstanceGatherError::PathUnset)
     }
 
-    while let Some(Ok(opt)) = tasks.join_next().await {
-        if let Some(result) = opt {
-            let mut instance = result?;
-            if instance.instance_type == InstanceType::MultiMC {
-                if let (Some(path), Some(icon)) = (&settings.icon_path, MMCConfig::check_icon(&instance.icon_path)) {
-                    instance.icon_path = format!("{path}/{icon}")
-                } else {
-                    instance.icon_path = "default_instance.png".into()
-                }
+    let cache = if force_rescan.unwrap_or(false) {
+        info!("Rescanning all instances");
+        ScanCache::default()
+    } else {
+        ScanCache::get()
+    };
+    let cache = Arc::new(cache);
+
+    let mut list = InstanceList::default();
+    let mut tasks = Vec::new();
+    for root in roots.clone() {
+        if let Some(icon_path) = &root.icon_path {
+            if let Err(err) = app_handle.asset_protocol_scope().allow_directory(icon_path, false) {
+                list.errors.push(BrokenInstance::new(icon_path.into(), InstanceGatherError::PathUnlockFailed(icon_path.to_string(), err)));
             }
-            debug!("{:?} - {} | Icon: {:?}", &instance.instance_type, &instance.name, &instance.icon_path);
-            instances.push(instance);
         }
+        tasks.push((PathBuf::from(&root.path), tokio::spawn(get_root_instances(root, cache.clone()))));
     }
 
-    instances.sort_unstable_by(|a, b| 
+    let mut cached = Vec::new();
+    for (path, task) in tasks {
+        match task.await {
+            Ok((root_list, root_cached)) => {
+                list.instances.extend(root_list.instances);
+                list.errors.extend(root_list.errors);
+                cached.extend(root_cached);
+            },
+            Err(err) => list.errors.push(BrokenInstance::new(path, InstanceGatherError::ScanFailed(err.to_string())))
+        }
+    }
+    // Instances that are gone or weren't scanned are dropped from the cache
+    ScanCache::from_iter(cached).save();
+    for instance in &list.instances {
+        if let Err(err) = instance.unlock_icon(&app_handle) {
+            warn!("{err}");
+        }
+    }
+
+    list.instances.sort_unstable_by(|a, b| 
         if let Some(l_a) = a.last_played {
             if let Some(l_b) = b.last_played {
                 l_b.cmp(&l_a)
@@ -112,14 +156,195 @@ pub async fn get_instances(app_handle: AppHandle) -> IResult<Vec<SimpleInstance>
         }
     );
 
-    info!("Finished gathering {} instances in {}s", instances.len(), &(Instant::now() - time_start).as_secs_f32().to_string()[..5]);
-    Ok(instances)
+    if let Err(err) = app_handle.state::<InstanceWatcher>().watch(roots, &list.instances, app_handle.clone()) {
+        warn!("{err}");
+    }
+
+    info!(
+        "Finished gathering {} instances ({} broken) in {}s",
+        list.instances.len(), list.errors.len(), &(Instant::now() - time_start).as_secs_f32().to_string()[..5]
+    );
+    Ok(list)
 }
 
 
+/// Gathers all instances directly inside of `root`, using `cache` for the ones that haven't changed.
+/// Also returns the new cache entries for the instances of the root.
+async fn get_root_instances(root: InstanceRoot, cache: Arc<ScanCache>) -> (InstanceList, Vec<CachedInstance>) {
+    let dir = Path::new(&root.path);
+    let mut list = InstanceList::default();
+    let mut cached = Vec::new();
+
+    let groups = InstGroups::get(dir).await.unwrap_or_else(|err| {
+        warn!("{err}");
+        None
+    });
+    let mut paths = match fs::read_dir(dir).await {
+        Ok(paths) => paths,
+        Err(_) => {
+            list.errors.push(BrokenInstance::new(dir.to_path_buf(), InstanceGatherError::DirectoryReadFailed(root.path.to_string())));
+            return (list, cached)
+        }
+    };
+
+    // Read once for the whole root instead of for every folder that isn't an instance of another launcher
+    let database = MRDatabase::get(dir).map(Arc::new);
+    let mut tasks = Vec::new();
+
+    while let Ok(Some(entry)) = paths.next_entry().await {
+        let path = entry.path();
+        match entry.file_type().await {
+            Ok(typ) if typ.is_dir() => {},
+            Ok(_) => continue,
+            Err(err) => {
+                list.errors.push(BrokenInstance::new(path.clone(), InstanceGatherError::FileTypeFailed(path, err)));
+                continue
+            }
+        }
+
+        let cache = cache.clone();
+        let icon_dir = root.icon_path.clone();
+        let database = database.clone();
+        let task_path = path.clone();
+        tasks.push((path, tokio::spawn(async move {
+            let path = task_path;
+            let stamp = InstanceStamp::get(&path);
+            if let Some(instance) = stamp.as_ref().and_then(|stamp| cache.get_valid(&path, stamp)) {
+                trace!("Using the cached instance at {path:?}");
+                return Some(Ok((instance.clone(), stamp)))
+            }
+            let result = SimpleInstance::from_path_in_root(&path, icon_dir.as_deref(), database.as_deref()).await?;
+            // The stamp from before parsing makes changes made in the meantime parse the instance again next time,
+            // only the metadata is stamped again, since parsing writes it for new instances
+            Some(result.map(|instance| (instance, stamp.map(|stamp| stamp.with_metadata(&path)))))
+        })));
+    }
+
+    for (path, task) in tasks {
+        let (mut instance, stamp) = match task.await {
+            Ok(Some(Ok(parsed))) => parsed,
+            Ok(Some(Err(err))) => {
+                list.errors.push(BrokenInstance::new(path, err));
+                continue
+            },
+            Ok(None) => continue,
+            Err(err) => {
+                list.errors.push(BrokenInstance::new(path, InstanceGatherError::ScanFailed(err.to_string())));
+                continue
+            }
+        };
+
+        if let Some(stamp) = stamp {
+            cached.push(CachedInstance::new(stamp, instance.clone()));
+        }
+        instance.attach_root(&root, groups.as_ref()).await;
+        debug!("{:?} - {} | Icon: {:?}", &instance.instance_type, &instance.name, &instance.icon_path);
+        list.instances.push(instance);
+    }
+
+    debug!("Found {} instances in {} ({})", list.instances.len(), root.label, root.path);
+    (list, cached)
+}
+
+
+impl BrokenInstance {
+    fn new(path: PathBuf, error: InstanceGatherError) -> Self {
+        warn!("Skipping {path:?}: {error}");
+        Self { path, error }
+    }
+}
+
 impl SimpleInstance {
+    /// Detects which launcher the instance at `path` belongs to and parses it.
+    /// Returns `None` if the folder does not contain a recognized instance.
+    /// `icon_dir` is the icon folder of the instance root, used for CurseForge icons.
+    pub async fn from_path(path: &Path, icon_dir: Option<&str>) -> Option<IResult<Self>> {
+        let database = path.parent().and_then(MRDatabase::get);
+        Self::from_path_in_root(path, icon_dir, database.as_ref()).await
+    }
+
+    /// Like [`SimpleInstance::from_path`], with the Modrinth App `database` of the instance root already read,
+    /// so that scanning a root doesn't open it again for every folder
+    pub async fn from_path_in_root(path: &Path, icon_dir: Option<&str>, database: Option<&MRDatabase>) -> Option<IResult<Self>> {
+        trace!("Scanning folder {path:?}");
+
+        if path.join(YAMCL_INSTANCE_FILENAME).is_file() {
+            trace!("Found {YAMCL_INSTANCE_FILENAME} in {path:?}");
+            Some(Self::get_from_yamcl(path).await)
+        } else if path.join("minecraftinstance.json").is_file() {
+            trace!("Found minecraftinstance.json in {path:?}");
+            Some(Self::get_from_cf(path, icon_dir).await)
+        } else if path.join("instance.cfg").is_file() {
+            trace!("Found instance.cfg in {path:?}");
+            Some(Self::get_from_mmc(&path.to_path_buf()).await)
+        } else if path.join(MODRINTH_PROFILE_FILENAME).is_file() {
+            trace!("Found {MODRINTH_PROFILE_FILENAME} in {path:?}");
+            Some(Self::get_from_modrinth(path, None).await)
+        } else if path.join(TECHNIC_VERSION_FILE).is_file() {
+            trace!("Found {TECHNIC_VERSION_FILE} in {path:?}");
+            Some(Self::get_from_technic(path).await)
+        } else if let Some(contents) = read_json(&path.join(ATL_INSTANCE_FILENAME)).await {
+            // ATLauncher and GDLauncher Carbon both use instance.json
+            if ATLInstance::is_atlauncher(&contents) {
+                trace!("Found ATLauncher {ATL_INSTANCE_FILENAME} in {path:?}");
+                Some(Self::get_from_atl(path).await)
+            } else if GDLInstance::is_gdlauncher(&contents) {
+                trace!("Found GDLauncher {GDL_INSTANCE_FILENAME} in {path:?}");
+                Some(Self::get_from_gdl(path).await)
+            } else {
+                info!("The instance.json at {path:?} does not belong to a known launcher!");
+                None
+            }
+        } else if read_json(&path.join(GDL_CONFIG_FILENAME)).await.map_or(false, |contents| GDLInstance::is_legacy_config(&contents)) {
+            trace!("Found GDLauncher {GDL_CONFIG_FILENAME} in {path:?}");
+            Some(Self::get_from_gdl(path).await)
+        } else {
+            // Newer Modrinth App versions only keep their profiles in the database
+            match database.filter(|database| database.get_profile(path).is_some()) {
+                Some(database) => {
+                    trace!("Found {path:?} in the Modrinth App database");
+                    Some(Self::get_from_modrinth(path, Some(database)).await)
+                },
+                None => {
+                    info!("The folder at {path:?} does not contain a recognized minecraft instance!");
+                    None
+                }
+            }
+        }
+    }
+
+    /// Tags the instance with the root it was found in and resolves what depends on the root
+    pub async fn attach_root(&mut self, root: &InstanceRoot, groups: Option<&InstGroups>) {
+        self.root = root.label.to_string();
+        if self.instance_type == InstanceType::MultiMC {
+            if let (Some(path), Some(icon)) = (&root.icon_path, MMCConfig::check_icon(&self.icon_path)) {
+                self.icon_path = format!("{path}/{icon}")
+            } else {
+                self.icon_path = "default_instance.png".into()
+            }
+            if let Some(groups) = groups {
+                groups.apply(self).await;
+            }
+        }
+    }
+
+    /// Instances of other launchers reference icons by absolute path, those need to be allowed one by one.
+    /// MultiMC and CurseForge icons live in the icon directory, which is allowed as a whole.
+    pub fn unlock_icon(&self, app_handle: &AppHandle) -> IResult<()> {
+        let unlock = match self.instance_type {
+            InstanceType::CurseForge | InstanceType::MultiMC => false,
+            _ => self.icon_path != "default_instance.png"
+        };
+        if unlock {
+            app_handle.asset_protocol_scope().allow_file(&self.icon_path).map_err(
+                |err| InstanceGatherError::PathUnlockFailed(self.icon_path.to_string(), err)
+            )?;
+        }
+        Ok(())
+    }
+
     pub async fn get_from_mmc(path: &PathBuf) -> IResult<Self> {
-        let meta = MMCMetadata::get(path).await?;
+        let meta = InstanceMetadata::get(path).await?;
         let instance_cfg = MMCConfig::get(path).await?;
         let pack_json = MMCPack::get(path).await?;
 
@@ -132,7 +357,9 @@ impl SimpleInstance {
                 path.join("minecraft")
             },
             instance_path: path.clone(),
-            id: meta.instance_id,
+            id: meta.id,
+            user_data: meta.user_data,
+            root: String::new(),
             instance_type: InstanceType::MultiMC,
             last_played: instance_cfg.last_played.and_then(|time| NaiveDateTime::from_timestamp_millis(time)),
             mc_version: pack_json.components.iter()
@@ -164,16 +391,41 @@ impl SimpleInstance {
         })
     }
 
-    pub async fn get_from_cf(path: &PathBuf) -> IResult<Self> {
-        let meta = CFMetadata::get(path).await?;
+    pub async fn get_from_yamcl(path: &Path) -> IResult<Self> {
+        let meta = InstanceMetadata::get(path).await?;
+        let instance_json = YamclInstance::get(path).await?;
+
+        Ok(SimpleInstance {
+            name: instance_json.name,
+            icon_path: instance_json.icon
+                .map(|icon| path.join(icon))
+                .filter(|icon| icon.is_file())
+                .map_or("default_instance.png".into(), |icon| icon.to_string_lossy().to_string()),
+            minecraft_path: path.join("minecraft"),
+            instance_path: path.to_path_buf(),
+            id: meta.id,
+            user_data: meta.user_data,
+            root: String::new(),
+            mc_version: instance_json.mc_version,
+            modloader: instance_json.modloader,
+            last_played: instance_json.last_played,
+            instance_type: InstanceType::Yamcl,
+        })
+    }
+
+    pub async fn get_from_cf(path: &Path, icon_dir: Option<&str>) -> IResult<Self> {
+        let mut meta = InstanceMetadata::get(path).await?;
+        let icon_path = CFInstance::get_saved_icon(path, &mut meta, icon_dir).await?;
         let instance_json = CFInstance::get(path).await?;
 
         Ok(SimpleInstance {
             name: instance_json.name,
-            icon_path: meta.saved_icon,
-            minecraft_path: path.clone(),
-            instance_path: path.clone(),
-            id: meta.instance_id,
+            icon_path,
+            minecraft_path: path.to_path_buf(),
+            instance_path: path.to_path_buf(),
+            id: meta.id,
+            user_data: meta.user_data,
+            root: String::new(),
             mc_version: instance_json.game_version,
             last_played: {
                 let time = NaiveDateTime::parse_and_remainder(&instance_json.last_played, "%Y-%m-%dT%H:%M:%S").map_err(
@@ -201,4 +453,123 @@ impl SimpleInstance {
             instance_type: InstanceType::CurseForge,
         })
     }
-}
\ No newline at end of file
+
+    /// `database` is where profiles without a `profile.json` are read from
+    pub async fn get_from_modrinth(path: &Path, database: Option<&MRDatabase>) -> IResult<Self> {
+        let meta = InstanceMetadata::get(path).await?;
+        let profile = MRProfile::get(path, database).await?;
+
+        Ok(SimpleInstance {
+            name: profile.name,
+            icon_path: get_icon_path(profile.icon.map(PathBuf::from)),
+            minecraft_path: path.to_path_buf(),
+            instance_path: path.to_path_buf(),
+            id: meta.id,
+            user_data: meta.user_data,
+            root: String::new(),
+            mc_version: profile.game_version,
+            modloader: ModLoader::from_parts(ModLoaders::from_modrinth(&profile.loader), profile.loader_version),
+            last_played: profile.last_played,
+            instance_type: InstanceType::Modrinth,
+        })
+    }
+
+    pub async fn get_from_atl(path: &Path) -> IResult<Self> {
+        let meta = InstanceMetadata::get(path).await?;
+        let instance_json = ATLInstance::get(path).await?;
+        let last_played = instance_json.get_last_played();
+        let loader = instance_json.launcher.loader_version;
+
+        Ok(SimpleInstance {
+            name: instance_json.launcher.name,
+            icon_path: get_icon_path(Some(path.join("instance.png"))),
+            minecraft_path: path.to_path_buf(),
+            instance_path: path.to_path_buf(),
+            id: meta.id,
+            user_data: meta.user_data,
+            root: String::new(),
+            mc_version: instance_json.id,
+            modloader: match loader {
+                Some(loader) => ModLoader::from_parts(ModLoaders::from_atlauncher(&loader.typ), Some(loader.version)),
+                None => ModLoader::from_parts(None, None)
+            },
+            last_played,
+            instance_type: InstanceType::ATLauncher,
+        })
+    }
+
+    pub async fn get_from_gdl(path: &Path) -> IResult<Self> {
+        let meta = InstanceMetadata::get(path).await?;
+        let instance = GDLInstance::get(path).await?;
+
+        Ok(SimpleInstance {
+            name: instance.name,
+            icon_path: get_icon_path(instance.icon),
+            minecraft_path: instance.minecraft_path,
+            instance_path: path.to_path_buf(),
+            id: meta.id,
+            user_data: meta.user_data,
+            root: String::new(),
+            mc_version: instance.mc_version,
+            modloader: match instance.loader {
+                Some((typ, version)) => ModLoader::from_parts(ModLoaders::from_gdlauncher(&typ), Some(version)),
+                None => ModLoader::from_parts(None, None)
+            },
+            last_played: instance.last_played,
+            instance_type: InstanceType::GDLauncher,
+        })
+    }
+
+    pub async fn get_from_technic(path: &Path) -> IResult<Self> {
+        let meta = InstanceMetadata::get(path).await?;
+        let version = TechnicVersion::get(path).await?;
+
+        Ok(SimpleInstance {
+            name: TechnicInstalledPacks::get_name(path).await.unwrap_or_else(
+                || path.file_name().map_or("Unknown".into(), |name| name.to_string_lossy().to_string())
+            ),
+            icon_path: get_icon_path(TechnicInstalledPacks::get_icon(path)),
+            minecraft_path: path.to_path_buf(),
+            instance_path: path.to_path_buf(),
+            id: meta.id,
+            user_data: meta.user_data,
+            root: String::new(),
+            mc_version: version.get_mc_version().ok_or(InstanceGatherError::MinecraftNotFound(path.join(TECHNIC_VERSION_FILE)))?,
+            modloader: match version.get_loader() {
+                Some((loader, loader_version)) => ModLoader::from_parts(Some(loader), Some(loader_version)),
+                None => ModLoader::from_parts(None, None)
+            },
+            // Technic does not keep track of this
+            last_played: None,
+            instance_type: InstanceType::Technic,
+        })
+    }
+}
+
+impl ModLoader {
+    /// Builds a [`ModLoader`], falling back to Vanilla if the loader is unknown
+    fn from_parts(loader: Option<ModLoaders>, version: Option<String>) -> Self {
+        match loader {
+            Some(loader) => ModLoader {
+                name: loader.to_string(),
+                typ: loader,
+                version: version.unwrap_or("Unknown Version!".into())
+            },
+            None => ModLoader {
+                name: "Vanilla".into(),
+                typ: ModLoaders::Vanilla,
+                version: "".into(),
+            }
+        }
+    }
+}
+
+/// The icon as a string, or the default icon if there is none
+fn get_icon_path(icon: Option<PathBuf>) -> String {
+    icon.filter(|icon| icon.is_file())
+        .map_or("default_instance.png".into(), |icon| icon.to_string_lossy().to_string())
+}
+
+async fn read_json(path: &Path) -> Option<serde_json::Value> {
+    serde_json::from_slice(&fs::read(path).await.ok()?).ok()
+}
@@ -0,0 +1,211 @@
+use std::{fs, path::Path};
+
+use log::{*};
+use rusqlite::Connection;
+use serde::{Serialize, Deserialize};
+use serde_json::Value;
+use tauri::{AppHandle, Manager};
+
+use crate::{configuration::settings::AppSettings, minecraft::{launching::launching::RunningInstances, mods::mods::is_mod_file}};
+
+use super::{
+    errors::InstanceGatherError,
+    instances::{IResult, InstanceType, SimpleInstance, META_FILENAME},
+    metadata::InstanceMetadata,
+    trash::{Trash, TrashEntry},
+    yamcl::{get_free_dir, YamclInstance},
+    modrinth::{MODRINTH_PROFILE_FILENAME, MODRINTH_DATABASE_FILENAME},
+    atlauncher::ATL_INSTANCE_FILENAME,
+    gdlauncher::GDL_INSTANCE_FILENAME
+};
+
+
+/// How the files of a cloned instance are created
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub enum CopyMode {
+    Copy,
+    /// Copy-on-write clones, falls back to copying on file systems without support
+    Reflink,
+    /// Shares the mods and resource packs with the original, which the game never changes.
+    /// Everything else, like worlds and options, is copied as with `Reflink`.
+    Hardlink
+}
+
+
+#[tauri::command(async)]
+pub async fn clone_instance(instance: SimpleInstance, name: String, mode: CopyMode, include_saves: bool, app_handle: AppHandle) -> IResult<SimpleInstance> {
+    ensure_stopped(&instance, &app_handle)?;
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(InstanceGatherError::InvalidName(name.to_string()))
+    }
+    if instance.instance_type == InstanceType::Modrinth && !instance.instance_path.join(MODRINTH_PROFILE_FILENAME).is_file() {
+        // The clone would not show up in the database of the Modrinth App
+        return Err(InstanceGatherError::Unsupported(InstanceType::Modrinth, "cloning".into()))
+    }
+
+    let parent = instance.instance_path.parent().unwrap_or(&instance.instance_path);
+    let target = get_free_dir(parent, name);
+    info!("Cloning {} to {target:?} ({mode:?}, saves: {include_saves})", instance.name);
+
+    let saves = instance.minecraft_path.join("saves");
+    let metadata = instance.instance_path.join(META_FILENAME);
+    let get_mode = |path: &Path| mode.for_file(path, &instance.minecraft_path);
+    copy_dir(&instance.instance_path, &target, &get_mode, &|path| path == metadata || (!include_saves && path == saves))?;
+
+    // The clone keeps the groups, tags, mod profiles and backup settings, but is a different instance
+    let mut meta = InstanceMetadata::get(&instance.instance_path).await?;
+    meta.id = uuid::Uuid::new_v4().to_string();
+    meta.legacy_id = None;
+    meta.save(&target).await?;
+
+    match set_name(&target, &instance.instance_type, name).await {
+        // These use the folder name, which already is the new name
+        Err(InstanceGatherError::Unsupported(..)) => debug!("Keeping the folder name as the name of {target:?}"),
+        result => result?
+    }
+
+    let icon_dir = AppSettings::get().get_root(&target).and_then(|root| root.icon_path.clone());
+    let clone = SimpleInstance::from_path(&target, icon_dir.as_deref()).await.ok_or(
+        InstanceGatherError::Unsupported(instance.instance_type, "cloning".into())
+    )??;
+    clone.unlock_icon(&app_handle)?;
+    Ok(clone)
+}
+
+#[tauri::command(async)]
+pub async fn rename_instance(instance: SimpleInstance, name: String, app_handle: AppHandle) -> IResult<()> {
+    ensure_stopped(&instance, &app_handle)?;
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(InstanceGatherError::InvalidName(name.to_string()))
+    }
+
+    info!("Renaming {} to {name}", instance.name);
+    set_name(&instance.instance_path, &instance.instance_type, name).await
+}
+
+#[tauri::command(async)]
+pub async fn delete_instance(instance: SimpleInstance, app_handle: AppHandle) -> IResult<TrashEntry> {
+    ensure_stopped(&instance, &app_handle)?;
+    Trash::add(&instance)
+}
+
+
+fn ensure_stopped(instance: &SimpleInstance, app_handle: &AppHandle) -> IResult<()> {
+    if app_handle.state::<RunningInstances>().contains(&instance.instance_path) {
+        Err(InstanceGatherError::InstanceRunning(instance.name.to_string()))
+    } else {
+        Ok(())
+    }
+}
+
+/// Writes `name` to the file the launcher of the instance reads its name from
+async fn set_name(instance_path: &Path, instance_type: &InstanceType, name: &str) -> IResult<()> {
+    match instance_type {
+        InstanceType::Yamcl => {
+            let mut instance = YamclInstance::get(instance_path).await?;
+            instance.name = name.to_string();
+            instance.save(instance_path).await
+        },
+        InstanceType::MultiMC => {
+            let path = instance_path.join("instance.cfg");
+            let config = fs::read_to_string(&path).map_err(|err| InstanceGatherError::FileReadFailed(path.clone(), err))?;
+            // Only the name line is touched, so everything MultiMC doesn't tell us about stays the same
+            let mut found = false;
+            let mut lines: Vec<String> = config.lines().map(|line| {
+                if line.starts_with("name=") {
+                    found = true;
+                    format!("name={name}")
+                } else {
+                    line.to_string()
+                }
+            }).collect();
+            if !found {
+                lines.push(format!("name={name}"));
+            }
+            fs::write(&path, lines.join("\n") + "\n").map_err(|err| InstanceGatherError::FileWriteFailed(path, err))
+        },
+        InstanceType::CurseForge => edit_json(&instance_path.join("minecraftinstance.json"), &["name"], name),
+        InstanceType::ATLauncher => edit_json(&instance_path.join(ATL_INSTANCE_FILENAME), &["launcher", "name"], name),
+        InstanceType::GDLauncher if instance_path.join(GDL_INSTANCE_FILENAME).is_file() => {
+            edit_json(&instance_path.join(GDL_INSTANCE_FILENAME), &["name"], name)
+        },
+        InstanceType::Modrinth if instance_path.join(MODRINTH_PROFILE_FILENAME).is_file() => {
+            edit_json(&instance_path.join(MODRINTH_PROFILE_FILENAME), &["metadata", "name"], name)
+        },
+        InstanceType::Modrinth => {
+            let db_path = instance_path.parent().and_then(Path::parent).map(|dir| dir.join(MODRINTH_DATABASE_FILENAME))
+                .ok_or(InstanceGatherError::Unsupported(InstanceType::Modrinth, "renaming".into()))?;
+            let profile = instance_path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+
+            Connection::open(&db_path)
+                .and_then(|connection| connection.execute("UPDATE profiles SET name = ?1 WHERE path = ?2", [name, &profile]))
+                .map(|_| ())
+                .map_err(|err| InstanceGatherError::DatabaseWriteFailed(db_path, err))
+        },
+        // The legacy GDLauncher and Technic name instances after their folder
+        typ => Err(InstanceGatherError::Unsupported(typ.clone(), "renaming".into()))
+    }
+}
+
+impl CopyMode {
+    /// How the file at `path` of the instance with the game files in `minecraft_path` is copied.
+    /// Only mods and resource packs are hardlinked, since the game changes everything else in place
+    /// and the name of the clone is written into the files of its launcher.
+    fn for_file(self, path: &Path, minecraft_path: &Path) -> Self {
+        match self {
+            CopyMode::Hardlink => {
+                let is_mod = path.starts_with(minecraft_path.join("mods")) && is_mod_file(path);
+                if is_mod || path.starts_with(minecraft_path.join("resourcepacks")) { CopyMode::Hardlink } else { CopyMode::Reflink }
+            },
+            mode => mode
+        }
+    }
+}
+
+/// Sets the string at `keys` in the json file at `path`, keeping everything else as it is
+fn edit_json(path: &Path, keys: &[&str], value: &str) -> IResult<()> {
+    let contents = fs::read(path).map_err(|err| InstanceGatherError::FileReadFailed(path.to_path_buf(), err))?;
+    let mut json: Value = serde_json::from_slice(&contents).map_err(
+        |err| InstanceGatherError::ParseFailedMeta(path.to_path_buf(), err)
+    )?;
+
+    let mut current = &mut json;
+    for key in keys {
+        current = &mut current[*key];
+    }
+    *current = Value::String(value.to_string());
+
+    fs::write(path, serde_json::to_string_pretty(&json).unwrap(/* this cannot fail */)).map_err(
+        |err| InstanceGatherError::FileWriteFailed(path.to_path_buf(), err)
+    )
+}
+
+/// Recursively copies `source` to `target` the way `mode` returns for each file, leaving out everything `skip` returns true for
+pub fn copy_dir(source: &Path, target: &Path, mode: &dyn Fn(&Path) -> CopyMode, skip: &dyn Fn(&Path) -> bool) -> IResult<()> {
+    fs::create_dir_all(target).map_err(|err| InstanceGatherError::DirectoryCreateFailed(target.to_path_buf(), err))?;
+
+    let entries = fs::read_dir(source).map_err(|err| InstanceGatherError::FileReadFailed(source.to_path_buf(), err))?;
+    for entry in entries {
+        let entry = entry.map_err(|err| InstanceGatherError::FileReadFailed(source.to_path_buf(), err))?;
+        let path = entry.path();
+        if skip(&path) {
+            trace!("Skipping {path:?}");
+            continue
+        }
+
+        let destination = target.join(entry.file_name());
+        if entry.file_type().map_err(|err| InstanceGatherError::FileTypeFailed(path.clone(), err))?.is_dir() {
+            copy_dir(&path, &destination, mode, skip)?;
+        } else {
+            let result = match mode(&path) {
+                CopyMode::Copy => fs::copy(&path, &destination).map(|_| ()),
+                CopyMode::Reflink => reflink_copy::reflink_or_copy(&path, &destination).map(|_| ()),
+                CopyMode::Hardlink => fs::hard_link(&path, &destination).or_else(|_| fs::copy(&path, &destination).map(|_| ()))
+            };
+            result.map_err(|err| InstanceGatherError::FileCopyFailed(path, destination, err))?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,142 @@
+use std::{collections::BTreeMap, io::ErrorKind, path::Path};
+
+use chrono::Utc;
+use log::{*};
+use serde::{Serialize, Deserialize};
+use serde_json::{Map, Value};
+use tokio::fs;
+
+use crate::minecraft::{mods::profiles::ModProfile, worlds::backups::BackupSettings};
+
+use super::{errors::InstanceGatherError, groups::InstanceUserData, instances::{IResult, META_FILENAME}};
+
+/// Version of the `InstanceMetadata` format that this version of yamcl writes
+pub const METADATA_FORMAT_VERSION: u32 = 1;
+
+
+/// Our own data about an instance, stored in "yamcl-data.json" inside of the folder of any launcher's instance:
+/// ```json
+/// {
+///   "format_version": 1,
+///   "id": "0f6c1c92-2ee1-4fe4-8bf0-71b47fbc4d4b",
+///   "legacy_id": 2891263574,
+///   "saved_icon": "/path/to/icons/curseforge_3521",
+///   "group": "Modded",
+///   "tags": ["skyblock"],
+///   "favorite": false,
+///   "mod_profiles": { "Debugging": { "enabled": ["sodium-fabric-0.5.3.jar"] } },
+///   "world_backups": { "after_exit": true, "keep_last": 5, "keep_daily": 7, "keep_weekly": 4 }
+/// }
+/// ```
+/// Files without `format_version` were written by older versions, which used a random `instance_id` number.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct InstanceMetadata {
+    pub format_version: u32,
+    /// Stays the same for the lifetime of the instance
+    pub id: String,
+    /// The `instance_id` of older versions, kept for reference
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub legacy_id: Option<u32>,
+    /// The downloaded icon of CurseForge instances, `None` if there has been no attempt yet
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub saved_icon: Option<String>,
+    #[serde(flatten)]
+    pub user_data: InstanceUserData,
+    /// Named sets of enabled mods
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub mod_profiles: BTreeMap<String, ModProfile>,
+    /// When the worlds are backed up automatically and which backups are kept
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub world_backups: Option<BackupSettings>,
+    /// Fields this version doesn't know about (e.g. written by a newer version), which are written back as they were
+    #[serde(flatten)]
+    pub extra: Map<String, Value>
+}
+
+impl InstanceMetadata {
+    /// Reads the metadata of the instance at `instance_path`, creating it if there is none.
+    /// Metadata that can not be read is backed up before it is replaced.
+    pub async fn get(instance_path: &Path) -> IResult<Self> {
+        let path = instance_path.join(META_FILENAME);
+
+        let contents = match fs::read(&path).await {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Self::create(instance_path).await,
+            Err(err) => return Err(InstanceGatherError::FileReadFailed(path, err))
+        };
+
+        match serde_json::from_slice(&contents).and_then(Self::from_value) {
+            Ok((meta, migrated)) => {
+                if migrated {
+                    info!("Migrated the metadata at {path:?} to format version {METADATA_FORMAT_VERSION}");
+                    meta.save(instance_path).await?;
+                }
+                Ok(meta)
+            },
+            Err(err) => {
+                warn!("{}", InstanceGatherError::ParseFailedMeta(path.clone(), err));
+                let backup = instance_path.join(format!("{META_FILENAME}.corrupt-{}", Utc::now().format("%Y%m%d%H%M%S")));
+                info!("Backing up the unreadable metadata to {backup:?}");
+                fs::rename(&path, &backup).await.map_err(
+                    |err| InstanceGatherError::FileCopyFailed(path, backup, err)
+                )?;
+                Self::create(instance_path).await
+            }
+        }
+    }
+
+    pub async fn save(&self, instance_path: &Path) -> IResult<()> {
+        let path = instance_path.join(META_FILENAME);
+
+        fs::write(&path, serde_json::to_string_pretty(self).unwrap(/* this cannot fail */)).await.map_err(
+            |err| InstanceGatherError::FileWriteFailed(path, err)
+        )
+    }
+
+    async fn create(instance_path: &Path) -> IResult<Self> {
+        let meta = Self {
+            format_version: METADATA_FORMAT_VERSION,
+            id: uuid::Uuid::new_v4().to_string(),
+            legacy_id: None,
+            saved_icon: None,
+            user_data: InstanceUserData::default(),
+            mod_profiles: BTreeMap::new(),
+            world_backups: None,
+            extra: Map::new()
+        };
+        meta.save(instance_path).await?;
+        Ok(meta)
+    }
+
+    /// Parses the metadata, migrating older formats first. Also returns whether a migration happened.
+    fn from_value(mut value: Value) -> serde_json::Result<(Self, bool)> {
+        let migrated = match value.as_object_mut() {
+            Some(object) if !object.contains_key("format_version") => {
+                Self::migrate_legacy(object);
+                true
+            },
+            _ => false
+        };
+
+        let meta: Self = serde_json::from_value(value)?;
+        if meta.format_version > METADATA_FORMAT_VERSION {
+            debug!("The metadata of {} was written by a newer version (format version {})", meta.id, meta.format_version);
+        }
+        Ok((meta, migrated))
+    }
+
+    /// Older versions wrote `{ "instance_id": 123, "saved_icon": "..." }` for CurseForge and
+    /// `{ "instance_id": 123 }` for all other instances, both possibly with the user data
+    fn migrate_legacy(object: &mut Map<String, Value>) {
+        let legacy_id = object.remove("instance_id");
+        object.insert("format_version".into(), METADATA_FORMAT_VERSION.into());
+        object.insert("id".into(), uuid::Uuid::new_v4().to_string().into());
+        if let Some(legacy_id) = legacy_id {
+            object.insert("legacy_id".into(), legacy_id);
+        }
+        // Icons that were never downloaded were saved as the default icon, which are tried again
+        if object.get("saved_icon").and_then(Value::as_str) == Some("default_instance.png") {
+            object.remove("saved_icon");
+        }
+    }
+}
@@ -0,0 +1,135 @@
+use std::{collections::HashMap, path::Path};
+
+use chrono::{DateTime, NaiveDateTime};
+use log::{*};
+use rusqlite::{Connection, OpenFlags};
+use serde::{Serialize, Deserialize};
+use tokio::fs;
+
+use super::{errors::InstanceGatherError, instances::{IResult, InstanceType}};
+
+pub const MODRINTH_PROFILE_FILENAME: &str = "profile.json";
+/// Newer versions of the Modrinth App keep their profiles in a database next to the `profiles` folder
+pub const MODRINTH_DATABASE_FILENAME: &str = "app.db";
+
+
+// Handling the "profile.json" file of older Modrinth App versions
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MRProfileFile {
+    pub metadata: MRProfileMetadata
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MRProfileMetadata {
+    pub name: String,
+    pub icon: Option<String>,
+    pub game_version: String,
+    pub loader: String,
+    pub loader_version: Option<MRLoaderVersion>,
+    pub last_played: Option<String>
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MRLoaderVersion {
+    pub id: String
+}
+
+/// A Modrinth App profile, either read from `profile.json` or from the app database
+#[derive(Debug, Clone)]
+pub struct MRProfile {
+    pub name: String,
+    pub icon: Option<String>,
+    pub game_version: String,
+    pub loader: String,
+    pub loader_version: Option<String>,
+    pub last_played: Option<NaiveDateTime>
+}
+
+/// The profiles in the Modrinth App database of an instance root, by the name of their folder
+#[derive(Debug, Clone, Default)]
+pub struct MRDatabase {
+    profiles: HashMap<String, MRProfile>
+}
+
+
+impl MRProfile {
+    /// Reads the profile at `instance_path` from its `profile.json`, or from `database` if it has none
+    pub async fn get(instance_path: &Path, database: Option<&MRDatabase>) -> IResult<Self> {
+        let path = instance_path.join(MODRINTH_PROFILE_FILENAME);
+        if path.is_file() {
+            Self::get_from_file(instance_path).await
+        } else {
+            database.and_then(|database| database.get_profile(instance_path)).ok_or(InstanceGatherError::FileReadFailed(
+                path,
+                std::io::Error::new(std::io::ErrorKind::NotFound, "Profile is neither in profile.json nor in the app database")
+            ))
+        }
+    }
+
+    async fn get_from_file(instance_path: &Path) -> IResult<Self> {
+        let path = instance_path.join(MODRINTH_PROFILE_FILENAME);
+        let profile_file = fs::read(&path).await.map_err(
+            |err| InstanceGatherError::FileReadFailed(path.clone(), err)
+        )?;
+
+        let profile: MRProfileFile = serde_json::from_slice(&profile_file).map_err(
+            |err| InstanceGatherError::ParseFailedJson(InstanceType::Modrinth, path, err)
+        )?;
+        let meta = profile.metadata;
+
+        Ok(Self {
+            name: meta.name,
+            icon: meta.icon,
+            game_version: meta.game_version,
+            loader: meta.loader,
+            loader_version: meta.loader_version.map(|ver| ver.id),
+            last_played: meta.last_played
+                .and_then(|time| DateTime::parse_from_rfc3339(&time).ok())
+                .map(|time| time.naive_utc())
+        })
+    }
+}
+
+impl MRDatabase {
+    /// Reads all profiles from the `app.db` that belongs to `root`, the `profiles` folder of the Modrinth App.
+    /// Returns `None` if the root has no database. A database that can't be read is treated like a missing one,
+    /// so that the other instances in the root can still be found.
+    pub fn get(root: &Path) -> Option<Self> {
+        let db_path = root.parent().map(|dir| dir.join(MODRINTH_DATABASE_FILENAME)).filter(|path| path.is_file())?;
+        match Self::read(&db_path) {
+            Ok(database) => Some(database),
+            Err(err) => {
+                warn!("{err}");
+                None
+            }
+        }
+    }
+
+    /// The profile in the folder at `instance_path`, if the database knows it
+    pub fn get_profile(&self, instance_path: &Path) -> Option<MRProfile> {
+        let name = instance_path.file_name()?.to_string_lossy();
+        self.profiles.get(name.as_ref()).cloned()
+    }
+
+    fn read(db_path: &Path) -> IResult<Self> {
+        let read_failed = |err| InstanceGatherError::DatabaseReadFailed(db_path.to_path_buf(), err);
+        let connection = Connection::open_with_flags(db_path, OpenFlags::SQLITE_OPEN_READ_ONLY).map_err(read_failed)?;
+        let mut statement = connection.prepare(
+            "SELECT path, name, icon_path, game_version, mod_loader, mod_loader_version, last_played FROM profiles"
+        ).map_err(read_failed)?;
+
+        let profiles = statement.query_map([], |row| Ok((
+            row.get::<_, String>(0)?,
+            MRProfile {
+                name: row.get(1)?,
+                icon: row.get(2)?,
+                game_version: row.get(3)?,
+                loader: row.get(4)?,
+                loader_version: row.get(5)?,
+                last_played: row.get::<_, Option<i64>>(6)?.and_then(|secs| NaiveDateTime::from_timestamp_opt(secs, 0))
+            }
+        ))).and_then(|rows| rows.collect::<Result<HashMap<_, _>, _>>()).map_err(read_failed)?;
+
+        Ok(Self { profiles })
+    }
+}
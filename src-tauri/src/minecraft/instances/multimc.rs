@@ -1,20 +1,65 @@
-use std::path::Path;
+use std::{path::Path, str::FromStr};
 
-use log::warn;
-use serde::{Serialize, Deserialize};
+use serde::{Deserialize, Deserializer};
 use tokio::fs;
 
-use super::{errors::InstanceGatherError, instances::{IResult, InstanceType, META_FILENAME}};
+use crate::minecraft::launching::overrides::LaunchOverrides;
+
+use super::{errors::InstanceGatherError, instances::{IResult, InstanceType}};
 
 
 // Handling the "instance.cfg" file
-#[derive(Debug, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
 pub struct MMCConfig {
+    #[serde(rename = "name")]
     pub name: String,
     #[serde(rename = "lastLaunchTime")]
     pub last_played: Option<i64>,
-    pub icon_key: Option<String>
+    #[serde(rename = "iconKey")]
+    pub icon_key: Option<String>,
+    #[serde(rename = "notes", default, deserialize_with = "ini_string")]
+    pub notes: Option<String>,
+
+    #[serde(default, deserialize_with = "ini_bool")]
+    pub override_java_location: bool,
+    #[serde(default, deserialize_with = "ini_string")]
+    pub java_path: Option<String>,
+
+    #[serde(default, deserialize_with = "ini_bool")]
+    pub override_memory: bool,
+    #[serde(default, deserialize_with = "ini_number")]
+    pub min_mem_alloc: Option<u32>,
+    #[serde(default, deserialize_with = "ini_number")]
+    pub max_mem_alloc: Option<u32>,
+
+    #[serde(default, deserialize_with = "ini_bool")]
+    pub override_java_args: bool,
+    #[serde(default, deserialize_with = "ini_string")]
+    pub jvm_args: Option<String>,
+
+    #[serde(default, deserialize_with = "ini_bool")]
+    pub override_window: bool,
+    #[serde(default, deserialize_with = "ini_number")]
+    pub minecraft_win_width: Option<u32>,
+    #[serde(default, deserialize_with = "ini_number")]
+    pub minecraft_win_height: Option<u32>,
+    #[serde(default, deserialize_with = "ini_bool")]
+    pub launch_maximized: bool,
+
+    #[serde(default, deserialize_with = "ini_bool")]
+    pub override_commands: bool,
+    #[serde(default, deserialize_with = "ini_string")]
+    pub pre_launch_command: Option<String>,
+    #[serde(default, deserialize_with = "ini_string")]
+    pub wrapper_command: Option<String>,
+    #[serde(default, deserialize_with = "ini_string")]
+    pub post_exit_command: Option<String>,
+
+    #[serde(default, deserialize_with = "ini_bool")]
+    pub join_server_on_launch: bool,
+    #[serde(default, deserialize_with = "ini_string")]
+    pub join_server_on_launch_address: Option<String>
 }
 
 impl MMCConfig {
@@ -28,6 +73,38 @@ impl MMCConfig {
         )
     }
 
+    /// The launch settings of this instance that are enabled through one of the `Override*` keys
+    pub fn get_overrides(&self) -> LaunchOverrides {
+        let mut overrides = LaunchOverrides::default();
+
+        if self.override_java_location {
+            overrides.java_path = self.java_path.clone();
+        }
+        if self.override_memory {
+            overrides.min_memory = self.min_mem_alloc;
+            overrides.max_memory = self.max_mem_alloc;
+        }
+        if self.override_java_args {
+            overrides.jvm_args = self.jvm_args.clone();
+        }
+        if self.override_window {
+            overrides.maximized = self.launch_maximized;
+            if let (Some(width), Some(height)) = (self.minecraft_win_width, self.minecraft_win_height) {
+                overrides.window_size = Some((width, height));
+            }
+        }
+        if self.override_commands {
+            overrides.pre_launch_command = self.pre_launch_command.clone();
+            overrides.wrapper_command = self.wrapper_command.clone();
+            overrides.post_exit_command = self.post_exit_command.clone();
+        }
+        if self.join_server_on_launch {
+            overrides.join_server = self.join_server_on_launch_address.clone();
+        }
+
+        overrides
+    }
+
     pub fn check_icon(icon_key: &str) -> Option<String> {
         let internal_icons = [
             "default", "bee", "brick", "chicken", "creeper", "diamond", "dirt", "enderman", "enderpearl", "flame", "fox", "gear", "herobrine",
@@ -47,6 +124,28 @@ impl MMCConfig {
 }
 
 
+// serde_ini hands out every value as a string, so these have to be parsed by hand.
+// Values are written by QSettings, which quotes and escapes strings containing special characters.
+fn ini_string<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<String>, D::Error> {
+    Ok(Option::<String>::deserialize(deserializer)?.and_then(|value| {
+        let value = value.trim();
+        let value = match value.strip_prefix('"').and_then(|v| v.strip_suffix('"')) {
+            Some(quoted) => quoted.replace("\\n", "\n").replace("\\\"", "\"").replace("\\\\", "\\"),
+            None => value.to_string()
+        };
+        if value.is_empty() { None } else { Some(value) }
+    }))
+}
+
+fn ini_bool<'de, D: Deserializer<'de>>(deserializer: D) -> Result<bool, D::Error> {
+    Ok(ini_string(deserializer)?.map_or(false, |value| value == "true"))
+}
+
+fn ini_number<'de, D: Deserializer<'de>, T: FromStr>(deserializer: D) -> Result<Option<T>, D::Error> {
+    Ok(ini_string(deserializer)?.and_then(|value| value.parse().ok()))
+}
+
+
 // Handling the "mmc-instance.json" file
 #[derive(Debug, Deserialize)]
 pub struct MMCPack {
@@ -76,45 +175,3 @@ impl MMCPack {
         )
     }
 }
-
-// Handling our metadata ("yamcl-data.json" file)
-#[derive(Clone, Debug, Serialize, Deserialize)]
-pub struct MMCMetadata {
-    pub instance_id: u32
-}
-
-impl MMCMetadata {
-    pub async fn get(instance_path: &Path) -> IResult<Self> {
-        let path = instance_path.join(META_FILENAME);
-
-        match fs::read(&path).await {
-            Ok(contents) => {
-                match serde_json::from_slice(&contents) {
-                    Ok(parsed) => Ok(parsed),
-                    Err(err) => {
-                        warn!("{}", InstanceGatherError::ParseFailedMeta(path, err));
-                        Ok(Self::generate(instance_path).await?)
-                    },
-                }
-            },
-            Err(err) => {
-                warn!("{}", InstanceGatherError::FileReadFailed(path, err));
-                Self::generate(instance_path).await
-            },
-        }
-    }
-
-    async fn generate(instance_path: &Path) -> IResult<Self> {
-        let path = instance_path.join(META_FILENAME);
-
-        let meta = MMCMetadata {
-            instance_id: fastrand::u32(..),
-        };
-
-        fs::write(&path, serde_json::to_string_pretty(&meta).unwrap(/* this cannot fail */)).await.map_err(
-            |err| InstanceGatherError::FileWriteFailed(path, err)
-        )?;
-
-        Ok(meta)
-    }
-}
\ No newline at end of file
@@ -0,0 +1,307 @@
+use std::{collections::HashSet, fs::File, io, iter, path::{Path, PathBuf}};
+
+use log::{*};
+use reqwest::Client;
+use serde::Deserialize;
+use serde_json::Value;
+use tokio::fs;
+use zip::{ZipArchive, ZipWriter};
+
+use crate::{download_file_verified, get_mmc_meta_dir, FileHashes, maven_identifier_to_path, minecraft::launching::mc_structs::{MCAssetIndex, MCGameArg, MCLibrary, MCLibraryDownloads, MCLibraryDownloadsArtifacts, MCLibraryDownloadsClassifiers, MCRule, MCVersionManifest}};
+
+use super::{errors::InstanceGatherError, instances::{IResult, InstanceType}, multimc::{MMCPack, MMCPackComponent}};
+
+const MMC_META_URL: &str = "https://meta.prismlauncher.org/v1";
+const MOJANG_LIBRARIES_URL: &str = "https://libraries.minecraft.net/";
+
+/// Components that yamcl resolves on its own (through the Mojang manifest or its mod loader support).
+/// They are only applied from MultiMC if the instance overrides them with a patch file.
+const NATIVE_COMPONENTS: [&str; 6] = [
+    "net.minecraft",
+    "org.lwjgl",
+    "org.lwjgl3",
+    "net.fabricmc.intermediary",
+    "net.fabricmc.fabric-loader",
+    "net.minecraftforge",
+];
+
+
+// Handling component files ("patches/<uid>.json" or from the meta server)
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MMCComponentFile {
+    pub uid: String,
+    pub version: Option<String>,
+    pub main_class: Option<String>,
+    pub minecraft_arguments: Option<String>,
+    pub asset_index: Option<MCAssetIndex>,
+    pub libraries: Option<Vec<MMCLibrary>>,
+    #[serde(rename = "+libraries")]
+    pub plus_libraries: Option<Vec<MMCLibrary>>,
+    #[serde(rename = "+jvmArgs")]
+    pub plus_jvm_args: Option<Vec<String>>,
+    #[serde(rename = "+agents")]
+    pub plus_agents: Option<Vec<MMCAgent>>,
+    #[serde(rename = "+tweakers")]
+    pub plus_tweakers: Option<Vec<String>>,
+    pub main_jar: Option<MMCLibrary>,
+    pub jar_mods: Option<Vec<MMCJarMod>>
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MMCLibrary {
+    pub name: String,
+    pub downloads: Option<MCLibraryDownloads>,
+    pub url: Option<String>,
+    pub rules: Option<Vec<MCRule>>,
+    pub natives: Option<Value>,
+    #[serde(rename = "MMC-hint")]
+    pub hint: Option<String>,
+    #[serde(rename = "MMC-filename")]
+    pub filename: Option<String>
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MMCAgent {
+    #[serde(flatten)]
+    pub library: MMCLibrary,
+    pub argument: Option<String>
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MMCJarMod {
+    #[serde(rename = "MMC-filename")]
+    pub filename: String
+}
+
+
+impl MMCPack {
+    /// Applies all components of the instance at `instance_path` to `version`, in the order of `mmc-pack.json`.
+    pub async fn apply_components(&self, instance_path: &Path, version: &mut MCVersionManifest, client: &Client) -> IResult<()> {
+        let mut jar_mods = Vec::new();
+
+        for component in &self.components {
+            if let Some(file) = MMCComponentFile::get(instance_path, component, client).await? {
+                info!("Applying component {}", file.uid);
+                file.apply_to(instance_path, version, &mut jar_mods, client).await;
+            }
+        }
+
+        if !jar_mods.is_empty() {
+            let base = version.get_game_jar(client).await;
+            let target = instance_path.join("bin").join("minecraft.jar");
+            info!("Merging {} jar mods into {target:?}", jar_mods.len());
+            merge_jar_mods(&base, &jar_mods, &target).map_err(
+                |err| InstanceGatherError::FileWriteFailed(target.clone(), err)
+            )?;
+            version.custom_jar = Some(target);
+        }
+
+        Ok(())
+    }
+}
+
+impl MMCComponentFile {
+    /// Loads the patch file of `component` if the instance has one, otherwise the version file from the meta server.
+    /// Returns `None` for components yamcl handles on its own.
+    pub async fn get(instance_path: &Path, component: &MMCPackComponent, client: &Client) -> IResult<Option<Self>> {
+        let patch = instance_path.join("patches").join(format!("{}.json", component.uid));
+
+        let path = if patch.is_file() {
+            debug!("Using patch file {patch:?}");
+            patch
+        } else if NATIVE_COMPONENTS.contains(&component.uid.as_str()) {
+            return Ok(None)
+        } else if let Some(version) = &component.version {
+            let path = get_mmc_meta_dir().join(&component.uid).join(format!("{version}.json"));
+            // Only successful responses are kept, so a failed download is tried again next time
+            let url = format!("{MMC_META_URL}/{}/{version}.json", component.uid);
+            download_file_verified(client, &FileHashes::default(), &path, &[url]).await.map_err(
+                |err| InstanceGatherError::ComponentDownloadFailed(component.uid.to_string(), err)
+            )?;
+            path
+        } else {
+            warn!("Component {} has neither a patch file nor a version, skipping it", component.uid);
+            return Ok(None)
+        };
+
+        let contents = fs::read(&path).await.map_err(
+            |err| InstanceGatherError::FileReadFailed(path.clone(), err)
+        )?;
+        serde_json::from_slice(&contents).map(Some).map_err(|err| {
+            // Error pages cached by older versions would otherwise never be downloaded again
+            if path.starts_with(get_mmc_meta_dir()) {
+                let _ = std::fs::remove_file(&path);
+            }
+            InstanceGatherError::ParseFailedJson(InstanceType::MultiMC, path, err)
+        })
+    }
+
+    /// Merges this component into `version`, following the rules MultiMC uses for its launch profile:
+    /// scalar values override earlier components, `+` lists are appended and libraries replace
+    /// earlier libraries with the same group, artifact and classifier.
+    pub async fn apply_to(self, instance_path: &Path, version: &mut MCVersionManifest, jar_mods: &mut Vec<PathBuf>, client: &Client) {
+        if let Some(main_class) = self.main_class.filter(|class| !class.is_empty()) {
+            version.main_class = main_class;
+        }
+        if let Some(args) = self.minecraft_arguments.filter(|args| !args.is_empty()) {
+            version.minecraft_arguments = Some(args);
+        }
+        if let Some(asset_index) = self.asset_index {
+            version.asset_index = asset_index;
+        }
+
+        for lib in self.libraries.into_iter().flatten().chain(self.plus_libraries.into_iter().flatten()) {
+            apply_library(version, lib.into_mc_library(instance_path));
+        }
+
+        version.extra_jvm_args.append(&mut self.plus_jvm_args.unwrap_or_default());
+
+        for agent in self.plus_agents.into_iter().flatten() {
+            let lib = agent.library.into_mc_library(instance_path);
+            lib.download_checked(client).await;
+            if let Some(path) = lib.get_paths().first() {
+                version.extra_jvm_args.push(match agent.argument {
+                    Some(arg) => format!("-javaagent:{}={arg}", path.to_string_lossy()),
+                    None => format!("-javaagent:{}", path.to_string_lossy())
+                });
+            }
+        }
+
+        for tweaker in self.plus_tweakers.into_iter().flatten() {
+            if let Some(args) = &mut version.arguments {
+                args.game.push(MCGameArg::GameArg("--tweakClass".into()));
+                args.game.push(MCGameArg::GameArg(tweaker));
+            } else {
+                let args = version.minecraft_arguments.get_or_insert_with(String::new);
+                args.push_str(&format!(" --tweakClass {tweaker}"));
+            }
+        }
+
+        if let Some(main_jar) = self.main_jar {
+            let lib = main_jar.into_mc_library(instance_path);
+            lib.download_checked(client).await;
+            version.custom_jar = lib.get_paths().into_iter().next();
+        }
+
+        for jar_mod in self.jar_mods.into_iter().flatten() {
+            jar_mods.push(instance_path.join("jarmods").join(jar_mod.filename));
+        }
+    }
+}
+
+impl MMCLibrary {
+    /// Converts the library into the format of the Mojang manifest.
+    /// Local libraries point to the `libraries` folder of the instance, libraries without
+    /// `downloads` are resolved from their maven repository `url`.
+    pub fn into_mc_library(self, instance_path: &Path) -> MCLibrary {
+        let path = maven_identifier_to_path(&self.name);
+
+        let downloads = if self.hint.as_deref() == Some("local") {
+            let file_name = self.filename.unwrap_or_else(|| path.rsplit('/').next().unwrap_or(&path).to_string());
+            MCLibraryDownloads {
+                artifact: Some(MCLibraryDownloadsArtifacts {
+                    path: instance_path.join("libraries").join(file_name).to_string_lossy().to_string(),
+                    url: String::new(),
+                    size: 0,
+                    sha1: None
+                }),
+                ..Default::default()
+            }
+        } else if let Some(downloads) = self.downloads {
+            downloads
+        } else {
+            let base_url = self.url.as_deref().unwrap_or(MOJANG_LIBRARIES_URL).trim_end_matches('/').to_string();
+            let artifact_for = |path: String| MCLibraryDownloadsArtifacts {
+                url: format!("{base_url}/{path}"),
+                path,
+                size: 0,
+                sha1: None
+            };
+
+            match self.natives.as_ref().and_then(get_native_classifier) {
+                Some(classifier) => {
+                    let native = Some(artifact_for(maven_identifier_to_path(&format!("{}:{classifier}", self.name))));
+                    MCLibraryDownloads {
+                        classifiers: Some(if cfg!(windows) {
+                            MCLibraryDownloadsClassifiers { natives_windows: native, ..Default::default() }
+                        } else if cfg!(target_os = "macos") {
+                            MCLibraryDownloadsClassifiers { natives_osx: native, ..Default::default() }
+                        } else {
+                            MCLibraryDownloadsClassifiers { natives_linux: native, ..Default::default() }
+                        }),
+                        ..Default::default()
+                    }
+                },
+                None => MCLibraryDownloads {
+                    artifact: Some(artifact_for(path)),
+                    ..Default::default()
+                }
+            }
+        };
+
+        MCLibrary {
+            downloads,
+            name: self.name,
+            rules: self.rules,
+            natives: self.natives
+        }
+    }
+}
+
+/// Reads the classifier of the current OS from a `natives` map like `{"linux": "natives-linux-${arch}"}`
+fn get_native_classifier(natives: &Value) -> Option<String> {
+    let os = if cfg!(windows) { "windows" } else if cfg!(target_os = "macos") { "osx" } else { "linux" };
+    let arch = if cfg!(target_pointer_width = "64") { "64" } else { "32" };
+
+    natives.get(os)?.as_str().map(|classifier| classifier.replace("${arch}", arch))
+}
+
+/// The part of a maven identifier that stays the same across versions: `group:artifact[:classifier]`
+fn get_library_key(name: &str) -> String {
+    let name = name.split('@').next().unwrap_or(name);
+    let parts: Vec<&str> = name.split(':').collect();
+    match parts.as_slice() {
+        [group, artifact, _, classifier, ..] => format!("{group}:{artifact}:{classifier}"),
+        [group, artifact, ..] => format!("{group}:{artifact}"),
+        _ => name.to_string()
+    }
+}
+
+fn apply_library(version: &mut MCVersionManifest, lib: MCLibrary) {
+    let key = get_library_key(&lib.name);
+    if let Some(existing) = version.libraries.iter_mut().find(|existing| get_library_key(&existing.name) == key) {
+        debug!("Replacing library {} with {}", existing.name, lib.name);
+        *existing = lib;
+    } else {
+        version.libraries.push(lib);
+    }
+}
+
+/// Builds a jar from `base` with the contents of `jar_mods` on top. Later jar mods take precedence
+/// over earlier ones, the signature files of the base jar are dropped since they would no longer match.
+fn merge_jar_mods(base: &Path, jar_mods: &[PathBuf], target: &Path) -> io::Result<()> {
+    if let Some(parent) = target.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut writer = ZipWriter::new(File::create(target)?);
+    let mut written = HashSet::new();
+
+    let jars = jar_mods.iter().rev().map(|jar| (jar.as_path(), true)).chain(iter::once((base, false)));
+    for (path, is_mod) in jars {
+        trace!("Adding {path:?} to {target:?}");
+        let mut archive = ZipArchive::new(File::open(path)?)?;
+
+        for index in 0..archive.len() {
+            let file = archive.by_index(index)?;
+            let name = file.name().to_string();
+            if file.is_dir() || (!is_mod && name.starts_with("META-INF/")) || !written.insert(name) {
+                continue;
+            }
+            writer.raw_copy_file(file)?;
+        }
+    }
+
+    writer.finish()?;
+    Ok(())
+}
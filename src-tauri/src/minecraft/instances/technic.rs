@@ -0,0 +1,109 @@
+use std::{collections::HashMap, path::{Path, PathBuf}};
+
+use log::debug;
+use serde::{Serialize, Deserialize};
+use tokio::fs;
+
+use crate::minecraft::modloaders::modloaders::ModLoaders;
+
+use super::{errors::InstanceGatherError, instances::{IResult, InstanceType}};
+
+pub const TECHNIC_VERSION_FILE: &str = "bin/version.json";
+
+
+// Handling the "bin/version.json" file, the version manifest shipped with the modpack
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TechnicVersion {
+    pub id: String,
+    pub inherits_from: Option<String>,
+    #[serde(default)]
+    pub libraries: Vec<TechnicLibrary>
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TechnicLibrary {
+    pub name: String
+}
+
+// Handling the "installedPacks" file in the Technic launcher directory
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TechnicInstalledPacks {
+    pub installed_packs: HashMap<String, TechnicInstalledPack>
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TechnicInstalledPack {
+    pub name: String,
+    pub directory: Option<String>
+}
+
+
+impl TechnicVersion {
+    pub async fn get(instance_path: &Path) -> IResult<Self> {
+        let path = instance_path.join(TECHNIC_VERSION_FILE);
+        let version_file = fs::read(&path).await.map_err(
+            |err| InstanceGatherError::FileReadFailed(path.clone(), err)
+        )?;
+
+        serde_json::from_slice(&version_file).map_err(
+            |err| InstanceGatherError::ParseFailedJson(InstanceType::Technic, path, err)
+        )
+    }
+
+    /// Finds the mod loader (and its version) through the libraries of the manifest
+    pub fn get_loader(&self) -> Option<(ModLoaders, String)> {
+        self.libraries.iter().find_map(|lib| {
+            let (loader, version) = ModLoaders::from_technic(&lib.name)?;
+            // Forge versions are prefixed (and sometimes suffixed) with the Minecraft version
+            let version = match self.get_mc_version() {
+                Some(mc_ver) => version.trim_start_matches(&format!("{mc_ver}-")).trim_end_matches(&format!("-{mc_ver}")).to_string(),
+                None => version
+            };
+            Some((loader, version))
+        })
+    }
+
+    pub fn get_mc_version(&self) -> Option<String> {
+        if let Some(parent) = &self.inherits_from {
+            return Some(parent.to_string())
+        }
+        // Old packs have no `inheritsFrom`, their id is either the Minecraft version or starts with it
+        self.libraries.iter()
+            .find(|lib| lib.name.starts_with("net.minecraftforge:forge:"))
+            .and_then(|lib| lib.name.split(':').nth(2))
+            .filter(|version| version.contains('-'))
+            .and_then(|version| version.split('-').next())
+            .or_else(|| self.id.split('-').next())
+            .map(String::from)
+    }
+}
+
+impl TechnicInstalledPacks {
+    /// Instances live in `<technic>/modpacks/<slug>`, `installedPacks` in `<technic>`
+    pub fn get_path(instance_path: &Path) -> Option<PathBuf> {
+        instance_path.parent()?.parent().map(|dir| dir.join("installedPacks"))
+    }
+
+    /// The name of the pack that is installed at `instance_path`, if Technic knows about it
+    pub async fn get_name(instance_path: &Path) -> Option<String> {
+        let path = Self::get_path(instance_path)?;
+        let folder = instance_path.file_name()?.to_string_lossy().to_string();
+
+        let packs: Self = serde_json::from_slice(&fs::read(&path).await.ok()?).map_err(
+            |err| debug!("{}", InstanceGatherError::ParseFailedJson(InstanceType::Technic, path, err))
+        ).ok()?;
+
+        packs.installed_packs.into_iter().find(|(slug, pack)| {
+            slug == &folder || pack.directory.as_ref().map_or(false, |dir| dir.replace('\\', "/").ends_with(&format!("/{folder}")))
+        }).map(|(_, pack)| pack.name)
+    }
+
+    /// Technic keeps pack icons in its asset cache
+    pub fn get_icon(instance_path: &Path) -> Option<PathBuf> {
+        let slug = instance_path.file_name()?;
+        let icon = instance_path.parent()?.parent()?.join("assets/packs").join(slug).join("icon.png");
+        if icon.is_file() { Some(icon) } else { None }
+    }
+}
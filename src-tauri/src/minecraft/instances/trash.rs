@@ -0,0 +1,144 @@
+use std::{cmp::Reverse, fs, path::{Path, PathBuf}};
+
+use chrono::{Duration, NaiveDateTime, Utc};
+use log::{*};
+use serde::{Serialize, Deserialize};
+use tauri::AppHandle;
+
+use crate::{configuration::settings::AppSettings, get_trash_dir};
+
+use super::{
+    errors::InstanceGatherError,
+    instances::{IResult, InstanceType, SimpleInstance},
+    management::{copy_dir, CopyMode},
+    yamcl::get_free_dir
+};
+
+const TRASH_ENTRY_FILENAME: &str = "yamcl-trash.json";
+
+
+/// Deleted instances are moved into the trash folder of the launcher:
+/// ```text
+/// <data>/trash/<id>/
+/// ├── yamcl-trash.json   this struct
+/// └── instance/          the instance folder as it was
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrashEntry {
+    pub id: String,
+    pub name: String,
+    pub original_path: PathBuf,
+    pub instance_type: InstanceType,
+    pub deleted: NaiveDateTime
+}
+
+pub struct Trash;
+
+
+#[tauri::command(async)]
+pub async fn get_trash() -> IResult<Vec<TrashEntry>> {
+    Trash::purge_expired();
+    Ok(Trash::get_all())
+}
+
+#[tauri::command(async)]
+pub async fn restore_instance(id: String, app_handle: AppHandle) -> IResult<SimpleInstance> {
+    let path = Trash::restore(&id)?;
+    let icon_dir = AppSettings::get().get_root(&path).and_then(|root| root.icon_path.clone());
+    let instance = SimpleInstance::from_path(&path, icon_dir.as_deref()).await.ok_or(
+        InstanceGatherError::DirectoryReadFailed(path.to_string_lossy().to_string())
+    )??;
+    instance.unlock_icon(&app_handle)?;
+    Ok(instance)
+}
+
+
+impl Trash {
+    pub fn add(instance: &SimpleInstance) -> IResult<TrashEntry> {
+        let entry = TrashEntry {
+            id: uuid::Uuid::new_v4().to_string(),
+            name: instance.name.to_string(),
+            original_path: instance.instance_path.clone(),
+            instance_type: instance.instance_type.clone(),
+            deleted: Utc::now().naive_utc()
+        };
+        let dir = get_trash_dir().join(&entry.id);
+        info!("Moving {} to the trash at {dir:?}", instance.name);
+
+        fs::create_dir_all(&dir).map_err(|err| InstanceGatherError::DirectoryCreateFailed(dir.clone(), err))?;
+        let path = dir.join(TRASH_ENTRY_FILENAME);
+        fs::write(&path, serde_json::to_string_pretty(&entry).unwrap(/* this cannot fail */)).map_err(
+            |err| InstanceGatherError::FileWriteFailed(path, err)
+        )?;
+
+        if let Err(err) = move_dir(&instance.instance_path, &dir.join("instance")) {
+            let _ = fs::remove_dir_all(&dir);
+            return Err(err)
+        }
+        Ok(entry)
+    }
+
+    pub fn get_all() -> Vec<TrashEntry> {
+        let dirs = match fs::read_dir(get_trash_dir()) {
+            Ok(dirs) => dirs,
+            Err(_) => return Vec::new()
+        };
+
+        let mut entries: Vec<TrashEntry> = dirs.filter_map(|dir| {
+            let path = dir.ok()?.path().join(TRASH_ENTRY_FILENAME);
+            let contents = fs::read(&path).map_err(|err| warn!("{}", InstanceGatherError::FileReadFailed(path.clone(), err))).ok()?;
+            serde_json::from_slice(&contents).map_err(|err| warn!("{}", InstanceGatherError::ParseFailedMeta(path, err))).ok()
+        }).collect();
+
+        entries.sort_unstable_by_key(|entry| Reverse(entry.deleted));
+        entries
+    }
+
+    /// Moves the instance back to where it was, or next to it if that folder has been taken since
+    pub fn restore(id: &str) -> IResult<PathBuf> {
+        let entry = Self::get_all().into_iter().find(|entry| entry.id == id).ok_or(
+            InstanceGatherError::TrashEntryNotFound(id.to_string())
+        )?;
+        let dir = get_trash_dir().join(&entry.id);
+
+        let target = if entry.original_path.exists() {
+            let parent = entry.original_path.parent().unwrap_or(&entry.original_path);
+            get_free_dir(parent, &entry.name)
+        } else {
+            entry.original_path
+        };
+        info!("Restoring {} to {target:?}", entry.name);
+
+        move_dir(&dir.join("instance"), &target)?;
+        fs::remove_dir_all(&dir).map_err(|err| InstanceGatherError::FileDeleteFailed(dir, err))?;
+        Ok(target)
+    }
+
+    /// Deletes everything that has been in the trash for longer than the configured retention time
+    pub fn purge_expired() {
+        let retention = Duration::days(AppSettings::get().trash_retention_days.into());
+        let now = Utc::now().naive_utc();
+
+        for entry in Self::get_all().into_iter().filter(|entry| entry.deleted + retention < now) {
+            let dir = get_trash_dir().join(&entry.id);
+            info!("Purging {} from the trash", entry.name);
+            if let Err(err) = fs::remove_dir_all(&dir) {
+                error!("{}", InstanceGatherError::FileDeleteFailed(dir, err));
+            }
+        }
+    }
+}
+
+/// Renames `source` to `target`, or copies it if they are on different file systems
+fn move_dir(source: &Path, target: &Path) -> IResult<()> {
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent).map_err(|err| InstanceGatherError::DirectoryCreateFailed(parent.to_path_buf(), err))?;
+    }
+    if fs::rename(source, target).is_ok() {
+        return Ok(())
+    }
+
+    debug!("Could not rename {source:?} to {target:?}, copying instead");
+    copy_dir(source, target, &|_| CopyMode::Copy, &|_| false)?;
+    fs::remove_dir_all(source).map_err(|err| InstanceGatherError::FileDeleteFailed(source.to_path_buf(), err))
+}
@@ -0,0 +1,177 @@
+use std::{collections::{HashMap, HashSet}, fs, path::{Path, PathBuf}, sync::{Arc, Mutex}, time::Duration};
+
+use log::{*};
+use notify_debouncer_mini::{new_debouncer, notify::{RecommendedWatcher, RecursiveMode, Watcher}, DebounceEventResult, Debouncer};
+use tauri::{AppHandle, Manager};
+
+use crate::configuration::settings::InstanceRoot;
+
+use super::{
+    errors::InstanceGatherError,
+    instances::{IResult, SimpleInstance, INSTANCE_FILES},
+    groups::{InstGroups, INSTGROUPS_FILENAME}
+};
+
+/// Changes are collected for this long before the affected instances are parsed again
+const DEBOUNCE_TIMEOUT: Duration = Duration::from_millis(750);
+
+
+/// Watches the enabled instance roots and emits `instance_added`, `instance_removed` and `instance_changed`
+/// with the affected `SimpleInstance` whenever an instance is changed on disk.
+/// Only the roots and the folders with `INSTANCE_FILES` are watched, since watching everything inside of
+/// the instances would run out of the watches the system allows.
+#[derive(Default)]
+pub struct InstanceWatcher {
+    debouncer: Mutex<Option<Debouncer<RecommendedWatcher>>>,
+    /// Instance folder -> the instance as the frontend knows it
+    known: Arc<Mutex<HashMap<PathBuf, SimpleInstance>>>
+}
+
+impl InstanceWatcher {
+    /// Replaces the watched roots with `roots`, `instances` are the instances that were just gathered from them
+    pub fn watch(&self, roots: Vec<InstanceRoot>, instances: &[SimpleInstance], app_handle: AppHandle) -> IResult<()> {
+        *self.known.lock().unwrap() = instances.iter()
+            .map(|instance| (instance.instance_path.clone(), instance.clone()))
+            .collect();
+
+        // Dropping the old debouncer stops it
+        let mut current = self.debouncer.lock().unwrap();
+        *current = None;
+
+        let known = self.known.clone();
+        let watched_roots = roots.clone();
+        let mut debouncer = new_debouncer(DEBOUNCE_TIMEOUT, move |result: DebounceEventResult| {
+            match result {
+                Ok(events) => {
+                    let changed = get_changed_instances(&watched_roots, events.iter().map(|event| event.path.as_path()), &known);
+                    if !changed.is_empty() {
+                        tauri::async_runtime::spawn(update_instances(changed, known.clone(), app_handle.clone()));
+                    }
+                },
+                Err(err) => warn!("Error while watching the instance folders: {err}")
+            }
+        }).map_err(|err| InstanceGatherError::WatchFailed("the instance folders".into(), err))?;
+
+        for root in &roots {
+            debug!("Watching {} for changes", root.path);
+            let root_path = Path::new(&root.path);
+            debouncer.watcher().watch(root_path, RecursiveMode::NonRecursive).map_err(
+                |err| InstanceGatherError::WatchFailed(root.path.to_string(), err)
+            )?;
+            // Also folders that aren't instances yet, their instance files may still be written
+            if let Ok(entries) = fs::read_dir(root_path) {
+                for path in entries.filter_map(|entry| entry.ok()).map(|entry| entry.path()).filter(|path| path.is_dir()) {
+                    watch_instance(debouncer.watcher(), &path);
+                }
+            }
+        }
+
+        *current = Some(debouncer);
+        Ok(())
+    }
+
+    /// Starts watching the folder at `path` that was added to one of the watched roots
+    fn add_instance(&self, path: &Path) {
+        if let Some(debouncer) = self.debouncer.lock().unwrap().as_mut() {
+            watch_instance(debouncer.watcher(), path);
+        }
+    }
+}
+
+
+/// Watches the folders of the instance at `path` that `INSTANCE_FILES` are in, but nothing below them.
+/// Failures are only logged, so that the other instances are still watched.
+fn watch_instance(watcher: &mut dyn Watcher, path: &Path) {
+    let folders: HashSet<PathBuf> = INSTANCE_FILES.iter()
+        .filter_map(|file| path.join(file).parent().map(Path::to_path_buf))
+        .collect();
+    for folder in folders.iter().filter(|folder| folder.is_dir()) {
+        if let Err(err) = watcher.watch(folder, RecursiveMode::NonRecursive) {
+            warn!("Failed to watch {folder:?}: {err}");
+        }
+    }
+}
+
+
+/// Maps the changed paths to the instance folders (and their roots) that need to be parsed again
+fn get_changed_instances<'a>(
+    roots: &[InstanceRoot],
+    paths: impl Iterator<Item = &'a Path>,
+    known: &Mutex<HashMap<PathBuf, SimpleInstance>>
+) -> HashMap<PathBuf, InstanceRoot> {
+    let mut changed = HashMap::new();
+
+    for path in paths {
+        let (root, relative) = match roots.iter().find_map(|root| Some((root, path.strip_prefix(&root.path).ok()?))) {
+            Some(found) => found,
+            None => continue
+        };
+        let folder = match relative.components().next() {
+            Some(folder) => Path::new(&root.path).join(folder),
+            None => continue
+        };
+
+        if relative == Path::new(INSTGROUPS_FILENAME) {
+            // The groups of all instances in the root may have changed
+            let known = known.lock().unwrap();
+            for instance_path in known.keys().filter(|instance_path| instance_path.starts_with(&root.path)) {
+                changed.insert(instance_path.clone(), root.clone());
+            }
+        } else if relative.components().count() == 1 || INSTANCE_FILES.iter().any(|file| folder.join(file) == path) {
+            trace!("{path:?} changed");
+            changed.insert(folder, root.clone());
+        }
+    }
+    changed
+}
+
+async fn update_instances(changed: HashMap<PathBuf, InstanceRoot>, known: Arc<Mutex<HashMap<PathBuf, SimpleInstance>>>, app_handle: AppHandle) {
+    let roots: HashSet<&str> = changed.values().map(|root| root.path.as_str()).collect();
+    let mut groups = HashMap::new();
+    for root in roots {
+        groups.insert(root.to_string(), InstGroups::get(Path::new(root)).await.unwrap_or_else(|err| {
+            warn!("{err}");
+            None
+        }));
+    }
+
+    for (path, root) in changed {
+        let result = if path.is_dir() {
+            // Watched before parsing, so that files written in the meantime aren't missed
+            if !known.lock().unwrap().contains_key(&path) {
+                app_handle.state::<InstanceWatcher>().add_instance(&path);
+            }
+            SimpleInstance::from_path(&path, root.icon_path.as_deref()).await
+        } else {
+            None
+        };
+
+        match result {
+            Some(Ok(mut instance)) => {
+                instance.attach_root(&root, groups.get(&root.path).and_then(Option::as_ref)).await;
+                if let Err(err) = instance.unlock_icon(&app_handle) {
+                    warn!("{err}");
+                }
+
+                let previous = known.lock().unwrap().insert(path, instance.clone());
+                let event = if previous.is_some() { "instance_changed" } else { "instance_added" };
+                debug!("{event}: {}", instance.name);
+                emit(&app_handle, event, instance);
+            },
+            Some(Err(err)) => warn!("Failed to read the changed instance at {path:?}: {err}"),
+            None => {
+                let removed = known.lock().unwrap().remove(&path);
+                if let Some(instance) = removed {
+                    debug!("instance_removed: {}", instance.name);
+                    emit(&app_handle, "instance_removed", instance);
+                }
+            }
+        }
+    }
+}
+
+fn emit(app_handle: &AppHandle, event: &str, instance: SimpleInstance) {
+    if let Err(err) = app_handle.emit_all(event, instance) {
+        error!("Failed to emit {event}: {err}");
+    }
+}
@@ -0,0 +1,194 @@
+use std::path::{Path, PathBuf};
+
+use chrono::{NaiveDateTime, Utc};
+use log::{*};
+use serde::{Serialize, Deserialize};
+use tauri::AppHandle;
+use tokio::fs;
+
+use crate::{configuration::settings::AppSettings, minecraft::modloaders::modloaders::ModLoaders};
+
+use super::{errors::InstanceGatherError, instances::{IResult, InstanceType, ModLoader, SimpleInstance}};
+
+pub const YAMCL_INSTANCE_FILENAME: &str = "yamcl-instance.json";
+pub const YAMCL_FORMAT_VERSION: u32 = 1;
+/// Folder name for instances whose name has no usable characters
+const DEFAULT_FOLDER_NAME: &str = "Instance";
+
+
+/// Handling the "yamcl-instance.json" file, which marks an instance created by yamcl itself.
+///
+/// A native instance is a folder inside of the instance directory laid out like this:
+/// ```text
+/// <instance>/
+/// ├── yamcl-instance.json   this file
+/// ├── yamcl-data.json       launcher metadata, shared with the other instance types
+/// ├── icon.png              optional, referenced by `icon`
+/// └── minecraft/            the game directory
+/// ```
+/// `yamcl-instance.json` looks like this (timestamps are UTC, `icon` is relative to the instance folder):
+/// ```json
+/// {
+///   "format_version": 1,
+///   "name": "My Instance",
+///   "mc_version": "1.20.1",
+///   "modloader": { "name": "Fabric", "typ": "Fabric", "version": "0.15.3" },
+///   "icon": "icon.png",
+///   "created": "2024-01-01T12:00:00",
+///   "last_played": null
+/// }
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct YamclInstance {
+    pub format_version: u32,
+    pub name: String,
+    pub mc_version: String,
+    pub modloader: ModLoader,
+    pub icon: Option<String>,
+    pub created: NaiveDateTime,
+    pub last_played: Option<NaiveDateTime>
+}
+
+#[tauri::command(async)]
+pub async fn create_instance(
+    name: String,
+    mc_version: String,
+    loader: ModLoaders,
+    loader_version: Option<String>,
+    icon: Option<String>,
+    app_handle: AppHandle
+) -> IResult<SimpleInstance> {
+    let dir = AppSettings::get().get_primary_root().ok_or(InstanceGatherError::PathUnset)?.path.to_string();
+    let path = YamclInstance::create(Path::new(&dir), &name, &mc_version, loader, loader_version, icon).await?;
+
+    let instance = SimpleInstance::get_from_yamcl(&path).await?;
+    instance.unlock_icon(&app_handle)?;
+    Ok(instance)
+}
+
+
+impl YamclInstance {
+    pub async fn get(instance_path: &Path) -> IResult<Self> {
+        let path = instance_path.join(YAMCL_INSTANCE_FILENAME);
+        let instance_file = fs::read(&path).await.map_err(
+            |err| InstanceGatherError::FileReadFailed(path.clone(), err)
+        )?;
+
+        serde_json::from_slice(&instance_file).map_err(
+            |err| InstanceGatherError::ParseFailedJson(InstanceType::Yamcl, path, err)
+        )
+    }
+
+    pub async fn save(&self, instance_path: &Path) -> IResult<()> {
+        let path = instance_path.join(YAMCL_INSTANCE_FILENAME);
+
+        fs::write(&path, serde_json::to_string_pretty(self).unwrap(/* this cannot fail */)).await.map_err(
+            |err| InstanceGatherError::FileWriteFailed(path, err)
+        )
+    }
+
+    /// Creates a new instance folder inside of `instances_dir` and returns its path
+    pub async fn create(
+        instances_dir: &Path,
+        name: &str,
+        mc_version: &str,
+        loader: ModLoaders,
+        loader_version: Option<String>,
+        icon: Option<String>
+    ) -> IResult<PathBuf> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(InstanceGatherError::InvalidName(name.to_string()))
+        }
+
+        let path = get_free_dir(instances_dir, name);
+        info!("Creating instance {name} at {path:?}");
+
+        fs::create_dir_all(path.join("minecraft")).await.map_err(
+            |err| InstanceGatherError::DirectoryCreateFailed(path.clone(), err)
+        )?;
+
+        let icon = if let Some(source) = icon.map(PathBuf::from).filter(|icon| icon.is_file()) {
+            let file_name = format!("icon.{}", source.extension().map_or("png".into(), |ext| ext.to_string_lossy()));
+            fs::copy(&source, path.join(&file_name)).await.map_err(
+                |err| InstanceGatherError::FileWriteFailed(path.join(&file_name), err)
+            )?;
+            Some(file_name)
+        } else { None };
+
+        let instance = YamclInstance {
+            format_version: YAMCL_FORMAT_VERSION,
+            name: name.to_string(),
+            mc_version: mc_version.to_string(),
+            modloader: match loader {
+                ModLoaders::Vanilla => ModLoader {
+                    name: "Vanilla".into(),
+                    typ: ModLoaders::Vanilla,
+                    version: "".into(),
+                },
+                loader => ModLoader {
+                    name: loader.to_string(),
+                    typ: loader,
+                    version: loader_version.unwrap_or_default()
+                }
+            },
+            icon,
+            created: Utc::now().naive_utc(),
+            last_played: None
+        };
+        instance.save(&path).await?;
+
+        Ok(path)
+    }
+
+    /// Sets the last played time of the instance at `instance_path` to now
+    pub async fn update_last_played(instance_path: &Path) -> IResult<()> {
+        let mut instance = Self::get(instance_path).await?;
+        instance.last_played = Some(Utc::now().naive_utc());
+        instance.save(instance_path).await
+    }
+}
+
+/// Turns `name` into a folder name that doesn't exist in `instances_dir` yet.
+/// Leading and trailing spaces and dots are dropped, Windows can't handle them at the end of a folder name.
+pub fn get_free_dir(instances_dir: &Path, name: &str) -> PathBuf {
+    let folder_name: String = name.chars().map(|c| {
+        if c.is_alphanumeric() || " -_.".contains(c) { c } else { '_' }
+    }).collect();
+    let folder_name = match folder_name.trim_matches(|c| c == '.' || c == ' ') {
+        "" => DEFAULT_FOLDER_NAME,
+        trimmed => trimmed
+    };
+
+    let mut path = instances_dir.join(folder_name);
+    let mut i = 2;
+    while path.exists() {
+        path = instances_dir.join(format!("{folder_name} ({i})"));
+        i += 1;
+    }
+    path
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::tests::test_dir;
+
+    use super::*;
+
+    #[test]
+    fn finds_free_folder_names() {
+        let dir = test_dir("free-dir");
+        assert_eq!(get_free_dir(&dir, "My Pack: 1.20/Fabric"), dir.join("My Pack_ 1.20_Fabric"));
+        assert_eq!(get_free_dir(&dir, " ..pack.. "), dir.join("pack"));
+        assert_eq!(get_free_dir(&dir, ""), dir.join("Instance"));
+        assert_eq!(get_free_dir(&dir, " . .."), dir.join("Instance"));
+
+        fs::create_dir(dir.join("Instance")).unwrap();
+        fs::create_dir(dir.join("Instance (2)")).unwrap();
+        assert_eq!(get_free_dir(&dir, "..."), dir.join("Instance (3)"));
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
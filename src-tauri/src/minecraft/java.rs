@@ -1,11 +1,16 @@
-use std::process::Command;
+use std::{process::Command, fs};
 
+use chrono::{DateTime, FixedOffset};
 use log::{*};
 use serde::{Deserialize, Serialize};
+use thiserror::Error;
 
-use super::launching::mc_structs::MCVersionDetails;
+use super::{launching::mc_structs::MCVersionDetails, instances::instances::SimpleInstance, modloaders::modloaders::ModLoaders};
 
-#[derive(Debug, Serialize, Deserialize)]
+/// The largest heap a 32-bit JVM can reliably reserve, in MiB
+const MAX_32BIT_HEAP: u32 = 1536;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct JavaDetails {
     pub path: String,
     pub label: String,
@@ -16,12 +21,50 @@ pub struct JavaDetails {
     pub args: String
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct JavaMCRange {
     min: Option<MCVersionDetails>,
     max: Option<MCVersionDetails>
 }
 
+impl JavaMCRange {
+    pub fn new(min: MCVersionDetails, max: MCVersionDetails) -> Self {
+        Self { min: Some(min), max: Some(max) }
+    }
+
+    /// Whether `version` was released within this range, `false` if the range is not set
+    pub fn contains(&self, version: &MCVersionDetails) -> bool {
+        match (&self.min, &self.max) {
+            (Some(min), Some(max)) => {
+                let release = get_release_time(version);
+                get_release_time(min) <= release && release <= get_release_time(max)
+            },
+            _ => false
+        }
+    }
+}
+
+fn get_release_time(version: &MCVersionDetails) -> Option<DateTime<FixedOffset>> {
+    DateTime::parse_from_rfc3339(&version.release_time).ok()
+}
+
+#[derive(Debug, Clone, Serialize, Error)]
+pub enum MemoryWarning {
+    #[error("Min. memory ({xms} MiB) is larger than max. memory ({xmx} MiB), using {xmx} MiB for both.")]
+    MinAboveMax { xms: u32, xmx: u32 },
+    #[error("Max. memory ({xmx} MiB) is larger than the system memory ({total} MiB), limiting it to {total} MiB.")]
+    AboveSystemMemory { xmx: u32, total: u32 },
+    #[error("A 32-bit Java cannot use {xmx} MiB of memory, limiting it to {limit} MiB.")]
+    Above32BitLimit { xmx: u32, limit: u32 },
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MemorySuggestion {
+    pub xmx: u32,
+    pub xms: u32,
+    pub mod_count: u32
+}
+
 #[tauri::command(async)]
 pub fn get_java_version(path: String, args: String) -> Result<String, String> {
     info!("Getting Java version for: {} using args: {}", path, args);
@@ -42,8 +85,95 @@ pub fn get_java_version(path: String, args: String) -> Result<String, String> {
     }
 }
 
+#[tauri::command(async)]
+pub fn get_memory_suggestion(instance: SimpleInstance, java: JavaDetails) -> MemorySuggestion {
+    java.suggest_memory(&instance)
+}
+
 impl JavaDetails {
+    /// The Java from the settings that is meant for `version`, like the frontend picks it
+    pub fn find_for_version(java_settings: &[Self], version: &MCVersionDetails) -> Option<Self> {
+        java_settings.iter().find(|java| java.minecraft_versions.contains(version)).cloned()
+    }
+
     pub fn get_args(&self) -> String {
         format!("-Xmx{}M -Xms{}M {}", self.xmx, self.xms, self.args)
     }
+
+    /// Runs the JVM to find out whether it is a 32 or 64-bit build
+    pub fn get_bitness(&self) -> Option<u8> {
+        let output = Command::new(&self.path).arg("-XshowSettings:properties").arg("-version").output().ok()?;
+        let stderr = String::from_utf8_lossy(&output.stderr);
+
+        stderr.lines()
+            .find_map(|line| line.trim().strip_prefix("sun.arch.data.model = "))
+            .and_then(|model| model.trim().parse().ok())
+            .or_else(|| if stderr.contains("64-Bit") { Some(64) } else { None })
+    }
+
+    /// Corrects memory values that would keep the JVM from starting and returns what had to be changed
+    pub fn validate_memory(&mut self) -> Vec<MemoryWarning> {
+        let mut warnings = Vec::new();
+
+        if self.get_bitness() == Some(32) && self.xmx > MAX_32BIT_HEAP {
+            warnings.push(MemoryWarning::Above32BitLimit { xmx: self.xmx, limit: MAX_32BIT_HEAP });
+            self.xmx = MAX_32BIT_HEAP;
+        }
+        if let Some(total) = get_total_memory() {
+            if self.xmx > total {
+                warnings.push(MemoryWarning::AboveSystemMemory { xmx: self.xmx, total });
+                self.xmx = total;
+            }
+        }
+        if self.xms > self.xmx {
+            warnings.push(MemoryWarning::MinAboveMax { xms: self.xms, xmx: self.xmx });
+            self.xms = self.xmx;
+        }
+
+        for warning in &warnings {
+            warn!("{warning}");
+        }
+        warnings
+    }
+
+    /// Suggests memory values for `instance` based on its mod loader and the amount of mods installed
+    pub fn suggest_memory(&self, instance: &SimpleInstance) -> MemorySuggestion {
+        let mod_count = fs::read_dir(instance.minecraft_path.join("mods")).map_or(0, |entries| {
+            entries.filter_map(Result::ok)
+                .filter(|entry| entry.file_name().to_string_lossy().ends_with(".jar"))
+                .count() as u32
+        });
+
+        let (base, per_mod) = match instance.modloader.typ {
+            ModLoaders::Vanilla => (2048, 0),
+            ModLoaders::Fabric | ModLoaders::Quilt => (2048, 24),
+            _ => (3072, 40)
+        };
+        // Round up to the next 512 MiB
+        let mut xmx = (base + mod_count * per_mod + 511) / 512 * 512;
+
+        if let Some(total) = get_total_memory() {
+            xmx = xmx.min(total / 4 * 3);
+        }
+        if self.get_bitness() == Some(32) {
+            xmx = xmx.min(MAX_32BIT_HEAP);
+        }
+
+        MemorySuggestion { xmx, xms: (xmx / 2).min(2048), mod_count }
+    }
+}
+
+/// Returns the total physical memory of the system in MiB, if it can be determined
+pub fn get_total_memory() -> Option<u32> {
+    if cfg!(target_os = "linux") {
+        let meminfo = fs::read_to_string("/proc/meminfo").ok()?;
+        let kib: u64 = meminfo.lines()
+            .find_map(|line| line.strip_prefix("MemTotal:"))?
+            .trim()
+            .trim_end_matches("kB")
+            .trim()
+            .parse()
+            .ok()?;
+        Some((kib / 1024) as u32)
+    } else { None }
 }
@@ -0,0 +1,318 @@
+use std::{collections::HashMap, fs, path::{Path, PathBuf}};
+
+use log::{*};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::{configuration::settings::AppSettings, download_file_verified, get_runtimes_dir, FileHashes, Notif, NotificationState, Notifier};
+
+use super::{instances::instances::SimpleInstance, java::{JavaDetails, JavaMCRange}, launching::mc_structs::{MCDownload, MCVersionDetails, MCVersionList}};
+
+const RUNTIME_INDEX_URL: &str = "https://launchermeta.mojang.com/v1/products/java-runtime/2ec0cc96c44e5a76b9c8b7c39df7210883d12871/all.json";
+const RUNTIME_MARKER_FILENAME: &str = ".yamcl-runtime";
+
+
+/// platform -> component -> available builds
+type RuntimeIndex = HashMap<String, HashMap<String, Vec<RuntimeEntry>>>;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RuntimeEntry {
+    pub manifest: MCDownload,
+    pub version: RuntimeVersion
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RuntimeVersion {
+    pub name: String,
+    pub released: String
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RuntimeManifest {
+    pub files: HashMap<String, RuntimeFile>
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum RuntimeFile {
+    File {
+        #[serde(default)]
+        executable: bool,
+        downloads: RuntimeFileDownloads
+    },
+    Directory,
+    Link {
+        target: String
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RuntimeFileDownloads {
+    pub raw: MCDownload,
+    pub lzma: Option<MCDownload>
+}
+
+
+/// Downloads the Java runtime that Mojang ships for the Minecraft version of `instance` (if needed)
+/// and registers it in the Java settings for all Minecraft versions that use it.
+#[tauri::command(async)]
+pub async fn install_java_runtime(instance: SimpleInstance, app_handle: AppHandle) -> Result<JavaDetails, String> {
+    let client = Client::new();
+    let notifier = Notifier::new("java_runtime_status", app_handle);
+    let mc_version = &instance.mc_version;
+
+    let versions = MCVersionList::get(&client)
+        .await
+        .ok_or("Could not get the Minecraft version list!".to_string())?
+        .versions;
+    let index = versions.iter()
+        .position(|version| &version.id == mc_version)
+        .ok_or(format!("Could not find Minecraft version {mc_version}!"))?;
+    let version = versions[index]
+        .get_manifest(&client)
+        .await
+        .ok_or("Could not get Minecraft version manifest!".to_string())?;
+
+    let component = version.java_version.component;
+    info!("Minecraft {mc_version} requires the Java runtime {component}");
+
+    let result = match JavaRuntime::install(&component, &client, &notifier).await {
+        Ok(runtime_version) => {
+            let range = JavaRuntime::get_version_range(&versions, index, &component, &client).await;
+            Ok(JavaRuntime::register(&component, &runtime_version, range, &instance))
+        },
+        Err(err) => Err(err)
+    };
+    match &result {
+        Ok(java) => notifier.notify(&format!("Installed Java runtime {} ({})", component, java.version), NotificationState::Success),
+        Err(err) => notifier.notify(err, NotificationState::Error)
+    }
+    result
+}
+
+
+pub struct JavaRuntime;
+
+impl JavaRuntime {
+    /// Returns the platform key Mojang uses in the runtime index for the current system
+    pub fn get_platform() -> Option<&'static str> {
+        if cfg!(target_os = "linux") {
+            if cfg!(target_arch = "x86_64") { Some("linux") }
+            else if cfg!(target_arch = "x86") { Some("linux-i386") }
+            else { None }
+        } else if cfg!(target_os = "macos") {
+            if cfg!(target_arch = "aarch64") { Some("mac-os-arm64") }
+            else { Some("mac-os") }
+        } else if cfg!(target_os = "windows") {
+            if cfg!(target_arch = "x86_64") { Some("windows-x64") }
+            else if cfg!(target_arch = "x86") { Some("windows-x86") }
+            else if cfg!(target_arch = "aarch64") { Some("windows-arm64") }
+            else { None }
+        } else { None }
+    }
+
+    pub fn get_dir(component: &str) -> PathBuf {
+        get_runtimes_dir().join(component)
+    }
+
+    /// The path of the java binary inside of an installed runtime
+    pub fn get_java_path(component: &str) -> PathBuf {
+        let dir = Self::get_dir(component);
+        if cfg!(target_os = "macos") {
+            dir.join("jre.bundle/Contents/Home/bin/java")
+        } else if cfg!(windows) {
+            dir.join("bin/javaw.exe")
+        } else {
+            dir.join("bin/java")
+        }
+    }
+
+    pub async fn get_entry(component: &str, client: &Client) -> Result<RuntimeEntry, String> {
+        let platform = Self::get_platform().ok_or("Mojang does not provide Java runtimes for this platform!".to_string())?;
+
+        let mut index: RuntimeIndex = client.get(RUNTIME_INDEX_URL)
+            .send()
+            .await
+            .map_err(|err| format!("Failed to request the Java runtime index: {err}"))?
+            .json()
+            .await
+            .map_err(|err| format!("Failed to parse the Java runtime index: {err}"))?;
+
+        index.remove(platform)
+            .and_then(|mut components| components.remove(component))
+            .and_then(|entries| entries.into_iter().next())
+            .ok_or(format!("The Java runtime {component} is not available for {platform}!"))
+    }
+
+    /// Installs the runtime `component` into the runtimes directory and returns its version.
+    /// An installed runtime is only updated if the runtime index can be reached, so it also works offline.
+    pub async fn install(component: &str, client: &Client, notifier: &Notifier) -> Result<String, String> {
+        let dir = Self::get_dir(component);
+        let marker = dir.join(RUNTIME_MARKER_FILENAME);
+        let installed = Self::read_marker(&marker);
+
+        let entry = match (Self::get_entry(component, client).await, &installed) {
+            (Ok(entry), _) => entry,
+            (Err(err), Some((_, version))) => {
+                warn!("Could not check the Java runtime {component} for updates: {err}");
+                return Ok(version.to_string())
+            },
+            (Err(err), None) => return Err(err)
+        };
+
+        if installed.as_ref().map_or(false, |(sha1, _)| sha1 == &entry.manifest.sha1) {
+            debug!("Java runtime {component} is already up to date");
+        } else {
+            info!("Downloading Java runtime {component} ({})...", entry.version.name);
+            let manifest: RuntimeManifest = client.get(&entry.manifest.url)
+                .send()
+                .await
+                .map_err(|err| format!("Failed to request the manifest of {component}: {err}"))?
+                .json()
+                .await
+                .map_err(|err| format!("Failed to parse the manifest of {component}: {err}"))?;
+
+            fs::create_dir_all(&dir).map_err(|err| format!("Failed to create directory {dir:?}: {err}"))?;
+            Self::download_files(&dir, manifest, client, notifier).await?;
+
+            fs::write(&marker, format!("{}\n{}", entry.manifest.sha1, entry.version.name)).map_err(
+                |err| format!("Failed to write {marker:?}: {err}")
+            )?;
+        }
+
+        Ok(entry.version.name)
+    }
+
+    /// The manifest checksum and version of an installed runtime.
+    /// Runtimes installed by older versions only have the checksum in their marker.
+    fn read_marker(marker: &Path) -> Option<(String, String)> {
+        let contents = fs::read_to_string(marker).ok()?;
+        let mut lines = contents.lines().map(str::trim);
+        let sha1 = lines.next().filter(|sha1| !sha1.is_empty())?.to_string();
+        Some((sha1, lines.next().unwrap_or_default().to_string()))
+    }
+
+    /// The oldest and newest of `versions` that use the runtime `component`, found by searching outwards from
+    /// `versions[index]`, which uses it. `versions` is ordered newest first, like the version list.
+    /// Versions that use the same runtime were released one after another, so only a few manifests are needed.
+    pub async fn get_version_range(versions: &[MCVersionDetails], index: usize, component: &str, client: &Client) -> JavaMCRange {
+        // The newest version that uses the runtime
+        let (mut newer, mut older) = (0, index);
+        while newer < older {
+            let middle = (newer + older) / 2;
+            if Self::is_used_by(&versions[middle], component, client).await { older = middle } else { newer = middle + 1 }
+        }
+        let max = newer;
+
+        // The oldest version that uses the runtime
+        let (mut newer, mut older) = (index, versions.len() - 1);
+        while newer < older {
+            let middle = (newer + older + 1) / 2;
+            if Self::is_used_by(&versions[middle], component, client).await { newer = middle } else { older = middle - 1 }
+        }
+        let min = older;
+
+        debug!("Java runtime {component} is used from Minecraft {} to {}", versions[min].id, versions[max].id);
+        JavaMCRange::new(versions[min].clone(), versions[max].clone())
+    }
+
+    async fn is_used_by(version: &MCVersionDetails, component: &str, client: &Client) -> bool {
+        version.get_manifest(client).await.map_or(false, |manifest| manifest.java_version.component == component)
+    }
+
+    async fn download_files(dir: &Path, manifest: RuntimeManifest, client: &Client, notifier: &Notifier) -> Result<(), String> {
+        let mut files: Vec<(String, RuntimeFile)> = manifest.files.into_iter().collect();
+        // Directories have to exist before anything is put into them, links are created once their targets exist
+        files.sort_by_key(|(path, file)| (match file {
+            RuntimeFile::Directory => 0,
+            RuntimeFile::File { .. } => 1,
+            RuntimeFile::Link { .. } => 2,
+        }, path.to_string()));
+
+        let total = files.len() as u32;
+        for (i, (path, file)) in files.iter().enumerate() {
+            let full_path = dir.join(path);
+            match file {
+                RuntimeFile::Directory => fs::create_dir_all(&full_path).map_err(
+                    |err| format!("Failed to create directory {full_path:?}: {err}")
+                )?,
+                RuntimeFile::File { executable, downloads } => {
+                    notifier.notify_status(Notif::new(&format!("Downloading {path}"), i as u32, total, NotificationState::Running));
+                    let hashes = FileHashes { sha1: Some(downloads.raw.sha1.to_string()), sha512: None };
+                    download_file_verified(client, &hashes, &full_path, &[downloads.raw.url.to_string()]).await?;
+                    if *executable {
+                        set_executable(&full_path)?;
+                    }
+                },
+                RuntimeFile::Link { target } => create_link(&full_path, target)?
+            }
+        }
+        Ok(())
+    }
+
+    /// Adds the runtime to the Java settings for the Minecraft versions in `range`, with the memory suggested for `instance`.
+    /// An entry with the same path is updated instead, keeping its memory settings.
+    pub fn register(component: &str, version: &str, range: JavaMCRange, instance: &SimpleInstance) -> JavaDetails {
+        let path = Self::get_java_path(component).to_string_lossy().to_string();
+        let mut settings = AppSettings::get();
+
+        if let Some(existing) = settings.java_settings.iter_mut().find(|java| java.path == path) {
+            if !version.is_empty() {
+                existing.version = version.to_string();
+            }
+            existing.minecraft_versions = range;
+            let java = existing.clone();
+            settings.set();
+            return java
+        }
+
+        let mut java = JavaDetails {
+            path,
+            label: format!("Mojang {component}"),
+            version: version.to_string(),
+            minecraft_versions: range,
+            xmx: 0,
+            xms: 0,
+            args: String::new()
+        };
+        let memory = java.suggest_memory(instance);
+        java.xmx = memory.xmx;
+        java.xms = memory.xms;
+
+        info!("Registering Java runtime {component} at {}", java.path);
+        settings.java_settings.push(java.clone());
+        settings.set();
+        java
+    }
+}
+
+#[cfg(unix)]
+fn set_executable(path: &Path) -> Result<(), String> {
+    use std::os::unix::fs::PermissionsExt;
+    fs::set_permissions(path, fs::Permissions::from_mode(0o755)).map_err(
+        |err| format!("Failed to mark {path:?} as executable: {err}")
+    )
+}
+
+#[cfg(not(unix))]
+fn set_executable(_path: &Path) -> Result<(), String> { Ok(()) }
+
+#[cfg(unix)]
+fn create_link(path: &Path, target: &str) -> Result<(), String> {
+    if path.symlink_metadata().is_ok() {
+        fs::remove_file(path).map_err(|err| format!("Failed to replace link {path:?}: {err}"))?;
+    }
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|err| format!("Failed to create directory {parent:?}: {err}"))?;
+    }
+    std::os::unix::fs::symlink(target, path).map_err(
+        |err| format!("Failed to link {path:?} to {target}: {err}")
+    )
+}
+
+#[cfg(not(unix))]
+fn create_link(path: &Path, target: &str) -> Result<(), String> {
+    warn!("Skipping link {path:?} -> {target}, links are not supported on this platform");
+    Ok(())
+}
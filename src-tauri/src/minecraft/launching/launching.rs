@@ -1,12 +1,16 @@
-use std::{process::Command, path::PathBuf};
+use std::{process::Command, path::{Path, PathBuf}, sync::Mutex};
 
 use log::{*};
 use reqwest::Client;
-use tauri::AppHandle;
+use tauri::{AppHandle, Manager};
 
-use crate::{minecraft::{launching::mc_structs::MCVersionManifest, instances::instances::SimpleInstance, java::JavaDetails, authentication::auth_structs::MCAccount}, NotificationState, get_library_dir, get_classpath_separator, configuration::accounts::get_active_account, Notifier};
+use crate::{minecraft::{launching::mc_structs::MCVersionManifest, instances::{instances::{SimpleInstance, InstanceType}, yamcl::YamclInstance, multimc::MMCPack}, java::JavaDetails, authentication::auth_structs::MCAccount, mods::checker, worlds::backups::{self, BackupTrigger}}, NotificationState, get_library_dir, get_classpath_separator, configuration::accounts::get_active_account, Notifier};
 
-use super::mc_structs::MCVersionDetails;
+use super::{mc_structs::MCVersionDetails, overrides::LaunchOverrides};
+
+/// Instance paths of all games that are currently running, managed by tauri
+#[derive(Debug, Default)]
+pub struct RunningInstances(Mutex<Vec<PathBuf>>);
 
 #[derive(Debug)]
 struct Args {
@@ -15,32 +19,58 @@ struct Args {
     main_class: String
 }
 
+/// Game arguments whose values are secret
+const SECRET_ARGS: [&str; 2] = ["--accessToken", "--session"];
+
 #[tauri::command(async)]
-pub async fn launch_instance(instance: SimpleInstance, java: JavaDetails, app_handle: AppHandle) -> Result<(), String> {
+pub async fn launch_instance(instance: SimpleInstance, mut java: JavaDetails, app_handle: AppHandle) -> Result<(), String> {
     let SimpleInstance { minecraft_path, id, mc_version, .. } = instance.clone();
-    let notifier = Notifier::new(&format!("{id}_status"), app_handle);
+    let notifier = Notifier::new(&format!("{id}_status"), app_handle.clone());
+    let running = app_handle.state::<RunningInstances>();
     info!("Launching: {minecraft_path:?}, Version: {mc_version}, id: {id}");
 
-    let args = get_arguments(&instance, &java).await?;
-    let additional_args = java.get_args();
+    let overrides = LaunchOverrides::get(&instance).await?;
+    overrides.apply_to_java(&mut java);
+
+    for warning in java.validate_memory() {
+        notifier.notify(&warning.to_string(), NotificationState::Warning);
+    }
+
+    if !checker::confirm_launch(&instance, &java, &app_handle).await {
+        info!("Launch of {} cancelled because of mod issues", instance.name);
+        notifier.notify("Launch cancelled because of mod issues.", NotificationState::Error);
+        return Ok(())
+    }
+
+    let mut command = get_command(&instance, &java, &overrides).await?;
+
+    backups::auto_backup(&instance, BackupTrigger::BeforeLaunch, &notifier).await;
+
+    if let Some(command) = &overrides.pre_launch_command {
+        LaunchOverrides::run_command(command, &instance, &java)?;
+    }
 
-    debug!("Args: {:#?}\nCustom Args: {}", args, additional_args);
     info!("Launching NOW!");
 
-    let mut process = Command::new(java.path)
-    .current_dir(&minecraft_path)
-    .args(additional_args.split_whitespace())
-    .args(args.jvm)
-    .arg(args.main_class)
-    .args(args.game)
+    let mut process = command
     .spawn()
     .map_err(|err| format!("Failed to run Minecraft command: {err}"))?;
 
     notifier.notify("Instance launched successfully!", NotificationState::Success);
+    running.set(&instance.instance_path, true);
+
+    if instance.instance_type == InstanceType::Yamcl {
+        if let Err(err) = YamclInstance::update_last_played(&instance.instance_path).await {
+            warn!("Failed to update the last played time: {err}");
+        }
+    }
 
     let exit_status = process.wait().expect("Failed to wait on Java process! How did this happen?");
+    running.set(&instance.instance_path, false);
     info!("Exited with status: {}", exit_status);
 
+    backups::auto_backup(&instance, BackupTrigger::AfterExit, &notifier).await;
+
     if exit_status.success() {
         info!("{minecraft_path:?} exited successfully.");
         notifier.notify("Instance exited successfully.", NotificationState::Success);
@@ -49,10 +79,68 @@ pub async fn launch_instance(instance: SimpleInstance, java: JavaDetails, app_ha
         notifier.notify(&format!("Instance crashed with code {}", exit_status.code().unwrap_or(323)), NotificationState::Error);
     }
 
+    if let Some(command) = &overrides.post_exit_command {
+        LaunchOverrides::run_command(command, &instance, &java)?;
+    }
+
     Ok(())
 }
 
-async fn get_arguments(instance: &SimpleInstance, java: &JavaDetails) -> Result<Args, String> {
+impl RunningInstances {
+    /// Whether the game of the instance at `instance_path` is currently running
+    pub fn contains(&self, instance_path: &Path) -> bool {
+        self.0.lock().unwrap().iter().any(|path| path == instance_path)
+    }
+
+    pub fn set(&self, instance_path: &Path, running: bool) {
+        let mut instances = self.0.lock().unwrap();
+        if running {
+            instances.push(instance_path.to_path_buf());
+        } else if let Some(index) = instances.iter().position(|path| path == instance_path) {
+            instances.remove(index);
+        }
+    }
+}
+
+impl Args {
+    /// A copy with the values of `SECRET_ARGS` hidden, for logging
+    fn redacted(&self) -> Self {
+        let mut game = self.game.clone();
+        for i in 1..game.len() {
+            if SECRET_ARGS.contains(&game[i - 1].as_str()) {
+                game[i] = "<redacted>".into();
+            }
+        }
+        Self { jvm: self.jvm.clone(), game, main_class: self.main_class.to_string() }
+    }
+}
+
+/// The command that starts the game of `instance`, including the wrapper command of its `overrides`
+pub async fn get_command(instance: &SimpleInstance, java: &JavaDetails, overrides: &LaunchOverrides) -> Result<Command, String> {
+    let args = get_arguments(instance, java, overrides).await?;
+    let additional_args = java.get_args();
+
+    debug!("Args: {:#?}\nCustom Args: {}", args.redacted(), additional_args);
+
+    let mut command = match overrides.wrapper_command.as_ref().map(|wrapper| wrapper.split_whitespace()) {
+        Some(mut wrapper) => {
+            let mut cmd = Command::new(wrapper.next().unwrap_or(&java.path));
+            cmd.args(wrapper).arg(&java.path);
+            cmd
+        },
+        None => Command::new(&java.path)
+    };
+
+    command
+    .current_dir(&instance.minecraft_path)
+    .args(additional_args.split_whitespace())
+    .args(args.jvm)
+    .arg(args.main_class)
+    .args(args.game);
+    Ok(command)
+}
+
+async fn get_arguments(instance: &SimpleInstance, java: &JavaDetails, overrides: &LaunchOverrides) -> Result<Args, String> {
     let client = Client::new();
 
     let loader = instance.modloader.typ;
@@ -82,6 +170,16 @@ async fn get_arguments(instance: &SimpleInstance, java: &JavaDetails) -> Result<
         version.merge_with(mf)
     }
 
+    if instance.instance_type == InstanceType::MultiMC {
+        info!("Applying MultiMC components...");
+        MMCPack::get(&instance.instance_path)
+            .await
+            .map_err(|err| err.to_string())?
+            .apply_components(&instance.instance_path, &mut version, &client)
+            .await
+            .map_err(|err| err.to_string())?;
+    }
+
     info!("Finished getting manifest.");
 
     loader.prepare_launch(&instance.mc_version, &instance.modloader.version, &client, &java.path).await;
@@ -97,12 +195,23 @@ async fn get_arguments(instance: &SimpleInstance, java: &JavaDetails) -> Result<
             account,
             version,
             &instance.minecraft_path,
+            overrides,
             &client
         ).await
     )
 }
 
-async fn parse_arguments(args_struct: Args, account: MCAccount, version: MCVersionManifest, minecraft_path: &PathBuf, client: &Client) -> Args {
+async fn parse_arguments(args_struct: Args, account: MCAccount, version: MCVersionManifest, minecraft_path: &PathBuf, overrides: &LaunchOverrides, client: &Client) -> Args {
+    let (width, height) = overrides.window_size.unwrap_or((1200, 800));
+    let mut game_args = args_struct.game;
+
+    // Versions without the `arguments` format have no resolution placeholders
+    if version.arguments.is_none() && overrides.window_size.is_some() && !overrides.maximized {
+        game_args.extend(["--width".into(), width.to_string(), "--height".into(), height.to_string()]);
+    }
+    // Added once the placeholders are replaced, so the manifest's own Quick Play arguments can be removed
+    let server_args = overrides.get_server_args(version.supports_quick_play());
+
     let replacements = vec![
         ("${auth_player_name}", account.mc_profile.name),
         ("${auth_uuid}", account.mc_profile.id),
@@ -121,20 +230,22 @@ async fn parse_arguments(args_struct: Args, account: MCAccount, version: MCVersi
         ("${launcher_version}", "323".to_string()),
         ("${game_directory}", minecraft_path.to_string_lossy().to_string()),
         ("${user_type}", "msa".to_string()),
-        ("${resolution_width}", 1200.to_string()),
-        ("${resolution_height}", 800.to_string()),
+        ("${resolution_width}", width.to_string()),
+        ("${resolution_height}", height.to_string()),
 
         // Forge specifics
         ("${classpath_separator}", get_classpath_separator()),
         ("${library_directory}", get_library_dir().to_string_lossy().to_string())
     ];
 
+    // Quick Play arguments the launcher doesn't fill in, with their placeholders
     let to_remove = [
         "quickPlay",
+        "${quick_play",
         "--demo"
     ];
 
-    let args_final: (Vec<String>, Vec<String>) = [args_struct.jvm, args_struct.game].map(|args| {
+    let mut args_final: (Vec<String>, Vec<String>) = [args_struct.jvm, game_args].map(|args| {
         args.into_iter().map(|mut arg| {
             for replacement in &replacements {
                 arg = arg.replace(replacement.0, &replacement.1)
@@ -144,6 +255,7 @@ async fn parse_arguments(args_struct: Args, account: MCAccount, version: MCVersi
             !to_remove.iter().any(|remover| arg.contains(remover))
         }).collect()
     }).into();
+    args_final.1.extend(server_args);
 
     Args {
         jvm: args_final.0,
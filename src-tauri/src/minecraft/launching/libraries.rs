@@ -1,5 +1,6 @@
 use std::path::PathBuf;
 
+use log::warn;
 use reqwest::Client;
 
 use crate::{download_file_checked, get_library_dir};
@@ -37,6 +38,13 @@ impl MCLibrary {
     pub async fn download_checked(&self, client: &Client) {
         let lib_dir = get_library_dir();
         for download in self.get_downloads() {
+            if download.url.is_empty() {
+                // Local libraries (e.g. from MultiMC instances) can't be downloaded
+                if !lib_dir.join(&download.path).is_file() {
+                    warn!("Library {} is missing and has no download url!", self.name);
+                }
+                continue;
+            }
             download_file_checked(
                 client,
                 download.sha1.as_ref(),
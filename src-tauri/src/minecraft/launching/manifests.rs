@@ -44,6 +44,16 @@ impl MCVersionDetails {
 }
 
 impl MCVersionManifest {
+    /// Whether the game can join a server on its own through `--quickPlayMultiplayer`, which replaced `--server` in 1.20
+    pub fn supports_quick_play(&self) -> bool {
+        self.arguments.as_ref().map_or(false, |args| args.game.iter().any(|arg| match arg {
+            MCGameArg::GameRule(rule) => rule.rules.iter().any(|rule| {
+                rule.features.as_ref().map_or(false, |features| features.is_quick_play_multiplayer.is_some())
+            }),
+            MCGameArg::GameArg(_) => false
+        }))
+    }
+
     pub async fn get_jvm_args(&self, client: &Client) -> Vec<String> {
         let mut final_args: Vec<String> = Vec::new();
 
@@ -71,6 +81,8 @@ impl MCVersionManifest {
             final_args.push(config.0.replace("${path}", &config.1.to_string_lossy()))
         }
 
+        final_args.append(&mut self.extra_jvm_args.clone());
+
         final_args
     }
 
@@ -121,7 +133,7 @@ impl MCVersionManifest {
             .flat_map(|&lib| lib.get_paths() )
             .map(|path| path.to_string_lossy().to_string() )
             .chain(iter::once(
-                self.get_client_jar(client).await.to_string_lossy().to_string()
+                self.get_game_jar(client).await.to_string_lossy().to_string()
             ))
             .collect::<Vec<String>>()
             .join(&separator)
@@ -131,6 +143,14 @@ impl MCVersionManifest {
         self.main_class.to_string()
     }
 
+    /// The jar the game is started from: the client jar, unless the instance replaces it
+    pub async fn get_game_jar(&self, client: &Client) -> PathBuf {
+        match &self.custom_jar {
+            Some(jar) => jar.clone(),
+            None => self.get_client_jar(client).await
+        }
+    }
+
     pub async fn get_client_jar(&self, client: &Client) -> PathBuf {
         let path = get_client_jar_dir().join(format!("{}.jar", self.id));
         download_file_checked(
@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, path::PathBuf};
 
 use serde::{Serialize, Deserialize};
 use serde_json::Value;
@@ -10,7 +10,7 @@ pub struct MCVersionList {
     pub versions: Vec<MCVersionDetails>
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct MCVersionDetails {
     pub id: String,
@@ -49,6 +49,12 @@ pub struct MCVersionManifest {
     pub logging: Option<MCLogging>,
     #[serde(rename = "type")]
     pub typ: String,
+
+    // Not part of the manifest, set by instance specific overrides
+    #[serde(skip)]
+    pub extra_jvm_args: Vec<String>,
+    #[serde(skip)]
+    pub custom_jar: Option<PathBuf>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -162,7 +168,7 @@ pub struct MCLibrary {
     pub natives: Option<Value>
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Default, Serialize, Deserialize)]
 pub struct MCLibraryDownloads {
     pub artifact: Option<MCLibraryDownloadsArtifacts>,
     pub classifiers: Option<MCLibraryDownloadsClassifiers>,
@@ -177,7 +183,7 @@ pub struct MCLibraryDownloadsArtifacts {
     pub sha1: Option<String>
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Default, Serialize, Deserialize)]
 #[serde(rename_all = "kebab-case")]
 pub struct MCLibraryDownloadsClassifiers {
     pub natives_linux: Option<MCLibraryDownloadsArtifacts>,
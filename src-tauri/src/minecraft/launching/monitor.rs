@@ -0,0 +1,240 @@
+use std::{
+    io::{BufRead, BufReader, Read},
+    process::{Child, Stdio},
+    sync::{atomic::{AtomicBool, Ordering}, mpsc::{self, RecvTimeoutError}, Arc},
+    thread,
+    time::{Duration, Instant}
+};
+
+use log::{*};
+use serde::{Serialize, Deserialize};
+
+use crate::minecraft::{instances::instances::SimpleInstance, java::JavaDetails};
+
+use super::{launching::{RunningInstances, get_command}, overrides::LaunchOverrides};
+
+/// Lines that mean the game has crashed, even if it is still running (like Forge's error screen)
+const CRASH_MARKERS: [&str; 4] = ["---- Minecraft Crash Report ----", "#@!@# Game crashed!", "Crash report saved to", "Exception in thread \"main\""];
+/// Title screen of current and old versions, and a started server
+const DEFAULT_SUCCESS_MARKERS: [&str; 3] = ["Sound engine started", "OpenAL initialized", "Done ("];
+const MAX_LOG_LINES: usize = 50_000;
+
+
+/// What counts as a successful start of the game
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MonitorOptions {
+    /// The game counts as started once one of these shows up in its output
+    pub success_markers: Vec<String>,
+    pub timeout_secs: u64,
+    /// Whether a line logged as ERROR or FATAL counts as a failure
+    pub fail_on_error_log: bool
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum RunOutcome {
+    Passed { marker: String },
+    Crashed { reason: String },
+    ErrorLogged { line: String },
+    TimedOut,
+    Cancelled
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RunReport {
+    pub outcome: RunOutcome,
+    pub passed: bool,
+    /// From starting the game until the outcome was clear
+    pub duration_ms: u64,
+    /// Everything the game printed until then
+    pub log: Vec<String>
+}
+
+
+impl Default for MonitorOptions {
+    fn default() -> Self {
+        Self {
+            success_markers: DEFAULT_SUCCESS_MARKERS.iter().map(|marker| marker.to_string()).collect(),
+            timeout_secs: 300,
+            fail_on_error_log: false
+        }
+    }
+}
+
+impl RunReport {
+    /// A line about the outcome and how long it took
+    pub fn get_summary(&self) -> String {
+        let seconds = self.duration_ms as f64 / 1000.0;
+        match &self.outcome {
+            RunOutcome::Passed { marker } => format!("PASSED after {seconds:.1}s (found \"{marker}\")"),
+            RunOutcome::Crashed { reason } => format!("FAILED after {seconds:.1}s: The game crashed: {reason}"),
+            RunOutcome::ErrorLogged { line } => format!("FAILED after {seconds:.1}s: An error was logged: {line}"),
+            RunOutcome::TimedOut => format!("FAILED after {seconds:.1}s: The game did not start in time"),
+            RunOutcome::Cancelled => format!("CANCELLED after {seconds:.1}s")
+        }
+    }
+}
+
+/// Launches `instance` through the same pipeline as `launch_instance`, but with its output captured.
+/// `running` is passed in since this also runs without the app from the command line.
+/// The game is stopped as soon as it has started, crashed, timed out or `cancel` is set.
+pub async fn run_monitored(instance: &SimpleInstance, mut java: JavaDetails, options: &MonitorOptions, cancel: Option<Arc<AtomicBool>>, running: &RunningInstances) -> Result<RunReport, String> {
+    if running.contains(&instance.instance_path) {
+        return Err(format!("{} is already running!", instance.name))
+    }
+
+    let overrides = LaunchOverrides::get(instance).await?;
+    overrides.apply_to_java(&mut java);
+    let mut command = get_command(instance, &java, &overrides).await?;
+
+    if let Some(command) = &overrides.pre_launch_command {
+        LaunchOverrides::run_command(command, instance, &java)?;
+    }
+
+    info!("Launching {} with monitoring (timeout: {}s)", instance.name, options.timeout_secs);
+    let process = command
+    .stdout(Stdio::piped())
+    .stderr(Stdio::piped())
+    .spawn()
+    .map_err(|err| format!("Failed to run Minecraft command: {err}"))?;
+    running.set(&instance.instance_path, true);
+
+    let watch_options = options.clone();
+    let report = tokio::task::spawn_blocking(move || watch(process, &watch_options, cancel)).await;
+    running.set(&instance.instance_path, false);
+
+    if let Some(command) = &overrides.post_exit_command {
+        LaunchOverrides::run_command(command, instance, &java)?;
+    }
+
+    let report = report.map_err(|err| format!("Failed to watch the game: {err}"))?;
+    info!("Monitored run of {} finished after {}ms: {:?}", instance.name, report.duration_ms, report.outcome);
+    Ok(report)
+}
+
+
+/// Reads the output of `process` until its outcome is clear, then makes sure it is stopped
+fn watch(mut process: Child, options: &MonitorOptions, cancel: Option<Arc<AtomicBool>>) -> RunReport {
+    let start = Instant::now();
+    let deadline = start + Duration::from_secs(options.timeout_secs);
+
+    let (sender, receiver) = mpsc::channel();
+    let streams: [Option<Box<dyn Read + Send>>; 2] = [
+        process.stdout.take().map(|stdout| Box::new(stdout) as Box<dyn Read + Send>),
+        process.stderr.take().map(|stderr| Box::new(stderr) as Box<dyn Read + Send>)
+    ];
+    for stream in streams.into_iter().flatten() {
+        let sender = sender.clone();
+        thread::spawn(move || {
+            for line in BufReader::new(stream).lines().map_while(Result::ok) {
+                if sender.send(line).is_err() {
+                    break
+                }
+            }
+        });
+    }
+    drop(sender);
+
+    let mut log = Vec::new();
+    let outcome = loop {
+        if cancel.as_ref().map_or(false, |cancel| cancel.load(Ordering::Relaxed)) {
+            break RunOutcome::Cancelled
+        }
+        let now = Instant::now();
+        if now >= deadline {
+            break RunOutcome::TimedOut
+        }
+
+        match receiver.recv_timeout((deadline - now).min(Duration::from_millis(500))) {
+            Ok(line) => {
+                let outcome = classify(&line, options);
+                if log.len() < MAX_LOG_LINES {
+                    log.push(line);
+                }
+                if let Some(outcome) = outcome {
+                    break outcome
+                }
+            },
+            Err(RecvTimeoutError::Timeout) => continue,
+            // Both streams are closed, so the game has exited
+            Err(RecvTimeoutError::Disconnected) => break RunOutcome::Crashed {
+                reason: match process.wait() {
+                    Ok(status) => format!("The game exited with {status} before it had started"),
+                    Err(err) => format!("The game exited before it had started: {err}")
+                }
+            }
+        }
+    };
+
+    if let Ok(None) = process.try_wait() {
+        debug!("Stopping the game");
+        if let Err(err) = process.kill() {
+            warn!("Failed to stop the game: {err}");
+        }
+    }
+    let _ = process.wait();
+
+    RunReport {
+        passed: matches!(outcome, RunOutcome::Passed { .. }),
+        outcome,
+        duration_ms: start.elapsed().as_millis() as u64,
+        log
+    }
+}
+
+fn classify(line: &str, options: &MonitorOptions) -> Option<RunOutcome> {
+    if CRASH_MARKERS.iter().any(|marker| line.contains(marker)) {
+        Some(RunOutcome::Crashed { reason: line.trim().to_string() })
+    } else if let Some(marker) = options.success_markers.iter().find(|marker| line.contains(marker.as_str())) {
+        Some(RunOutcome::Passed { marker: marker.to_string() })
+    } else if options.fail_on_error_log && is_error_line(line) {
+        Some(RunOutcome::ErrorLogged { line: line.trim().to_string() })
+    } else {
+        None
+    }
+}
+
+/// Plain log lines look like "[12:00:00] [Render thread/ERROR]: ...",
+/// the log4j configs of the launcher manifests print XML events with a `level` attribute instead
+fn is_error_line(line: &str) -> bool {
+    ["/ERROR]", "/FATAL]", "level=\"ERROR\"", "level=\"FATAL\""].iter().any(|marker| line.contains(marker))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_error_lines() {
+        assert!(is_error_line("[12:00:00] [Render thread/ERROR]: Failed to load texture"));
+        assert!(is_error_line("[12:00:00] [main/FATAL]: Unreported exception thrown!"));
+        assert!(is_error_line("<log4j:Event logger=\"ecs\" timestamp=\"1\" level=\"ERROR\" thread=\"main\">"));
+        assert!(!is_error_line("[12:00:00] [Render thread/WARN]: Missing sound for event"));
+        assert!(!is_error_line("[12:00:00] [main/INFO]: Loading 0 errors"));
+    }
+
+    #[test]
+    fn classifies_lines() {
+        let options = MonitorOptions::default();
+        assert_eq!(
+            classify("[12:00:00] [Render thread/INFO]: Sound engine started", &options),
+            Some(RunOutcome::Passed { marker: "Sound engine started".into() })
+        );
+        assert_eq!(
+            classify("  #@!@# Game crashed! Crash report saved to: #@!@# crash.txt  ", &options),
+            Some(RunOutcome::Crashed { reason: "#@!@# Game crashed! Crash report saved to: #@!@# crash.txt".into() })
+        );
+        assert_eq!(classify("[12:00:00] [main/INFO]: Loading Minecraft", &options), None);
+        // Errors only count if asked for
+        assert_eq!(classify("[12:00:00] [main/ERROR]: Oops", &options), None);
+    }
+
+    #[test]
+    fn classifies_error_lines_if_asked_for() {
+        let options = MonitorOptions { success_markers: vec!["Ready".into()], fail_on_error_log: true, ..Default::default() };
+        assert_eq!(classify("[12:00:00] [main/ERROR]: Oops ", &options), Some(RunOutcome::ErrorLogged { line: "[12:00:00] [main/ERROR]: Oops".into() }));
+        assert_eq!(classify("[12:00:00] [main/INFO]: Ready", &options), Some(RunOutcome::Passed { marker: "Ready".into() }));
+        assert_eq!(classify("[12:00:00] [main/INFO]: Sound engine started", &options), None);
+    }
+}
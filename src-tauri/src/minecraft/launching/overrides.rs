@@ -0,0 +1,132 @@
+use std::process::Command;
+
+use log::{*};
+
+use crate::minecraft::{instances::{instances::{SimpleInstance, InstanceType}, multimc::MMCConfig}, java::JavaDetails};
+
+
+/// Instance specific launch settings that take precedence over the global [`JavaDetails`]
+#[derive(Debug, Clone, Default)]
+pub struct LaunchOverrides {
+    pub java_path: Option<String>,
+    pub min_memory: Option<u32>,
+    pub max_memory: Option<u32>,
+    pub jvm_args: Option<String>,
+    pub window_size: Option<(u32, u32)>,
+    /// The game has no argument for this, a maximized window only skips passing `window_size` to legacy versions
+    pub maximized: bool,
+    pub pre_launch_command: Option<String>,
+    pub wrapper_command: Option<String>,
+    pub post_exit_command: Option<String>,
+    pub join_server: Option<String>
+}
+
+impl LaunchOverrides {
+    pub async fn get(instance: &SimpleInstance) -> Result<Self, String> {
+        match instance.instance_type {
+            InstanceType::MultiMC => MMCConfig::get(&instance.instance_path)
+                .await
+                .map(|config| config.get_overrides())
+                .map_err(|err| err.to_string()),
+            _ => Ok(Self::default())
+        }
+    }
+
+    pub fn apply_to_java(&self, java: &mut JavaDetails) {
+        if let Some(path) = &self.java_path {
+            java.path = path.to_string();
+        }
+        if let Some(xms) = self.min_memory {
+            java.xms = xms;
+        }
+        if let Some(xmx) = self.max_memory {
+            java.xmx = xmx;
+        }
+        if let Some(args) = &self.jvm_args {
+            java.args = args.to_string();
+        }
+    }
+
+    /// Arguments that make the game connect to `join_server` once it has started.
+    /// Versions with `quick_play` support (since 1.20) don't know `--server` and `--port` anymore.
+    pub fn get_server_args(&self, quick_play: bool) -> Vec<String> {
+        match &self.join_server {
+            Some(address) if quick_play => vec!["--quickPlayMultiplayer".into(), address.to_string()],
+            Some(address) => {
+                let (host, port) = split_address(address);
+                vec!["--server".into(), host.to_string(), "--port".into(), port.to_string()]
+            },
+            None => Vec::new()
+        }
+    }
+
+    /// Runs `command` through the shell, with the same environment variables MultiMC provides
+    pub fn run_command(command: &str, instance: &SimpleInstance, java: &JavaDetails) -> Result<(), String> {
+        info!("Running command: {command}");
+        let mut process = if cfg!(windows) {
+            let mut cmd = Command::new("cmd");
+            cmd.arg("/C").arg(command);
+            cmd
+        } else {
+            let mut cmd = Command::new("sh");
+            cmd.arg("-c").arg(command);
+            cmd
+        };
+
+        let status = process
+            .current_dir(&instance.minecraft_path)
+            .env("INST_NAME", &instance.name)
+            .env("INST_ID", instance.instance_path.file_name().unwrap_or_default())
+            .env("INST_DIR", &instance.instance_path)
+            .env("INST_MC_DIR", &instance.minecraft_path)
+            .env("INST_JAVA", &java.path)
+            .env("INST_JAVA_ARGS", java.get_args())
+            .status()
+            .map_err(|err| format!("Failed to run command {command:?}: {err}"))?;
+
+        if status.success() {
+            Ok(())
+        } else {
+            Err(format!("Command {command:?} failed with code {}", status.code().unwrap_or(-1)))
+        }
+    }
+}
+
+/// Splits a server address into its host and port, like "[::1]:25566" into "::1" and "25566".
+/// The port is 25565 if there is none.
+fn split_address(address: &str) -> (&str, &str) {
+    if let Some(rest) = address.strip_prefix('[') {
+        if let Some((host, after)) = rest.split_once(']') {
+            return (host, after.strip_prefix(':').filter(|port| !port.is_empty()).unwrap_or("25565"))
+        }
+    }
+    match address.split_once(':') {
+        // More than one colon is an IPv6 address without a port
+        Some((host, port)) if !port.contains(':') => (host, port),
+        _ => (address, "25565")
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_server_addresses() {
+        assert_eq!(split_address("example.com"), ("example.com", "25565"));
+        assert_eq!(split_address("example.com:25566"), ("example.com", "25566"));
+        assert_eq!(split_address("127.0.0.1:25566"), ("127.0.0.1", "25566"));
+        assert_eq!(split_address("[::1]:25566"), ("::1", "25566"));
+        assert_eq!(split_address("[::1]"), ("::1", "25565"));
+        assert_eq!(split_address("2001:db8::1"), ("2001:db8::1", "25565"));
+    }
+
+    #[test]
+    fn joins_servers_with_quick_play_if_supported() {
+        let overrides = LaunchOverrides { join_server: Some("[::1]:25566".into()), ..Default::default() };
+        assert_eq!(overrides.get_server_args(true), ["--quickPlayMultiplayer", "[::1]:25566"]);
+        assert_eq!(overrides.get_server_args(false), ["--server", "::1", "--port", "25566"]);
+        assert!(LaunchOverrides::default().get_server_args(true).is_empty());
+    }
+}
@@ -0,0 +1,179 @@
+use std::{fs, path::PathBuf};
+
+use log::{*};
+use reqwest::Client;
+use tauri::{AppHandle, Manager};
+
+use crate::{
+    configuration::settings::AppSettings,
+    minecraft::{instances::instances::SimpleInstance, java::{JavaDetails, JavaMCRange}},
+    NotificationState, Notifier
+};
+
+use super::{launching::RunningInstances, mc_structs::MCVersionDetails, monitor::{MonitorOptions, RunReport, run_monitored}};
+
+const USAGE: &str = "Usage: yamcl --smoke-test <instance folder> [--marker <text>]... [--timeout <seconds>] [--fail-on-error] [--java <path>] [--log <file>]";
+
+
+/// Options of `yamcl --smoke-test`
+#[derive(Debug)]
+struct CliOptions {
+    instance_path: PathBuf,
+    java_path: Option<String>,
+    /// Where the captured log is written to
+    log_path: Option<PathBuf>,
+    monitor: MonitorOptions
+}
+
+
+/// Starts `instance` and stops it again once it has started, to check whether it works at all
+#[tauri::command(async)]
+pub async fn smoke_test(instance: SimpleInstance, java: JavaDetails, options: Option<MonitorOptions>, app_handle: AppHandle) -> Result<RunReport, String> {
+    let notifier = Notifier::new(&format!("{}_smoke_test", instance.id), app_handle.clone());
+    notifier.notify("Starting the game...", NotificationState::Running);
+
+    let report = run_monitored(&instance, java, &options.unwrap_or_default(), None, app_handle.state::<RunningInstances>().inner()).await;
+    match &report {
+        Ok(report) => notifier.notify(&report.get_summary(), if report.passed { NotificationState::Success } else { NotificationState::Error }),
+        Err(err) => notifier.notify(err, NotificationState::Error)
+    }
+    report
+}
+
+/// Runs a smoke test without the app, for `yamcl --smoke-test` on build machines. Returns the exit code:
+/// 0 if the test passed, 1 if it failed and 2 if it could not be run.
+pub fn run_cli(args: &[String]) -> i32 {
+    let options = match CliOptions::parse(args) {
+        Ok(options) => options,
+        Err(err) => {
+            eprintln!("{err}\n{USAGE}");
+            return 2
+        }
+    };
+    let log_path = options.log_path.clone();
+
+    match tauri::async_runtime::block_on(run_cli_test(options)) {
+        Ok(report) => {
+            if let Some(path) = log_path {
+                if let Err(err) = fs::write(&path, report.log.join("\n")) {
+                    eprintln!("Failed to write the log to {path:?}: {err}");
+                }
+            } else if !report.passed {
+                // The end of the log usually tells why
+                let start = report.log.len().saturating_sub(50);
+                eprintln!("{}", report.log[start..].join("\n"));
+            }
+            println!("{}", report.get_summary());
+            if report.passed { 0 } else { 1 }
+        },
+        Err(err) => {
+            eprintln!("The smoke test could not be run: {err}");
+            2
+        }
+    }
+}
+
+
+async fn run_cli_test(options: CliOptions) -> Result<RunReport, String> {
+    let instance = SimpleInstance::from_path(&options.instance_path, None)
+        .await
+        .ok_or_else(|| format!("{:?} is not an instance folder!", options.instance_path))?
+        .map_err(|err| err.to_string())?;
+    info!("Smoke testing {} ({})", instance.name, instance.mc_version);
+
+    let java_settings = AppSettings::get().java_settings;
+    let java = match options.java_path {
+        Some(path) => java_settings.iter().find(|java| java.path == path).cloned().unwrap_or(JavaDetails {
+            path,
+            label: "Command line".into(),
+            version: String::new(),
+            minecraft_versions: JavaMCRange::default(),
+            xmx: 4096,
+            xms: 2048,
+            args: String::new()
+        }),
+        None => {
+            let version = MCVersionDetails::from_id(instance.mc_version.to_string(), &Client::new())
+                .await
+                .ok_or_else(|| format!("Could not find Minecraft version {}!", instance.mc_version))?;
+            JavaDetails::find_for_version(&java_settings, &version).ok_or_else(
+                || format!("No Java is set up for Minecraft {}, pass one with --java!", instance.mc_version)
+            )?
+        }
+    };
+
+    run_monitored(&instance, java, &options.monitor, None, &RunningInstances::default()).await
+}
+
+impl CliOptions {
+    fn parse(args: &[String]) -> Result<Self, String> {
+        let mut args = args.iter();
+        let instance_path = args.next().filter(|arg| !arg.starts_with("--")).ok_or("Missing the instance folder!")?;
+        let mut options = Self {
+            instance_path: PathBuf::from(instance_path),
+            java_path: None,
+            log_path: None,
+            monitor: MonitorOptions::default()
+        };
+
+        let mut markers = Vec::new();
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().cloned().ok_or(format!("Missing the value of {arg}!"));
+            match arg.as_str() {
+                "--marker" => markers.push(value()?),
+                "--timeout" => options.monitor.timeout_secs = value()?.parse().map_err(|err| format!("Invalid timeout: {err}"))?,
+                "--fail-on-error" => options.monitor.fail_on_error_log = true,
+                "--java" => options.java_path = Some(value()?),
+                "--log" => options.log_path = Some(PathBuf::from(value()?)),
+                other => return Err(format!("Unknown option {other}!"))
+            }
+        }
+        if !markers.is_empty() {
+            options.monitor.success_markers = markers;
+        }
+        Ok(options)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<CliOptions, String> {
+        CliOptions::parse(&args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn parses_the_defaults() {
+        let options = parse(&["instances/Pack"]).unwrap();
+        assert_eq!(options.instance_path, PathBuf::from("instances/Pack"));
+        assert_eq!(options.java_path, None);
+        assert_eq!(options.log_path, None);
+        assert_eq!(options.monitor.success_markers, MonitorOptions::default().success_markers);
+        assert_eq!(options.monitor.timeout_secs, 300);
+        assert!(!options.monitor.fail_on_error_log);
+    }
+
+    #[test]
+    fn parses_all_options() {
+        let options = parse(&[
+            "Pack", "--marker", "Ready", "--marker", "Done", "--timeout", "60",
+            "--fail-on-error", "--java", "/usr/bin/java", "--log", "game.log"
+        ]).unwrap();
+        assert_eq!(options.monitor.success_markers, ["Ready", "Done"]);
+        assert_eq!(options.monitor.timeout_secs, 60);
+        assert!(options.monitor.fail_on_error_log);
+        assert_eq!(options.java_path.as_deref(), Some("/usr/bin/java"));
+        assert_eq!(options.log_path, Some(PathBuf::from("game.log")));
+    }
+
+    #[test]
+    fn rejects_invalid_arguments() {
+        assert!(parse(&[]).is_err());
+        assert!(parse(&["--timeout", "60"]).is_err());
+        assert!(parse(&["Pack", "--timeout"]).is_err());
+        assert!(parse(&["Pack", "--timeout", "soon"]).is_err());
+        assert!(parse(&["Pack", "--unknown"]).is_err());
+    }
+}
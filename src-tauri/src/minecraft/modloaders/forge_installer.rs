@@ -1,11 +1,10 @@
 use std::{path::PathBuf, fs::{self, create_dir_all}, process::Command, iter};
 
-use jars::JarOptionBuilder;
 use log::{*};
 use reqwest::Client;
 use serde::{Serialize, Deserialize};
 
-use crate::{get_forge_cache_dir, download_file_checked, get_library_dir, get_classpath_separator, maven_identifier_to_path, get_client_jar_dir, get_data_dir};
+use crate::{get_forge_cache_dir, download_file_checked, read_jar_files, get_library_dir, get_classpath_separator, maven_identifier_to_path, get_client_jar_dir, get_data_dir};
 
 use super::forge::ForgeInstallProfile;
 
@@ -45,15 +44,9 @@ impl ForgeInstaller {
         let installer = Self::download(mc_ver, forge_ver, client).await;
 
         debug!("Extracting installer jar...");
-        let jar = jars::jar(
-            installer, 
-            JarOptionBuilder::builder()
-            .targets(&vec!["version.json", "install_profile.json", "data"])
-            .ext("the library has a bug that requires both of these to be set") // otherwise it will always extract all files
-            .build()
-        ).expect("Failed to extract Forge installer jar!");
-
-        let files = jar.files.iter().filter(|(_, contents)| !contents.is_empty());
+        let files = read_jar_files(&installer, &["version.json", "install_profile.json", "data/"]).expect("Failed to extract Forge installer jar!");
+
+        let files = files.iter().filter(|(_, contents)| !contents.is_empty());
         for (f_path, f_contents) in files {
             let full_path = get_installer_extracts_dir(mc_ver, forge_ver).join(f_path);
             if let Some(p) = full_path.parent() {
@@ -156,18 +149,10 @@ pub enum Side {
 }
 
 pub fn get_jar_main_class(jar_path: PathBuf) -> String {
-    let jar = jars::jar(
-        &jar_path,
-        JarOptionBuilder::builder()
-        .keep_meta_info().target("META-INF/MANIFEST.MF")
-        .ext("MF")
-        .build()    
-    ).expect(&format!("Failed to open jar {jar_path:?}"));
+    let files = read_jar_files(&jar_path, &["META-INF/MANIFEST.MF"]).expect(&format!("Failed to open jar {jar_path:?}"));
 
     let jar_mf = String::from_utf8_lossy(
-        jar.files.iter().find(|&(path, _)| {
-            path == "META-INF/MANIFEST.MF"
-        }).expect(&format!("Could not find MANIFEST.MF in jar {jar_path:?}")).1
+        files.get("META-INF/MANIFEST.MF").expect(&format!("Could not find MANIFEST.MF in jar {jar_path:?}"))
     );
 
     let main_class = jar_mf.split("\n").find(|&line| {
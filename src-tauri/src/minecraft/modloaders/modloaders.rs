@@ -14,7 +14,7 @@ pub enum LoaderManifests {
     Forge(ForgeVersionManifest)
 }
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum ModLoaders {
     Vanilla,
     Forge,
@@ -52,13 +52,67 @@ impl ModLoaders {
     
     pub fn from_cf(cf_name: &str) -> Option<Self> {
         if let Some(name) = cf_name.split('-').nth(0) {
-            STRING_LOADERS.iter().find(|&loader| {
-                loader.0 == name
-            }).map(|v| v.1)
+            Self::from_name(name)
         } else {
             None
         }
     }
+
+    /// Modrinth App profiles use lowercase loader names, with `vanilla` for no loader
+    pub fn from_modrinth(mr_name: &str) -> Option<Self> {
+        Self::from_name(mr_name)
+    }
+
+    /// ATLauncher uses capitalized names, Legacy Fabric is treated like Fabric
+    pub fn from_atlauncher(atl_type: &str) -> Option<Self> {
+        match atl_type {
+            "LegacyFabric" => Some(ModLoaders::Fabric),
+            _ => Self::from_name(atl_type)
+        }
+    }
+
+    pub fn from_gdlauncher(gdl_type: &str) -> Option<Self> {
+        Self::from_name(gdl_type)
+    }
+
+    /// Technic packs ship the loader as a library, returns the loader and the version part of the maven identifier
+    pub fn from_technic(library: &str) -> Option<(Self, String)> {
+        let mut parts = library.split(':');
+        let artifact = format!("{}:{}", parts.next()?, parts.next()?);
+        let version = parts.next()?;
+
+        TECHNIC_LOADERS.iter().find(|&loader| {
+            loader.0 == artifact
+        }).map(|v| (v.1, version.to_string()))
+    }
+
+    /// Maps a dependency key of `modrinth.index.json` to its loader, `minecraft` itself is not a loader
+    pub fn from_mrpack(dependency: &str) -> Option<Self> {
+        MRPACK_LOADERS.iter().find(|&loader| {
+            loader.0 == dependency
+        }).map(|v| v.1)
+    }
+
+    /// The MultiMC component uid of the loader
+    pub fn to_uid(self) -> Option<&'static str> {
+        MMC_LOADERS.iter().find(|&loader| loader.1 == self).map(|v| v.0)
+    }
+
+    /// The loader name CurseForge uses in `forge-47.2.0` style identifiers
+    pub fn to_cf(self) -> Option<&'static str> {
+        STRING_LOADERS.iter().find(|&loader| loader.1 == self).map(|v| v.0)
+    }
+
+    /// The dependency key of the loader in `modrinth.index.json`
+    pub fn to_mrpack(self) -> Option<&'static str> {
+        MRPACK_LOADERS.iter().find(|&loader| loader.1 == self).map(|v| v.0)
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        STRING_LOADERS.iter().find(|&loader| {
+            loader.0.eq_ignore_ascii_case(name)
+        }).map(|v| v.1)
+    }
 }
 
 impl fmt::Display for ModLoaders {
@@ -71,11 +125,26 @@ const MMC_LOADERS: [(&str, ModLoaders); 5] = [
     ("net.minecraftforge", ModLoaders::Forge),
     ("net.neoforged", ModLoaders::NeoForge),
     ("net.fabricmc.fabric-loader", ModLoaders::Fabric),
-    ("something", ModLoaders::Quilt),
-    ("something2", ModLoaders::LiteLoader),
+    ("org.quiltmc.quilt-loader", ModLoaders::Quilt),
+    ("com.mumfrey.liteloader", ModLoaders::LiteLoader),
 ];
-const STRING_LOADERS: [(&str, ModLoaders); 2] = [
+const STRING_LOADERS: [(&str, ModLoaders); 4] = [
     ("forge", ModLoaders::Forge),
+    ("neoforge", ModLoaders::NeoForge),
     ("fabric", ModLoaders::Fabric),
+    ("quilt", ModLoaders::Quilt),
+];
+const MRPACK_LOADERS: [(&str, ModLoaders); 4] = [
+    ("forge", ModLoaders::Forge),
+    ("neoforge", ModLoaders::NeoForge),
+    ("fabric-loader", ModLoaders::Fabric),
+    ("quilt-loader", ModLoaders::Quilt),
+];
+const TECHNIC_LOADERS: [(&str, ModLoaders); 5] = [
+    ("net.minecraftforge:forge", ModLoaders::Forge),
+    ("net.minecraftforge:minecraftforge", ModLoaders::Forge),
+    ("net.neoforged:neoforge", ModLoaders::NeoForge),
+    ("net.fabricmc:fabric-loader", ModLoaders::Fabric),
+    ("org.quiltmc:quilt-loader", ModLoaders::Quilt),
 ];
 
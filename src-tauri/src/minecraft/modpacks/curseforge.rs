@@ -0,0 +1,378 @@
+use std::{collections::HashMap, path::Path};
+
+use log::{*};
+use reqwest::{Client, RequestBuilder};
+use serde::{Serialize, Deserialize, de::DeserializeOwned};
+use serde_json::json;
+use tauri::AppHandle;
+
+use crate::{
+    configuration::settings::AppSettings,
+    download_file_verified, FileHashes, Notif, NotificationState, Notifier,
+    minecraft::{instances::{instances::SimpleInstance, yamcl::YamclInstance}, modloaders::modloaders::ModLoaders}
+};
+
+use super::{errors::ModpackError, modpacks::{extract_overrides, is_safe_path, open_archive, remove_failed_instance}};
+
+pub const CF_MANIFEST_FILENAME: &str = "manifest.json";
+/// The `algo` CurseForge uses for SHA-1 hashes
+const CF_HASH_SHA1: u32 = 1;
+/// CurseForge class ids of projects that don't go into the mods folder
+const CF_CLASS_FOLDERS: [(u32, &str); 2] = [
+    (12, "resourcepacks"),
+    (6552, "shaderpacks"),
+];
+
+
+// Handling the "manifest.json" file of exported CurseForge modpacks
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CFPackManifest {
+    pub minecraft: CFPackMinecraft,
+    pub manifest_type: String,
+    pub manifest_version: u32,
+    pub name: String,
+    pub version: Option<String>,
+    pub author: Option<String>,
+    pub files: Vec<CFPackFile>,
+    pub overrides: Option<String>
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CFPackMinecraft {
+    pub version: String,
+    pub mod_loaders: Vec<CFPackLoader>
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CFPackLoader {
+    /// Something like `forge-47.2.0`
+    pub id: String,
+    #[serde(default)]
+    pub primary: bool
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CFPackFile {
+    #[serde(rename = "projectID")]
+    pub project_id: u32,
+    #[serde(rename = "fileID")]
+    pub file_id: u32,
+    pub required: bool
+}
+
+// Responses of the CurseForge API
+#[derive(Debug, Deserialize)]
+struct CFApiResponse<T> {
+    data: T
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CFApiFile {
+    id: u32,
+    mod_id: u32,
+    file_name: String,
+    download_url: Option<String>,
+    #[serde(default)]
+    hashes: Vec<CFApiHash>
+}
+
+#[derive(Debug, Deserialize)]
+struct CFApiHash {
+    value: String,
+    algo: u32
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CFApiMod {
+    id: u32,
+    name: String,
+    class_id: Option<u32>,
+    links: Option<CFApiLinks>
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CFApiLinks {
+    website_url: Option<String>
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CFApiFingerprints {
+    exact_matches: Vec<CFApiFingerprintMatch>
+}
+
+#[derive(Debug, Deserialize)]
+struct CFApiFingerprintMatch {
+    file: CFApiFingerprintFile
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CFApiFingerprintFile {
+    id: u32,
+    mod_id: u32,
+    file_fingerprint: u32
+}
+
+/// A file the author of the pack doesn't allow to be downloaded by third party launchers
+#[derive(Debug, Clone, Serialize)]
+pub struct CFMissingFile {
+    pub name: String,
+    pub file_name: String,
+    /// Where the file has to be put, relative to the game directory
+    pub path: String,
+    /// The page the file can be downloaded from manually
+    pub url: Option<String>
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CFPackImport {
+    pub instance: SimpleInstance,
+    pub missing_files: Vec<CFMissingFile>
+}
+
+/// A CurseForge compatible API, configured through [`AppSettings`]
+pub struct CFApi<'a> {
+    client: &'a Client,
+    base_url: String,
+    api_key: Option<String>
+}
+
+
+#[tauri::command(async)]
+pub async fn import_cf_pack(path: String, app_handle: AppHandle) -> Result<CFPackImport, ModpackError> {
+    let notifier = Notifier::new("modpack_import_status", app_handle.clone());
+    let settings = AppSettings::get();
+    let dir = settings.get_primary_root().ok_or(ModpackError::PathUnset)?.path.to_string();
+
+    let client = Client::new();
+    let api = CFApi::new(&client, settings.curseforge_api_url, settings.curseforge_api_key);
+
+    let import = CFPackManifest::import(Path::new(&path), Path::new(&dir), &api, &notifier).await?;
+    import.instance.unlock_icon(&app_handle)?;
+
+    if import.missing_files.is_empty() {
+        notifier.notify(&format!("Imported '{}'!", import.instance.name), NotificationState::Success);
+    } else {
+        notifier.notify(
+            &format!("Imported '{}', but {} files have to be downloaded manually!", import.instance.name, import.missing_files.len()),
+            NotificationState::Warning
+        );
+    }
+    Ok(import)
+}
+
+
+impl CFPackManifest {
+    pub fn get(pack_path: &Path) -> Result<Self, ModpackError> {
+        let mut archive = open_archive(pack_path)?;
+        let manifest = archive.by_name(CF_MANIFEST_FILENAME).map_err(
+            |err| ModpackError::ArchiveReadFailed(pack_path.to_path_buf(), err)
+        )?;
+
+        serde_json::from_reader(manifest).map_err(
+            |err| ModpackError::ParseFailed(pack_path.to_path_buf(), CF_MANIFEST_FILENAME.into(), err)
+        )
+    }
+
+    /// Creates a native instance in `instances_dir` from the CurseForge pack zip at `pack_path`.
+    /// Files that can't be downloaded are skipped and returned, anything else failing removes the instance again.
+    pub async fn import(pack_path: &Path, instances_dir: &Path, api: &CFApi<'_>, notifier: &Notifier) -> Result<CFPackImport, ModpackError> {
+        let manifest = Self::get(pack_path)?;
+        if manifest.manifest_type != "minecraftModpack" || manifest.manifest_version != 1 {
+            return Err(ModpackError::Unsupported(format!("{} version {}", manifest.manifest_type, manifest.manifest_version)))
+        }
+
+        let (loader, loader_version) = manifest.get_loader();
+        info!("Importing modpack {} ({}, {loader} {})", manifest.name, manifest.minecraft.version, loader_version.as_deref().unwrap_or_default());
+        let instance_path = YamclInstance::create(instances_dir, &manifest.name, &manifest.minecraft.version, loader, loader_version, None).await?;
+
+        match manifest.install(pack_path, &instance_path.join("minecraft"), api, notifier).await {
+            Ok(missing_files) => Ok(CFPackImport {
+                instance: SimpleInstance::get_from_yamcl(&instance_path).await?,
+                missing_files
+            }),
+            Err(err) => {
+                remove_failed_instance(&instance_path);
+                Err(err)
+            }
+        }
+    }
+
+    /// The primary mod loader, Vanilla if there is none
+    pub fn get_loader(&self) -> (ModLoaders, Option<String>) {
+        let loader = self.minecraft.mod_loaders.iter().find(|loader| loader.primary).or(self.minecraft.mod_loaders.first());
+
+        loader.and_then(|loader| {
+            let typ = ModLoaders::from_cf(&loader.id)?;
+            Some((typ, loader.id.split_once('-').map(|(_, version)| version.to_string())))
+        }).unwrap_or((ModLoaders::Vanilla, None))
+    }
+
+    async fn install(&self, pack_path: &Path, minecraft_path: &Path, api: &CFApi<'_>, notifier: &Notifier) -> Result<Vec<CFMissingFile>, ModpackError> {
+        let files: Vec<&CFPackFile> = self.files.iter().filter(|file| {
+            if !file.required {
+                debug!("Skipping optional file {} of project {}", file.file_id, file.project_id);
+            }
+            file.required
+        }).collect();
+
+        notifier.notify_status(Notif::new("Resolving files", 0, files.len() as u32, NotificationState::Running));
+        let api_files = api.get_files(files.iter().map(|file| file.file_id).collect()).await?;
+        let projects = api.get_mods(files.iter().map(|file| file.project_id).collect()).await?;
+
+        // Files that were deleted or hidden by their author are left out by the API
+        let mut missing_files: Vec<CFMissingFile> = files.iter()
+            .filter(|file| !api_files.iter().any(|api_file| api_file.id == file.file_id))
+            .map(|file| {
+                let project = projects.get(&file.project_id);
+                let name = project.map_or(format!("Project {}", file.project_id), |project| project.name.to_string());
+                warn!("{name} ({}) is not available on CurseForge anymore", file.file_id);
+                notifier.notify(&format!("{name} has to be downloaded manually!"), NotificationState::Warning);
+                CFMissingFile::new(name, format!("File {}", file.file_id), project, file.file_id)
+            })
+            .collect();
+
+        let total = api_files.len() as u32;
+        for (i, file) in api_files.into_iter().enumerate() {
+            let project = projects.get(&file.mod_id);
+            let folder = get_folder(project);
+            let path = format!("{folder}/{}", file.file_name);
+            if !is_safe_path(&path) {
+                return Err(ModpackError::UnsafePath(path))
+            }
+
+            let url = match file.download_url {
+                Some(url) => url,
+                None => {
+                    let name = project.map_or(file.file_name.to_string(), |project| project.name.to_string());
+                    warn!("{name} ({}) can't be downloaded automatically", file.file_name);
+                    notifier.notify(&format!("{name} has to be downloaded manually!"), NotificationState::Warning);
+                    missing_files.push(CFMissingFile::new(name, file.file_name, project, file.id));
+                    continue
+                }
+            };
+
+            notifier.notify_status(Notif::new(&format!("Downloading {path}"), i as u32, total, NotificationState::Running));
+            let hashes = FileHashes {
+                sha1: file.hashes.iter().find(|hash| hash.algo == CF_HASH_SHA1).map(|hash| hash.value.to_string()),
+                sha512: None
+            };
+            download_file_verified(api.client, &hashes, &minecraft_path.join(&path), &[url]).await.map_err(
+                |err| ModpackError::DownloadFailed(path, err)
+            )?;
+        }
+
+        notifier.notify_status(Notif::new("Applying overrides", total, total, NotificationState::Running));
+        let overrides = format!("{}/", self.overrides.as_deref().unwrap_or("overrides"));
+        extract_overrides(pack_path, &[&overrides], minecraft_path)?;
+
+        Ok(missing_files)
+    }
+}
+
+impl CFMissingFile {
+    fn new(name: String, file_name: String, project: Option<&CFApiMod>, file_id: u32) -> Self {
+        Self {
+            name,
+            file_name,
+            path: get_folder(project).to_string(),
+            url: project.and_then(|project| project.links.as_ref())
+                .and_then(|links| links.website_url.as_ref())
+                .map(|url| format!("{url}/files/{file_id}"))
+        }
+    }
+}
+
+impl<'a> CFApi<'a> {
+    pub fn new(client: &'a Client, base_url: String, api_key: Option<String>) -> Self {
+        Self { client, base_url: base_url.trim_end_matches('/').to_string(), api_key }
+    }
+
+    async fn get_files(&self, file_ids: Vec<u32>) -> Result<Vec<CFApiFile>, ModpackError> {
+        self.post("mods/files", json!({ "fileIds": file_ids })).await
+    }
+
+    async fn get_mods(&self, mod_ids: Vec<u32>) -> Result<HashMap<u32, CFApiMod>, ModpackError> {
+        let mods: Vec<CFApiMod> = self.post("mods", json!({ "modIds": mod_ids })).await?;
+        Ok(mods.into_iter().map(|project| (project.id, project)).collect())
+    }
+
+    /// Looks up files by their [`get_fingerprint`], returns fingerprint -> (project id, file id) for the known ones
+    pub async fn get_fingerprint_matches(&self, fingerprints: Vec<u32>) -> Result<HashMap<u32, (u32, u32)>, ModpackError> {
+        if fingerprints.is_empty() {
+            return Ok(HashMap::new())
+        }
+        let matches: CFApiFingerprints = self.post("fingerprints", json!({ "fingerprints": fingerprints })).await?;
+        Ok(matches.exact_matches.into_iter().map(|m| (m.file.file_fingerprint, (m.file.mod_id, m.file.id))).collect())
+    }
+
+    async fn post<T: DeserializeOwned>(&self, endpoint: &str, body: serde_json::Value) -> Result<T, ModpackError> {
+        let url = format!("{}/{endpoint}", self.base_url);
+        debug!("Requesting {url}");
+
+        let response: CFApiResponse<T> = self.with_key(self.client.post(&url))
+            .json(&body)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|err| ModpackError::DownloadFailed(url.to_string(), err.to_string()))?
+            .json()
+            .await
+            .map_err(|err| ModpackError::DownloadFailed(url, err.to_string()))?;
+        Ok(response.data)
+    }
+
+    fn with_key(&self, request: RequestBuilder) -> RequestBuilder {
+        match &self.api_key {
+            Some(key) => request.header("x-api-key", key),
+            None => request
+        }
+    }
+}
+
+/// The folder in the game directory that files of `project` go into
+fn get_folder(project: Option<&CFApiMod>) -> &'static str {
+    project.and_then(|project| project.class_id)
+        .and_then(|class| CF_CLASS_FOLDERS.iter().find(|folder| folder.0 == class))
+        .map_or("mods", |folder| folder.1)
+}
+
+/// The fingerprint CurseForge identifies files by, a MurmurHash2 of the contents without whitespace
+pub fn get_fingerprint(contents: &[u8]) -> u32 {
+    let data: Vec<u8> = contents.iter().copied().filter(|byte| !matches!(byte, 9 | 10 | 13 | 32)).collect();
+    murmur2(&data, 1)
+}
+
+fn murmur2(data: &[u8], seed: u32) -> u32 {
+    const M: u32 = 0x5bd1e995;
+    let mut hash = seed ^ data.len() as u32;
+
+    let mut chunks = data.chunks_exact(4);
+    for chunk in &mut chunks {
+        let mut k = u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]).wrapping_mul(M);
+        k ^= k >> 24;
+        hash = hash.wrapping_mul(M) ^ k.wrapping_mul(M);
+    }
+
+    let rest = chunks.remainder();
+    if rest.len() >= 3 {
+        hash ^= (rest[2] as u32) << 16;
+    }
+    if rest.len() >= 2 {
+        hash ^= (rest[1] as u32) << 8;
+    }
+    if !rest.is_empty() {
+        hash ^= rest[0] as u32;
+        hash = hash.wrapping_mul(M);
+    }
+
+    hash ^= hash >> 13;
+    hash = hash.wrapping_mul(M);
+    hash ^ (hash >> 15)
+}
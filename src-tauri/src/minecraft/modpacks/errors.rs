@@ -0,0 +1,48 @@
+use std::path::PathBuf;
+
+use log::error;
+use serde::Serialize;
+use thiserror::Error;
+use tokio::io;
+use zip::result::ZipError;
+
+use crate::minecraft::instances::errors::InstanceGatherError;
+
+
+#[derive(Debug, Error)]
+pub enum ModpackError {
+    #[error("No instance folder is enabled! Head to the settings to add one.")]
+    PathUnset,
+
+    #[error("Failed to open modpack at {0:?}: {1}")]
+    ArchiveReadFailed(PathBuf, #[source] ZipError),
+    #[error("Failed to parse {1} of modpack {0:?}: {2}")]
+    ParseFailed(PathBuf, String, #[source] serde_json::Error),
+    #[error("Unsupported modpack: {0}")]
+    Unsupported(String),
+    #[error("The modpack contains a file outside of the instance: {0}")]
+    UnsafePath(String),
+
+    #[error("Failed to read {0:?}: {1}")]
+    FileReadFailed(PathBuf, #[source] io::Error),
+    #[error("Failed to write modpack to {0:?}: {1}")]
+    ArchiveWriteFailed(PathBuf, #[source] ZipError),
+
+    #[error("Failed to download {0}: {1}")]
+    DownloadFailed(String, String),
+    #[error("Failed to extract {0} to {1:?}: {2}")]
+    ExtractFailed(String, PathBuf, #[source] io::Error),
+
+    #[error(transparent)]
+    Instance(#[from] InstanceGatherError),
+}
+
+
+impl Serialize for ModpackError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer {
+        error!("{self}");
+        serializer.serialize_str(&self.to_string())
+    }
+}
@@ -0,0 +1,364 @@
+use std::{collections::{HashMap, HashSet}, fs::{self, File, FileType}, io::{self, Write}, path::{Path, PathBuf}};
+
+use log::{*};
+use reqwest::Client;
+use serde::{Serialize, Deserialize};
+use serde_json::json;
+use sha1_smol::Sha1;
+use tauri::AppHandle;
+use zip::{ZipWriter, write::FileOptions, result::ZipError};
+
+use crate::{
+    configuration::settings::AppSettings,
+    Notif, NotificationState, Notifier,
+    minecraft::instances::instances::SimpleInstance
+};
+
+use super::{
+    errors::ModpackError,
+    modpacks::is_safe_path,
+    mrpack::{MRPackIndex, MRPackFile, MRPackHashes, MRPACK_INDEX_FILENAME},
+    curseforge::{CFApi, CFPackManifest, CFPackMinecraft, CFPackLoader, CFPackFile, CF_MANIFEST_FILENAME, get_fingerprint}
+};
+
+const MODRINTH_API_URL: &str = "https://api.modrinth.com/v2";
+/// Only files in these folders are looked up on Modrinth or CurseForge, everything else is always embedded
+const REMOTE_FOLDERS: [&str; 3] = ["mods", "resourcepacks", "shaderpacks"];
+
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub enum ExportFormat {
+    Modrinth,
+    CurseForge,
+    MultiMC
+}
+
+/// A file of the instance that goes into the pack
+struct ExportFile {
+    /// Relative to the game directory, with `/` as separator
+    path: String,
+    /// Where the file is, it is only read while looking it up and while packing it
+    source: PathBuf
+}
+
+// Responses of the Modrinth API
+#[derive(Debug, Deserialize)]
+struct MRApiVersion {
+    files: Vec<MRApiFile>
+}
+
+#[derive(Debug, Deserialize)]
+struct MRApiFile {
+    hashes: MRPackHashes,
+    url: String,
+    size: u64
+}
+
+
+#[tauri::command(async)]
+pub async fn export_instance(
+    instance: SimpleInstance,
+    format: ExportFormat,
+    folders: Vec<String>,
+    version: String,
+    target: String,
+    app_handle: AppHandle
+) -> Result<(), ModpackError> {
+    let notifier = Notifier::new("modpack_export_status", app_handle);
+    let target = PathBuf::from(target);
+
+    let files = collect_files(&instance.minecraft_path, &folders)?;
+    info!("Exporting {} with {} files as {format:?} to {target:?}", instance.name, files.len());
+
+    let client = Client::new();
+    match format {
+        ExportFormat::Modrinth => export_mrpack(&instance, files, &version, &target, &client, &notifier).await?,
+        ExportFormat::CurseForge => {
+            let settings = AppSettings::get();
+            let api = CFApi::new(&client, settings.curseforge_api_url, settings.curseforge_api_key);
+            export_cf_pack(&instance, files, &version, &target, &api, &notifier).await?
+        },
+        ExportFormat::MultiMC => export_mmc(&instance, files, &target, &notifier)?
+    }
+
+    notifier.notify(&format!("Exported '{}'!", instance.name), NotificationState::Success);
+    Ok(())
+}
+
+
+async fn export_mrpack(instance: &SimpleInstance, files: Vec<ExportFile>, version: &str, target: &Path, client: &Client, notifier: &Notifier) -> Result<(), ModpackError> {
+    notifier.notify_status(Notif::new("Looking up files on Modrinth", 0, 0, NotificationState::Running));
+    let mut hashes = HashMap::new();
+    for file in files.iter().filter(|file| is_remote_candidate(&file.path)) {
+        hashes.insert(file.path.to_string(), get_sha1(&file.read()?));
+    }
+    let known = get_modrinth_files(client, hashes.values().cloned().collect()).await?;
+
+    let mut dependencies = HashMap::from([("minecraft".to_string(), instance.mc_version.to_string())]);
+    if let Some(id) = instance.modloader.typ.to_mrpack() {
+        dependencies.insert(id.to_string(), instance.modloader.version.to_string());
+    }
+
+    let mut index = MRPackIndex {
+        format_version: 1,
+        game: "minecraft".into(),
+        version_id: version.to_string(),
+        name: instance.name.to_string(),
+        summary: None,
+        files: Vec::new(),
+        dependencies
+    };
+    let mut overrides = Vec::new();
+
+    for file in files {
+        match hashes.get(&file.path).and_then(|sha1| known.get(sha1)) {
+            Some(remote) => index.files.push(MRPackFile {
+                path: file.path,
+                hashes: MRPackHashes { sha1: remote.hashes.sha1.to_string(), sha512: remote.hashes.sha512.to_string() },
+                env: None,
+                downloads: vec![remote.url.to_string()],
+                file_size: remote.size
+            }),
+            _ => overrides.push(file)
+        }
+    }
+    debug!("{} files are on Modrinth, {} are embedded", index.files.len(), overrides.len());
+
+    let index_json = serde_json::to_vec_pretty(&index).unwrap(/* this cannot fail */);
+    write_archive(target, vec![(MRPACK_INDEX_FILENAME.to_string(), index_json)], "overrides/", overrides, notifier)
+}
+
+async fn export_cf_pack(instance: &SimpleInstance, files: Vec<ExportFile>, version: &str, target: &Path, api: &CFApi<'_>, notifier: &Notifier) -> Result<(), ModpackError> {
+    notifier.notify_status(Notif::new("Looking up files on CurseForge", 0, 0, NotificationState::Running));
+    let mut fingerprints = HashMap::new();
+    for file in files.iter().filter(|file| is_remote_candidate(&file.path)) {
+        fingerprints.insert(file.path.to_string(), get_fingerprint(&file.read()?));
+    }
+    let known = api.get_fingerprint_matches(fingerprints.values().copied().collect()).await?;
+
+    let mut manifest = CFPackManifest {
+        minecraft: CFPackMinecraft {
+            version: instance.mc_version.to_string(),
+            mod_loaders: instance.modloader.typ.to_cf().map(|loader| CFPackLoader {
+                id: format!("{loader}-{}", instance.modloader.version),
+                primary: true
+            }).into_iter().collect()
+        },
+        manifest_type: "minecraftModpack".into(),
+        manifest_version: 1,
+        name: instance.name.to_string(),
+        version: Some(version.to_string()),
+        author: None,
+        files: Vec::new(),
+        overrides: Some("overrides".into())
+    };
+    let mut overrides = Vec::new();
+
+    for file in files {
+        match fingerprints.get(&file.path).and_then(|fingerprint| known.get(fingerprint)) {
+            Some(&(project_id, file_id)) => manifest.files.push(CFPackFile {
+                project_id,
+                file_id,
+                required: true
+            }),
+            _ => overrides.push(file)
+        }
+    }
+    debug!("{} files are on CurseForge, {} are embedded", manifest.files.len(), overrides.len());
+
+    let manifest_json = serde_json::to_vec_pretty(&manifest).unwrap(/* this cannot fail */);
+    write_archive(target, vec![(CF_MANIFEST_FILENAME.to_string(), manifest_json)], "overrides/", overrides, notifier)
+}
+
+/// MultiMC can't download anything itself, so every file is embedded
+fn export_mmc(instance: &SimpleInstance, files: Vec<ExportFile>, target: &Path, notifier: &Notifier) -> Result<(), ModpackError> {
+    let mut components = vec![json!({ "uid": "net.minecraft", "version": instance.mc_version, "important": true })];
+    if let Some(uid) = instance.modloader.typ.to_uid() {
+        components.push(json!({ "uid": uid, "version": instance.modloader.version }));
+    }
+    let pack = json!({ "formatVersion": 1, "components": components });
+    let config = format!("InstanceType=OneSix\nname={}\n", escape_ini_value(&instance.name));
+
+    write_archive(target, vec![
+        ("instance.cfg".to_string(), config.into_bytes()),
+        ("mmc-pack.json".to_string(), serde_json::to_vec_pretty(&pack).unwrap(/* this cannot fail */))
+    ], ".minecraft/", files, notifier)
+}
+
+
+/// Returns sha1 -> file for all of `hashes` that Modrinth knows about
+async fn get_modrinth_files(client: &Client, hashes: Vec<String>) -> Result<HashMap<String, MRApiFile>, ModpackError> {
+    if hashes.is_empty() {
+        return Ok(HashMap::new())
+    }
+    let url = format!("{MODRINTH_API_URL}/version_files");
+    let versions: HashMap<String, MRApiVersion> = client.post(&url)
+        .json(&json!({ "hashes": hashes, "algorithm": "sha1" }))
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|err| ModpackError::DownloadFailed(url.to_string(), err.to_string()))?
+        .json()
+        .await
+        .map_err(|err| ModpackError::DownloadFailed(url, err.to_string()))?;
+
+    Ok(versions.into_iter().filter_map(|(sha1, version)| {
+        let file = version.files.into_iter().find(|file| file.hashes.sha1 == sha1)?;
+        Some((sha1, file))
+    }).collect())
+}
+
+/// Escapes `value` the way MultiMC's INI parser reads it back, so names can't break out of their line
+fn escape_ini_value(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {},
+            '\t' => escaped.push_str("\\t"),
+            '#' => escaped.push_str("\\#"),
+            c => escaped.push(c)
+        }
+    }
+    escaped
+}
+
+fn get_sha1(contents: &[u8]) -> String {
+    Sha1::from(contents).digest().to_string()
+}
+
+fn is_remote_candidate(path: &str) -> bool {
+    path.split_once('/').map_or(false, |(folder, _)| REMOTE_FOLDERS.contains(&folder))
+}
+
+/// Finds all files inside of `folders` (or the files themselves), relative to `minecraft_path`.
+/// Files that are selected more than once through overlapping folders are only listed once.
+fn collect_files(minecraft_path: &Path, folders: &[String]) -> Result<Vec<ExportFile>, ModpackError> {
+    let mut files = Vec::new();
+    for folder in folders {
+        if !is_safe_path(folder.trim_matches('/')) {
+            return Err(ModpackError::UnsafePath(folder.to_string()))
+        }
+        let folder = folder.split('/').filter(|part| !part.is_empty()).collect::<Vec<_>>().join("/");
+        let path = minecraft_path.join(&folder);
+        match fs::symlink_metadata(&path) {
+            Ok(metadata) => collect_path(&path, &folder, metadata.file_type(), &mut files)?,
+            Err(_) => debug!("Skipping {path:?} since it doesn't exist")
+        }
+    }
+    let mut seen = HashSet::new();
+    files.retain(|file| seen.insert(file.path.to_string()));
+    Ok(files)
+}
+
+/// Symlinks to folders are skipped, following them could loop forever
+fn collect_path(path: &Path, relative: &str, file_type: FileType, files: &mut Vec<ExportFile>) -> Result<(), ModpackError> {
+    if file_type.is_dir() {
+        let entries = fs::read_dir(path).map_err(|err| ModpackError::FileReadFailed(path.to_path_buf(), err))?;
+        for entry in entries {
+            let entry = entry.map_err(|err| ModpackError::FileReadFailed(path.to_path_buf(), err))?;
+            let file_type = entry.file_type().map_err(|err| ModpackError::FileReadFailed(entry.path(), err))?;
+            let name = entry.file_name().to_string_lossy().to_string();
+            collect_path(&entry.path(), &format!("{relative}/{name}"), file_type, files)?;
+        }
+    } else if file_type.is_symlink() && path.is_dir() {
+        warn!("Skipping {path:?} since it links to a folder");
+    } else {
+        files.push(ExportFile { path: relative.to_string(), source: path.to_path_buf() });
+    }
+    Ok(())
+}
+
+impl ExportFile {
+    fn read(&self) -> Result<Vec<u8>, ModpackError> {
+        fs::read(&self.source).map_err(|err| ModpackError::FileReadFailed(self.source.to_path_buf(), err))
+    }
+}
+
+/// Writes `entries` to the root of the archive at `target` and `files` into `prefix`.
+/// If that fails, the incomplete archive is removed again.
+fn write_archive(target: &Path, entries: Vec<(String, Vec<u8>)>, prefix: &str, files: Vec<ExportFile>, notifier: &Notifier) -> Result<(), ModpackError> {
+    let result = write_archive_files(target, entries, prefix, files, notifier);
+    if result.is_err() {
+        if let Err(err) = fs::remove_file(target) {
+            warn!("Failed to remove the incomplete modpack at {target:?}: {err}");
+        }
+    }
+    result
+}
+
+fn write_archive_files(target: &Path, entries: Vec<(String, Vec<u8>)>, prefix: &str, files: Vec<ExportFile>, notifier: &Notifier) -> Result<(), ModpackError> {
+    let map_err = |err: ZipError| ModpackError::ArchiveWriteFailed(target.to_path_buf(), err);
+
+    let mut writer = ZipWriter::new(File::create(target).map_err(|err| map_err(err.into()))?);
+    let options = FileOptions::default();
+
+    for (name, contents) in entries {
+        writer.start_file(name, options).map_err(map_err)?;
+        writer.write_all(&contents).map_err(|err| map_err(err.into()))?;
+    }
+
+    let total = files.len() as u32;
+    for (i, file) in files.into_iter().enumerate() {
+        notifier.notify_status(Notif::new(&format!("Packing {}", file.path), i as u32, total, NotificationState::Running));
+        let mut source = File::open(&file.source).map_err(|err| ModpackError::FileReadFailed(file.source.to_path_buf(), err))?;
+        let size = source.metadata().map_or(0, |metadata| metadata.len());
+        writer.start_file(format!("{prefix}{}", file.path), options.large_file(size >= u32::MAX as u64)).map_err(map_err)?;
+        io::copy(&mut source, &mut writer).map_err(|err| ModpackError::FileReadFailed(file.source, err))?;
+    }
+
+    writer.finish().map_err(map_err)?;
+    Ok(())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::test_dir;
+
+    use super::*;
+
+    fn collected_paths(minecraft_path: &Path, folders: &[&str]) -> Vec<String> {
+        let folders: Vec<String> = folders.iter().map(|folder| folder.to_string()).collect();
+        let mut paths: Vec<String> = collect_files(minecraft_path, &folders).unwrap().into_iter().map(|file| file.path).collect();
+        paths.sort();
+        paths
+    }
+
+    #[test]
+    fn lists_overlapping_files_once() {
+        let dir = test_dir("export-overlap");
+        fs::create_dir_all(dir.join("config/mod")).unwrap();
+        fs::write(dir.join("config/foo.toml"), "foo").unwrap();
+        fs::write(dir.join("config/mod/bar.toml"), "bar").unwrap();
+        fs::write(dir.join("options.txt"), "fov:0.0").unwrap();
+
+        assert_eq!(
+            collected_paths(&dir, &["config", "config/foo.toml", "config//mod/", "options.txt", "missing"]),
+            ["config/foo.toml", "config/mod/bar.toml", "options.txt"]
+        );
+        assert!(matches!(collect_files(&dir, &["../saves".to_string()]), Err(ModpackError::UnsafePath(_))));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn skips_symlinked_folders() {
+        let dir = test_dir("export-symlinks");
+        fs::create_dir_all(dir.join("config")).unwrap();
+        fs::write(dir.join("config/foo.toml"), "foo").unwrap();
+        std::os::unix::fs::symlink(dir.join("config"), dir.join("config/loop")).unwrap();
+        std::os::unix::fs::symlink(dir.join("config/foo.toml"), dir.join("config/link.toml")).unwrap();
+
+        assert_eq!(collected_paths(&dir, &["config"]), ["config/foo.toml", "config/link.toml"]);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn escapes_names_for_instance_cfg() {
+        assert_eq!(escape_ini_value("My Pack"), "My Pack");
+        assert_eq!(escape_ini_value("Line\r\nInstanceType=Legacy"), "Line\\nInstanceType=Legacy");
+        assert_eq!(escape_ini_value("C:\\Pack #1\t"), "C:\\\\Pack \\#1\\t");
+    }
+}
@@ -0,0 +1,64 @@
+use std::{fs::{self, File}, io, path::{Component, Path, PathBuf}};
+
+use log::{*};
+use zip::ZipArchive;
+
+use super::errors::ModpackError;
+
+
+pub fn open_archive(pack_path: &Path) -> Result<ZipArchive<File>, ModpackError> {
+    let file = File::open(pack_path).map_err(
+        |err| ModpackError::ArchiveReadFailed(pack_path.to_path_buf(), err.into())
+    )?;
+    ZipArchive::new(file).map_err(
+        |err| ModpackError::ArchiveReadFailed(pack_path.to_path_buf(), err)
+    )
+}
+
+/// Copies the contents of the `prefixes` folders inside of the pack into `minecraft_path`,
+/// later folders replace files of earlier ones
+pub fn extract_overrides(pack_path: &Path, prefixes: &[&str], minecraft_path: &Path) -> Result<(), ModpackError> {
+    let mut archive = open_archive(pack_path)?;
+
+    for prefix in prefixes {
+        for index in 0..archive.len() {
+            let mut entry = archive.by_index(index).map_err(
+                |err| ModpackError::ArchiveReadFailed(pack_path.to_path_buf(), err)
+            )?;
+            let relative = match entry.name().strip_prefix(prefix) {
+                Some(relative) if !relative.is_empty() => relative.to_string(),
+                _ => continue
+            };
+            if !is_safe_path(&relative) {
+                return Err(ModpackError::UnsafePath(entry.name().to_string()))
+            }
+
+            let target = minecraft_path.join(&relative);
+            trace!("Extracting {} to {target:?}", entry.name());
+            let result = if entry.is_dir() {
+                fs::create_dir_all(&target)
+            } else {
+                target.parent().map_or(Ok(()), fs::create_dir_all)
+                    .and_then(|_| File::create(&target))
+                    .and_then(|mut file| io::copy(&mut entry, &mut file))
+                    .map(|_| ())
+            };
+            result.map_err(|err| ModpackError::ExtractFailed(relative, target, err))?;
+        }
+    }
+    Ok(())
+}
+
+/// Only relative paths that stay inside of the game directory are allowed
+pub fn is_safe_path(path: &str) -> bool {
+    let path = PathBuf::from(path);
+    path.components().next().is_some() && path.components().all(|comp| matches!(comp, Component::Normal(_)))
+}
+
+/// Removes a partially imported instance after a failed import
+pub fn remove_failed_instance(instance_path: &Path) {
+    warn!("Removing partially imported instance at {instance_path:?}");
+    if let Err(err) = fs::remove_dir_all(instance_path) {
+        error!("Failed to remove {instance_path:?}: {err}");
+    }
+}
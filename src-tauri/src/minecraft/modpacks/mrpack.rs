@@ -0,0 +1,258 @@
+use std::{collections::HashMap, path::Path};
+
+use log::{*};
+use reqwest::Client;
+use serde::{Serialize, Deserialize};
+use tauri::AppHandle;
+
+use crate::{
+    configuration::settings::AppSettings,
+    download_file_verified, FileHashes, Notif, NotificationState, Notifier,
+    minecraft::{instances::{instances::SimpleInstance, yamcl::YamclInstance}, modloaders::modloaders::ModLoaders}
+};
+
+use super::{errors::ModpackError, modpacks::{extract_overrides, is_safe_path, open_archive, remove_failed_instance}};
+
+pub const MRPACK_INDEX_FILENAME: &str = "modrinth.index.json";
+/// Folders inside of the pack that get copied into the game directory, in the order they are applied
+const MRPACK_OVERRIDES: [&str; 2] = ["overrides/", "client-overrides/"];
+
+
+// Handling the "modrinth.index.json" file of .mrpack files
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MRPackIndex {
+    pub format_version: u32,
+    pub game: String,
+    pub version_id: String,
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+    pub files: Vec<MRPackFile>,
+    pub dependencies: HashMap<String, String>
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MRPackFile {
+    pub path: String,
+    pub hashes: MRPackHashes,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub env: Option<MRPackEnv>,
+    pub downloads: Vec<String>,
+    pub file_size: u64
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MRPackHashes {
+    pub sha1: String,
+    pub sha512: String
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MRPackEnv {
+    pub client: MRPackSide,
+    pub server: MRPackSide
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MRPackSide {
+    Required,
+    Optional,
+    Unsupported
+}
+
+
+#[tauri::command(async)]
+pub async fn import_mrpack(path: String, app_handle: AppHandle) -> Result<SimpleInstance, ModpackError> {
+    let notifier = Notifier::new("modpack_import_status", app_handle.clone());
+    let dir = AppSettings::get().get_primary_root().ok_or(ModpackError::PathUnset)?.path.to_string();
+
+    let instance = MRPackIndex::import(Path::new(&path), Path::new(&dir), &Client::new(), &notifier).await?;
+    instance.unlock_icon(&app_handle)?;
+
+    notifier.notify(&format!("Imported '{}'!", instance.name), NotificationState::Success);
+    Ok(instance)
+}
+
+
+impl MRPackIndex {
+    pub fn get(pack_path: &Path) -> Result<Self, ModpackError> {
+        let mut archive = open_archive(pack_path)?;
+        let index = archive.by_name(MRPACK_INDEX_FILENAME).map_err(
+            |err| ModpackError::ArchiveReadFailed(pack_path.to_path_buf(), err)
+        )?;
+
+        serde_json::from_reader(index).map_err(
+            |err| ModpackError::ParseFailed(pack_path.to_path_buf(), MRPACK_INDEX_FILENAME.into(), err)
+        )
+    }
+
+    /// Creates a native instance in `instances_dir` from the .mrpack at `pack_path`.
+    /// The files are downloaded through `client`, so any server hosting them works.
+    /// If anything fails, the half finished instance is removed again.
+    pub async fn import(pack_path: &Path, instances_dir: &Path, client: &Client, notifier: &Notifier) -> Result<SimpleInstance, ModpackError> {
+        let index = Self::get(pack_path)?;
+        if index.format_version != 1 || index.game != "minecraft" {
+            return Err(ModpackError::Unsupported(format!("format version {} for {}", index.format_version, index.game)))
+        }
+
+        let mc_version = index.dependencies.get("minecraft").ok_or(
+            ModpackError::Unsupported(format!("{} does not depend on Minecraft", index.name))
+        )?;
+        let (loader, loader_version) = index.get_loader();
+
+        info!("Importing modpack {} {} ({mc_version}, {loader} {})", index.name, index.version_id, loader_version.as_deref().unwrap_or_default());
+        let instance_path = YamclInstance::create(instances_dir, &index.name, mc_version, loader, loader_version, None).await?;
+
+        if let Err(err) = index.install(pack_path, &instance_path.join("minecraft"), client, notifier).await {
+            remove_failed_instance(&instance_path);
+            return Err(err)
+        }
+
+        Ok(SimpleInstance::get_from_yamcl(&instance_path).await?)
+    }
+
+    /// The first dependency that is a known mod loader, Vanilla if there is none
+    pub fn get_loader(&self) -> (ModLoaders, Option<String>) {
+        self.dependencies.iter()
+            .find_map(|(id, version)| ModLoaders::from_mrpack(id).map(|loader| (loader, Some(version.to_string()))))
+            .unwrap_or((ModLoaders::Vanilla, None))
+    }
+
+    async fn install(&self, pack_path: &Path, minecraft_path: &Path, client: &Client, notifier: &Notifier) -> Result<(), ModpackError> {
+        let total = self.download_files(minecraft_path, client, |file, i, total| {
+            notifier.notify_status(Notif::new(&format!("Downloading {}", file.path), i, total, NotificationState::Running));
+        }).await?;
+
+        notifier.notify_status(Notif::new("Applying overrides", total, total, NotificationState::Running));
+        extract_overrides(pack_path, &MRPACK_OVERRIDES, minecraft_path)
+    }
+
+    /// Downloads the files the client needs into `minecraft_path`, calling `progress` before each of them.
+    /// Returns how many files were downloaded.
+    async fn download_files(&self, minecraft_path: &Path, client: &Client, progress: impl Fn(&MRPackFile, u32, u32)) -> Result<u32, ModpackError> {
+        let files: Vec<&MRPackFile> = self.files.iter().filter(|file| {
+            let supported = file.env.as_ref().map_or(true, |env| env.client != MRPackSide::Unsupported);
+            if !supported {
+                debug!("Skipping server only file {}", file.path);
+            }
+            supported
+        }).collect();
+
+        let total = files.len() as u32;
+        for (i, file) in files.into_iter().enumerate() {
+            if !is_safe_path(&file.path) {
+                return Err(ModpackError::UnsafePath(file.path.to_string()))
+            }
+            progress(file, i as u32, total);
+
+            let hashes = FileHashes {
+                sha1: Some(file.hashes.sha1.to_string()),
+                sha512: Some(file.hashes.sha512.to_string())
+            };
+            download_file_verified(client, &hashes, &minecraft_path.join(&file.path), &file.downloads).await.map_err(
+                |err| ModpackError::DownloadFailed(file.path.to_string(), err)
+            )?;
+        }
+        Ok(total)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, fs};
+
+    use reqwest::Client;
+    use tauri::async_runtime::block_on;
+
+    use crate::tests::{hashes_of, serve_files, test_dir};
+    use super::*;
+
+    fn pack_file(path: &str, contents: &[u8], downloads: Vec<String>) -> MRPackFile {
+        let hashes = hashes_of(contents);
+        MRPackFile {
+            path: path.to_string(),
+            hashes: MRPackHashes { sha1: hashes.sha1.unwrap(), sha512: hashes.sha512.unwrap() },
+            env: None,
+            downloads,
+            file_size: contents.len() as u64
+        }
+    }
+
+    fn index(files: Vec<MRPackFile>) -> MRPackIndex {
+        MRPackIndex {
+            format_version: 1,
+            game: "minecraft".into(),
+            version_id: "1.0.0".into(),
+            name: "Test Pack".into(),
+            summary: None,
+            files,
+            dependencies: HashMap::from([("minecraft".to_string(), "1.20.1".to_string())])
+        }
+    }
+
+    fn fixtures() -> String {
+        serve_files(HashMap::from([
+            ("/mod.jar".to_string(), b"mod".to_vec()),
+            ("/config.toml".to_string(), b"config".to_vec()),
+            ("/tampered.jar".to_string(), b"not the mod".to_vec())
+        ]))
+    }
+
+    #[test]
+    fn downloads_client_files() {
+        let url = fixtures();
+        let dir = test_dir("mrpack-download");
+        let mut server_only = pack_file("mods/server.jar", b"server", vec![format!("{url}/missing.jar")]);
+        server_only.env = Some(MRPackEnv { client: MRPackSide::Unsupported, server: MRPackSide::Required });
+        let index = index(vec![
+            pack_file("mods/mod.jar", b"mod", vec![format!("{url}/mod.jar")]),
+            pack_file("config/mod.toml", b"config", vec![format!("{url}/config.toml")]),
+            server_only
+        ]);
+
+        let downloaded = block_on(index.download_files(&dir, &Client::new(), |_, _, _| {})).unwrap();
+        assert_eq!(downloaded, 2);
+        assert_eq!(fs::read(dir.join("mods/mod.jar")).unwrap(), b"mod");
+        assert_eq!(fs::read(dir.join("config/mod.toml")).unwrap(), b"config");
+        assert!(!dir.join("mods/server.jar").exists());
+    }
+
+    #[test]
+    fn falls_back_to_next_download() {
+        let url = fixtures();
+        let dir = test_dir("mrpack-fallback");
+        let index = index(vec![
+            pack_file("mods/mod.jar", b"mod", vec![format!("{url}/missing.jar"), format!("{url}/tampered.jar"), format!("{url}/mod.jar")])
+        ]);
+
+        block_on(index.download_files(&dir, &Client::new(), |_, _, _| {})).unwrap();
+        assert_eq!(fs::read(dir.join("mods/mod.jar")).unwrap(), b"mod");
+    }
+
+    #[test]
+    fn rejects_hash_mismatch() {
+        let url = fixtures();
+        let dir = test_dir("mrpack-mismatch");
+        let index = index(vec![pack_file("mods/mod.jar", b"mod", vec![format!("{url}/tampered.jar")])]);
+
+        let result = block_on(index.download_files(&dir, &Client::new(), |_, _, _| {}));
+        assert!(matches!(result, Err(ModpackError::DownloadFailed(path, _)) if path == "mods/mod.jar"));
+        assert!(!dir.join("mods/mod.jar").exists());
+    }
+
+    #[test]
+    fn rejects_unsafe_paths() {
+        let url = fixtures();
+        let dir = test_dir("mrpack-unsafe");
+        for path in ["../escaped.jar", "mods/../../escaped.jar", "/tmp/escaped.jar", ""] {
+            let index = index(vec![pack_file(path, b"mod", vec![format!("{url}/mod.jar")])]);
+            let result = block_on(index.download_files(&dir.join("minecraft"), &Client::new(), |_, _, _| {}));
+            assert!(matches!(result, Err(ModpackError::UnsafePath(_))), "{path} was allowed");
+        }
+        assert!(!dir.join("escaped.jar").exists());
+    }
+}
@@ -0,0 +1,331 @@
+use std::{
+    collections::{HashMap, HashSet},
+    future::Future,
+    path::PathBuf,
+    pin::Pin,
+    sync::{atomic::{AtomicBool, AtomicU32, Ordering}, Arc, Mutex}
+};
+
+use log::{*};
+use serde::Serialize;
+use tauri::{AppHandle, Manager};
+
+use crate::{
+    minecraft::{
+        instances::{instances::SimpleInstance, metadata::InstanceMetadata},
+        java::JavaDetails,
+        launching::{launching::RunningInstances, monitor::{MonitorOptions, RunOutcome, run_monitored}}
+    },
+    NotificationState, Notifier
+};
+
+use super::{
+    errors::ModError,
+    mods::{ModInfo, DISABLED_SUFFIX, ensure_stopped},
+    profiles::{ModProfile, set_enabled_mods}
+};
+
+/// The mods that were enabled before bisecting are saved as this profile until they are restored,
+/// so they can be applied by hand if the launcher is closed in the middle
+const BACKUP_PROFILE: &str = "Before bisect";
+
+
+/// Cancel flags of the bisections that are currently running by instance path, managed by tauri
+#[derive(Debug, Default)]
+pub struct RunningBisects(Mutex<HashMap<PathBuf, Arc<AtomicBool>>>);
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BisectResult {
+    /// The mods (without `DISABLED_SUFFIX`) that crash the game together, empty if it didn't crash with all mods
+    pub culprits: Vec<String>,
+    pub runs: u32
+}
+
+/// Narrows down the mods that crash the game, `crashes` tells whether it crashes with exactly the given mods enabled
+struct Bisector<F> {
+    crashes: F,
+    /// Mod -> the mods it needs, which stay enabled with it
+    dependencies: HashMap<String, Vec<String>>
+}
+
+
+/// Finds the mod (or mods) that make `instance` crash on startup by launching it with fewer and fewer of them enabled.
+/// The enabled mods are restored afterwards, also if it fails or gets cancelled.
+#[tauri::command(async)]
+pub async fn bisect_mods(instance: SimpleInstance, java: JavaDetails, options: Option<MonitorOptions>, app_handle: AppHandle) -> Result<BisectResult, ModError> {
+    ensure_stopped(&instance, &app_handle)?;
+    let cancel = Arc::new(AtomicBool::new(false));
+    {
+        let mut bisects = app_handle.state::<RunningBisects>().inner().0.lock().unwrap();
+        if bisects.contains_key(&instance.instance_path) {
+            return Err(ModError::BisectRunning(instance.name.to_string()))
+        }
+        bisects.insert(instance.instance_path.to_path_buf(), cancel.clone());
+    }
+
+    let result = bisect(&instance, java, options.unwrap_or_default(), cancel, &app_handle).await;
+    app_handle.state::<RunningBisects>().inner().0.lock().unwrap().remove(&instance.instance_path);
+
+    let notifier = Notifier::new(&format!("{}_bisect", instance.id), app_handle.clone());
+    match &result {
+        Ok(result) if result.culprits.is_empty() => notifier.notify("The game did not crash with all mods enabled.", NotificationState::Warning),
+        Ok(result) => notifier.notify(&format!("Found the cause after {} runs: {}", result.runs, result.culprits.join(", ")), NotificationState::Success),
+        Err(err) => notifier.notify(&err.to_string(), NotificationState::Error)
+    }
+    result
+}
+
+#[tauri::command(async)]
+pub async fn cancel_bisect(instance: SimpleInstance, app_handle: AppHandle) {
+    if let Some(cancel) = app_handle.state::<RunningBisects>().inner().0.lock().unwrap().get(&instance.instance_path) {
+        info!("Cancelling the bisection of {}", instance.name);
+        cancel.store(true, Ordering::Relaxed);
+    }
+}
+
+
+async fn bisect(instance: &SimpleInstance, java: JavaDetails, options: MonitorOptions, cancel: Arc<AtomicBool>, app_handle: &AppHandle) -> Result<BisectResult, ModError> {
+    let mods_dir = instance.minecraft_path.join("mods");
+    let mods = ModInfo::get_all(&mods_dir)?;
+    let original: Vec<String> = mods.iter()
+        .filter(|info| info.enabled)
+        .map(|info| info.file_name.to_string())
+        .collect();
+
+    let mut meta = InstanceMetadata::get(&instance.instance_path).await?;
+    meta.mod_profiles.insert(BACKUP_PROFILE.into(), ModProfile { enabled: original.clone() });
+    meta.save(&instance.instance_path).await?;
+
+    let notifier = Notifier::new(&format!("{}_bisect", instance.id), app_handle.clone());
+    let runs = AtomicU32::new(0);
+    let (options, mods_dir, notifier, runs) = (&options, &mods_dir, &notifier, &runs);
+    let crashes = move |enabled: HashSet<String>| {
+        let (java, cancel) = (java.clone(), cancel.clone());
+        async move {
+            if cancel.load(Ordering::Relaxed) {
+                return Err(ModError::BisectCancelled)
+            }
+
+            set_enabled_mods(mods_dir, &enabled.iter().map(|file_name| file_name.trim_end_matches(DISABLED_SUFFIX)).collect())?;
+            let run = runs.fetch_add(1, Ordering::Relaxed) + 1;
+            notifier.notify(&format!("Run {run}: testing with {} mods", enabled.len()), NotificationState::Running);
+
+            let report = run_monitored(instance, java, options, Some(cancel), app_handle.state::<RunningInstances>().inner())
+                .await
+                .map_err(ModError::BisectFailed)?;
+            debug!("Run {run} with {} mods: {:?}", enabled.len(), report.outcome);
+            match report.outcome {
+                RunOutcome::Cancelled => Err(ModError::BisectCancelled),
+                RunOutcome::Passed { .. } => Ok(false),
+                // Slow mods would otherwise be blamed for a crash that never happened
+                RunOutcome::TimedOut => Err(ModError::BisectFailed(format!(
+                    "The game neither started nor crashed within {} seconds, try again with a longer timeout.", options.timeout_secs
+                ))),
+                RunOutcome::Crashed { .. } | RunOutcome::ErrorLogged { .. } => Ok(true)
+            }
+        }
+    };
+
+    info!("Bisecting {} mods of {}", original.len(), instance.name);
+    let mut bisector = Bisector { crashes, dependencies: get_dependencies(&mods) };
+    let result = bisector.run(original.clone()).await;
+
+    info!("Restoring the mods of {}", instance.name);
+    set_enabled_mods(mods_dir, &original.iter().map(String::as_str).collect())?;
+    let mut meta = InstanceMetadata::get(&instance.instance_path).await?;
+    meta.mod_profiles.remove(BACKUP_PROFILE);
+    meta.save(&instance.instance_path).await?;
+
+    result.map(|culprits| BisectResult { culprits, runs: runs.load(Ordering::Relaxed) })
+}
+
+/// The required dependencies of each enabled mod that are installed, by their file names
+fn get_dependencies(mods: &[ModInfo]) -> HashMap<String, Vec<String>> {
+    let enabled: Vec<&ModInfo> = mods.iter().filter(|info| info.enabled).collect();
+    let mut providers = HashMap::new();
+    for info in &enabled {
+        providers.entry(info.id.as_str()).or_insert(info.file_name.as_str());
+        for provided in &info.provides {
+            providers.entry(provided.id.as_str()).or_insert(info.file_name.as_str());
+        }
+    }
+
+    enabled.iter().map(|info| {
+        let needed = info.dependencies.iter()
+            .filter(|dependency| dependency.required)
+            .filter_map(|dependency| providers.get(dependency.id.as_str()))
+            .filter(|file_name| **file_name != info.file_name)
+            .map(|file_name| file_name.to_string())
+            .collect();
+        (info.file_name.to_string(), needed)
+    }).collect()
+}
+
+
+impl<F, Fut> Bisector<F>
+where
+    F: FnMut(HashSet<String>) -> Fut + Send,
+    Fut: Future<Output = Result<bool, ModError>> + Send
+{
+    async fn run(&mut self, mods: Vec<String>) -> Result<Vec<String>, ModError> {
+        if !self.crashes(&mods).await? {
+            return Ok(Vec::new())
+        }
+        if self.crashes(&[]).await? {
+            return Err(ModError::BisectFailed("The game also crashes without any mods!".into()))
+        }
+        self.narrow(mods, Vec::new()).await
+    }
+
+    /// The smallest part of `candidates` that still crashes the game together with `fixed`
+    fn narrow(&mut self, candidates: Vec<String>, fixed: Vec<String>) -> Pin<Box<dyn Future<Output = Result<Vec<String>, ModError>> + Send + '_>> {
+        Box::pin(async move {
+            if candidates.len() <= 1 {
+                return Ok(candidates)
+            }
+            let (first, second) = candidates.split_at(candidates.len() / 2);
+            let (first, second) = (first.to_vec(), second.to_vec());
+
+            if self.crashes(&[fixed.as_slice(), &first].concat()).await? {
+                return self.narrow(first, fixed).await
+            }
+            if self.crashes(&[fixed.as_slice(), &second].concat()).await? {
+                return self.narrow(second, fixed).await
+            }
+
+            // Neither half crashes on its own, so it takes mods from both of them
+            let first_culprits = self.narrow(first, [fixed.as_slice(), &second].concat()).await?;
+            let second_culprits = self.narrow(second, [fixed.as_slice(), &first_culprits].concat()).await?;
+            Ok([first_culprits, second_culprits].concat())
+        })
+    }
+
+    /// Whether the game crashes with only `mods` and what they need enabled
+    async fn crashes(&mut self, mods: &[String]) -> Result<bool, ModError> {
+        let enabled = self.with_dependencies(mods);
+        (self.crashes)(enabled).await
+    }
+
+    fn with_dependencies(&self, mods: &[String]) -> HashSet<String> {
+        let mut enabled = HashSet::new();
+        let mut queue: Vec<&String> = mods.iter().collect();
+        while let Some(file_name) = queue.pop() {
+            if enabled.insert(file_name.to_string()) {
+                queue.extend(self.dependencies.get(file_name).into_iter().flatten());
+            }
+        }
+        enabled
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::future;
+
+    use tauri::async_runtime::block_on;
+
+    use crate::minecraft::mods::metadata::{ModDependency, ProvidedMod};
+
+    use super::*;
+
+    fn mod_info(id: &str, dependencies: &[&str], provides: &[&str]) -> ModInfo {
+        ModInfo {
+            id: id.to_string(),
+            name: id.to_string(),
+            version: None,
+            authors: Vec::new(),
+            description: None,
+            dependencies: dependencies.iter().map(|id| ModDependency { id: id.to_string(), version: None, required: true }).collect(),
+            breaks: Vec::new(),
+            provides: provides.iter().map(|id| ProvidedMod { id: id.to_string(), version: None }).collect(),
+            source: None,
+            icon_path: None,
+            file_name: format!("{id}.jar"),
+            sha1: String::new(),
+            enabled: true
+        }
+    }
+
+    fn mods() -> Vec<ModInfo> {
+        let mut optional = mod_info("optional", &[], &[]);
+        optional.dependencies.push(ModDependency { id: "lib".into(), version: None, required: false });
+        let mut disabled = mod_info("disabled", &["lib"], &[]);
+        disabled.enabled = false;
+        vec![
+            mod_info("lib", &["minecraft"], &["lib-api"]),
+            mod_info("a", &["lib-api", "a"], &[]),
+            mod_info("b", &["a"], &[]),
+            mod_info("c", &[], &[]),
+            mod_info("d", &[], &[]),
+            mod_info("e", &[], &[]),
+            optional,
+            disabled
+        ]
+    }
+
+    /// Bisects `mods` with a game that crashes whenever all of `culprits` are enabled, returns the culprits and runs
+    fn bisect(culprits: &[&str]) -> (Vec<String>, u32) {
+        let mods = mods();
+        let culprits: Vec<String> = culprits.iter().map(|file_name| file_name.to_string()).collect();
+        let mut runs = 0;
+        let mut bisector = Bisector {
+            crashes: |enabled: HashSet<String>| {
+                runs += 1;
+                future::ready(Ok(culprits.iter().all(|culprit| enabled.contains(culprit))))
+            },
+            dependencies: get_dependencies(&mods)
+        };
+        let enabled = mods.iter().filter(|info| info.enabled).map(|info| info.file_name.to_string()).collect();
+        let mut found = block_on(bisector.run(enabled)).unwrap();
+        found.sort();
+        drop(bisector);
+        (found, runs)
+    }
+
+    #[test]
+    fn gets_installed_required_dependencies() {
+        let dependencies = get_dependencies(&mods());
+        assert_eq!(dependencies.len(), 7);
+        assert!(dependencies["lib.jar"].is_empty());
+        assert_eq!(dependencies["a.jar"], ["lib.jar"]);
+        assert_eq!(dependencies["b.jar"], ["a.jar"]);
+        assert!(dependencies["optional.jar"].is_empty());
+        assert!(!dependencies.contains_key("disabled.jar"));
+    }
+
+    #[test]
+    fn enables_dependencies_transitively() {
+        let bisector = Bisector { crashes: |_| future::ready(Ok(false)), dependencies: get_dependencies(&mods()) };
+        let enabled = bisector.with_dependencies(&["b.jar".into(), "c.jar".into()]);
+        assert_eq!(enabled, HashSet::from(["a.jar", "b.jar", "c.jar", "lib.jar"].map(String::from)));
+        assert!(bisector.with_dependencies(&[]).is_empty());
+    }
+
+    #[test]
+    fn finds_single_culprit() {
+        for culprit in ["c.jar", "e.jar", "optional.jar"] {
+            let (found, runs) = bisect(&[culprit]);
+            assert_eq!(found, [culprit]);
+            // Two runs to check that it crashes at all, then at most two per halving of the 7 mods
+            assert!(runs <= 8, "{runs} runs to find {culprit}");
+        }
+    }
+
+    #[test]
+    fn finds_culprit_with_its_dependencies() {
+        assert_eq!(bisect(&["b.jar"]).0, ["b.jar"]);
+        assert_eq!(bisect(&["lib.jar"]), (vec!["lib.jar".to_string()], 4));
+    }
+
+    #[test]
+    fn finds_culprit_pair() {
+        assert_eq!(bisect(&["c.jar", "e.jar"]).0, ["c.jar", "e.jar"]);
+        assert_eq!(bisect(&["lib.jar", "optional.jar"]).0, ["lib.jar", "optional.jar"]);
+    }
+
+    #[test]
+    fn reports_no_culprits_without_crash() {
+        assert_eq!(bisect(&["disabled.jar"]), (Vec::new(), 1));
+    }
+}
@@ -0,0 +1,244 @@
+use std::collections::HashMap;
+
+use log::{*};
+use serde::Serialize;
+use tauri::{AppHandle, Manager, api::dialog};
+use tokio::sync::oneshot;
+
+use crate::minecraft::{instances::instances::SimpleInstance, java::JavaDetails, modloaders::modloaders::ModLoaders};
+
+use super::{
+    errors::ModError,
+    metadata::{ModDependency, ModSource},
+    mods::ModInfo,
+    versions::{Version, matches_maven, matches_semver}
+};
+
+/// Ids that mods depend on which are part of the loader itself, rather than a mod
+const PLATFORM_IDS: [&str; 5] = ["fml", "javafml", "lowcodefml", "mcp", "minecraftforge"];
+/// How many issues are listed in the dialog before launching
+const MAX_LISTED_ISSUES: usize = 12;
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub enum ModIssueKind {
+    MissingDependency,
+    VersionMismatch,
+    Incompatible,
+    DuplicateId,
+    WrongLoader,
+    WrongMinecraftVersion
+}
+
+/// Something about the mods of an instance that will likely crash the game
+#[derive(Debug, Clone, Serialize)]
+pub struct ModIssue {
+    pub kind: ModIssueKind,
+    /// The mod that causes the issue
+    pub file_name: String,
+    pub message: String
+}
+
+/// A mod id that is available in the instance and the version it is available in, if known
+struct Available<'a> {
+    name: &'a str,
+    version: Option<Version>
+}
+
+
+#[tauri::command(async)]
+pub async fn check_mods(instance: SimpleInstance, java: Option<JavaDetails>) -> Result<Vec<ModIssue>, ModError> {
+    ModIssue::check_in_background(instance, java).await
+}
+
+/// Checks the mods of `instance` before launching and asks the user whether to launch anyway if there are issues.
+/// Returns whether the launch should go on.
+pub async fn confirm_launch(instance: &SimpleInstance, java: &JavaDetails, app_handle: &AppHandle) -> bool {
+    let issues = match ModIssue::check_in_background(instance.clone(), Some(java.clone())).await {
+        Ok(issues) => issues,
+        Err(err) => {
+            warn!("Skipping the mod check: {err}");
+            return true
+        }
+    };
+    if issues.is_empty() {
+        return true
+    }
+    warn!("Found {} issues with the mods of {}", issues.len(), instance.name);
+
+    let mut message: Vec<String> = issues.iter().take(MAX_LISTED_ISSUES).map(|issue| format!("• {}", issue.message)).collect();
+    if issues.len() > MAX_LISTED_ISSUES {
+        message.push(format!("...and {} more", issues.len() - MAX_LISTED_ISSUES));
+    }
+    message.push("\nLaunch anyway?".into());
+
+    let (sender, receiver) = oneshot::channel();
+    dialog::ask(
+        app_handle.get_window("main").as_ref(),
+        format!("Problems with the mods of {}", instance.name),
+        message.join("\n"),
+        move |launch| { let _ = sender.send(launch); }
+    );
+    receiver.await.unwrap_or(false)
+}
+
+
+impl ModIssue {
+    /// Runs [`ModIssue::check`] on a blocking thread, since it reads every enabled jar
+    pub async fn check_in_background(instance: SimpleInstance, java: Option<JavaDetails>) -> Result<Vec<Self>, ModError> {
+        tokio::task::spawn_blocking(move || Self::check(&instance, java.as_ref())).await
+            .map_err(|err| ModError::CheckFailed(err.to_string()))?
+    }
+
+    /// Checks the enabled mods of `instance` against each other, its loader, Minecraft version and `java`
+    pub fn check(instance: &SimpleInstance, java: Option<&JavaDetails>) -> Result<Vec<Self>, ModError> {
+        let loader = &instance.modloader.typ;
+        // The mods folder isn't loaded without a mod loader
+        if *loader == ModLoaders::Vanilla {
+            return Ok(Vec::new())
+        }
+
+        let mods = ModInfo::get_enabled_metadata(&instance.minecraft_path.join("mods"))?;
+        let available = get_available(instance, java, &mods);
+        let mut issues = Vec::new();
+
+        for info in &mods {
+            let source = match info.source {
+                Some(source) => source,
+                None => continue
+            };
+            if !loader_supports(loader, source) {
+                issues.push(Self::new(ModIssueKind::WrongLoader, info, format!("{} is a {source:?} mod, which {loader} can't load", info.name)));
+                // Its dependencies would only add noise
+                continue
+            }
+
+            for dependency in &info.dependencies {
+                if let Some(issue) = Self::check_dependency(info, source, dependency, &available) {
+                    issues.push(issue);
+                }
+            }
+            for incompatible in &info.breaks {
+                if let Some(other) = available.get(incompatible.id.as_str()) {
+                    if matches_dependency(source, incompatible, other) {
+                        let severity = if incompatible.required { "is incompatible with" } else { "has known issues with" };
+                        issues.push(Self::new(ModIssueKind::Incompatible, info, format!("{} {severity} {}", info.name, other.name)));
+                    }
+                }
+            }
+        }
+
+        let mut by_id: HashMap<&str, Vec<&ModInfo>> = HashMap::new();
+        for info in mods.iter().filter(|info| info.source.is_some()) {
+            by_id.entry(&info.id).or_default().push(info);
+        }
+        for (id, duplicates) in by_id.into_iter().filter(|(_, duplicates)| duplicates.len() > 1) {
+            let files: Vec<&str> = duplicates.iter().map(|info| info.file_name.as_str()).collect();
+            issues.push(Self::new(ModIssueKind::DuplicateId, duplicates[0], format!("The mod {id} is installed {} times: {}", files.len(), files.join(", "))));
+        }
+
+        Ok(issues)
+    }
+
+    fn check_dependency(info: &ModInfo, source: ModSource, dependency: &ModDependency, available: &HashMap<&str, Available>) -> Option<Self> {
+        let wanted = dependency.version.as_deref().filter(|version| !version.is_empty() && *version != "*");
+        match available.get(dependency.id.as_str()) {
+            Some(other) if !matches_dependency(source, dependency, other) => {
+                let (kind, name) = if dependency.id == "minecraft" {
+                    (ModIssueKind::WrongMinecraftVersion, "Minecraft")
+                } else {
+                    (ModIssueKind::VersionMismatch, other.name)
+                };
+                Some(Self::new(kind, info, format!("{} needs {name} {}", info.name, wanted.unwrap_or_default())))
+            },
+            Some(_) => None,
+            None if dependency.required => Some(Self::new(
+                ModIssueKind::MissingDependency,
+                info,
+                format!("{} needs {} {}, which is not installed", info.name, dependency.id, wanted.unwrap_or_default())
+            )),
+            None => None
+        }
+    }
+
+    fn new(kind: ModIssueKind, info: &ModInfo, message: String) -> Self {
+        Self { kind, file_name: info.file_name.to_string(), message: message.trim_end().to_string() }
+    }
+}
+
+
+/// All ids that dependencies can be satisfied by: the mods, what they provide and the platform itself
+fn get_available<'a>(instance: &'a SimpleInstance, java: Option<&JavaDetails>, mods: &'a [ModInfo]) -> HashMap<&'a str, Available<'a>> {
+    let mut available = HashMap::new();
+    let mut add = |id: &'a str, name: &'a str, version: Option<&str>| {
+        available.entry(id).or_insert_with(|| Available { name, version: version.map(Version::parse) });
+    };
+
+    for info in mods.iter().filter(|info| info.source.is_some()) {
+        add(&info.id, &info.name, info.version.as_deref());
+    }
+    for info in mods.iter() {
+        for provided in &info.provides {
+            add(&provided.id, &info.name, provided.version.as_deref());
+        }
+    }
+
+    add("minecraft", "Minecraft", Some(&instance.mc_version));
+    let java_major = java.map(|java| get_java_major(&java.version));
+    add("java", "Java", java_major.as_deref());
+
+    let loader_version = instance.modloader.version.trim_start_matches(&format!("{}-", instance.mc_version)).to_string();
+    let loader_version = Some(loader_version.as_str());
+    match instance.modloader.typ {
+        ModLoaders::Fabric => add("fabricloader", "Fabric Loader", loader_version),
+        ModLoaders::Quilt => {
+            add("quilt_loader", "Quilt Loader", loader_version);
+            // Quilt implements a Fabric Loader version of its own
+            add("fabricloader", "Fabric Loader", None);
+        },
+        ModLoaders::Forge => add("forge", "Forge", loader_version),
+        ModLoaders::NeoForge => {
+            add("neoforge", "NeoForge", loader_version);
+            // NeoForge for 1.20.1 still calls itself Forge
+            add("forge", "Forge", loader_version);
+        },
+        _ => {}
+    }
+    for id in PLATFORM_IDS {
+        add(id, id, None);
+    }
+
+    available
+}
+
+/// Whether `other` is in the version range of `dependency`, written in the syntax of the loader of `source`
+fn matches_dependency(source: ModSource, dependency: &ModDependency, other: &Available) -> bool {
+    match (&dependency.version, &other.version) {
+        (Some(range), Some(version)) => match source {
+            ModSource::Fabric | ModSource::Quilt => matches_semver(range, version),
+            ModSource::Forge | ModSource::NeoForge | ModSource::McModInfo => matches_maven(range, version)
+        },
+        _ => true
+    }
+}
+
+fn loader_supports(loader: &ModLoaders, source: ModSource) -> bool {
+    match loader {
+        ModLoaders::Fabric => source == ModSource::Fabric,
+        ModLoaders::Quilt => matches!(source, ModSource::Quilt | ModSource::Fabric),
+        ModLoaders::Forge => matches!(source, ModSource::Forge | ModSource::McModInfo),
+        ModLoaders::NeoForge => matches!(source, ModSource::NeoForge | ModSource::Forge),
+        ModLoaders::LiteLoader => source == ModSource::McModInfo,
+        ModLoaders::Vanilla | ModLoaders::Rift => true
+    }
+}
+
+/// "1.8.0_382" is Java 8, "17.0.8" is Java 17
+fn get_java_major(version: &str) -> String {
+    let mut parts = version.trim().split(['.', '_', '-', '+']);
+    match parts.next() {
+        Some("1") => parts.next().unwrap_or("1").to_string(),
+        Some(major) => major.to_string(),
+        None => version.to_string()
+    }
+}
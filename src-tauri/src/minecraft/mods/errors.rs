@@ -0,0 +1,59 @@
+use std::path::PathBuf;
+
+use log::error;
+use serde::Serialize;
+use thiserror::Error;
+use tokio::io;
+
+use crate::minecraft::instances::errors::InstanceGatherError;
+
+
+#[derive(Debug, Error)]
+pub enum ModError {
+    #[error("Could not read the mods folder at {0:?}: {1}")]
+    DirectoryReadFailed(PathBuf, #[source] io::Error),
+    #[error("Failed to create directory at {0:?}: {1}")]
+    DirectoryCreateFailed(PathBuf, #[source] io::Error),
+    #[error("Failed to read file at {0:?}: {1}")]
+    FileReadFailed(PathBuf, #[source] io::Error),
+    #[error("Failed to write file at {0:?}: {1}")]
+    FileWriteFailed(PathBuf, #[source] io::Error),
+    #[error("Failed to read the jar at {0:?}: {1}")]
+    JarReadFailed(PathBuf, #[source] io::Error),
+    #[error("Failed to parse {1} of {0:?}: {2}")]
+    ParseFailed(PathBuf, String, String),
+    #[error("Failed to rename {0:?} to {1:?}: {2}")]
+    RenameFailed(PathBuf, PathBuf, #[source] io::Error),
+    #[error("Can't rename {0:?} to {1:?}, that file already exists!")]
+    RenameConflict(PathBuf, PathBuf),
+    #[error("There is no mod {0} in the mods folder!")]
+    ModNotFound(String),
+    #[error("There is no mod profile named {0}!")]
+    ProfileNotFound(String),
+    #[error("Mod profiles need a name!")]
+    InvalidProfileName,
+    #[error("{0} is currently running!")]
+    InstanceRunning(String),
+    #[error("{0} is already being bisected!")]
+    BisectRunning(String),
+    #[error("Bisecting was cancelled.")]
+    BisectCancelled,
+    #[error("Bisecting failed: {0}")]
+    BisectFailed(String),
+    #[error("Checking the mods failed: {0}")]
+    CheckFailed(String),
+    #[error(transparent)]
+    Instance(#[from] InstanceGatherError),
+    #[error("Failed to whitelist path {0:?} for the asset protocol!")]
+    PathUnlockFailed(PathBuf, #[source] tauri::Error),
+}
+
+
+impl Serialize for ModError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer {
+        error!("{self}");
+        serializer.serialize_str(&self.to_string())
+    }
+}
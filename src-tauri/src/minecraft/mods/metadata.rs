@@ -0,0 +1,452 @@
+use std::{collections::HashMap, path::Path};
+
+use serde::{Serialize, Deserialize};
+use serde_json::Value;
+
+use super::errors::ModError;
+
+pub const FABRIC_METADATA: &str = "fabric.mod.json";
+pub const QUILT_METADATA: &str = "quilt.mod.json";
+pub const FORGE_METADATA: &str = "META-INF/mods.toml";
+pub const NEOFORGE_METADATA: &str = "META-INF/neoforge.mods.toml";
+pub const LEGACY_METADATA: &str = "mcmod.info";
+pub const MANIFEST: &str = "META-INF/MANIFEST.MF";
+/// The jars that (Neo)Forge mods bundle
+pub const JARJAR_METADATA: &str = "META-INF/jarjar/metadata.json";
+/// Everything that needs to be read from a jar to get its metadata
+pub const METADATA_FILES: [&str; 7] = [QUILT_METADATA, FABRIC_METADATA, NEOFORGE_METADATA, FORGE_METADATA, LEGACY_METADATA, MANIFEST, JARJAR_METADATA];
+
+
+/// Which metadata file the information of a mod comes from
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ModSource {
+    Quilt,
+    Fabric,
+    NeoForge,
+    Forge,
+    /// `mcmod.info` of old Forge (and LiteLoader) mods
+    McModInfo
+}
+
+/// The information shared by all of the metadata formats
+#[derive(Debug, Clone)]
+pub struct ModMetadata {
+    pub id: String,
+    pub name: Option<String>,
+    pub version: Option<String>,
+    pub authors: Vec<String>,
+    pub description: Option<String>,
+    pub dependencies: Vec<ModDependency>,
+    /// Mods this one does not work with, `required` is false for ones that only cause issues
+    pub breaks: Vec<ModDependency>,
+    /// Other mod ids this mod counts as
+    pub provides: Vec<ProvidedMod>,
+    /// Paths of the jars bundled inside of this one
+    pub nested_jars: Vec<String>,
+    /// Path of the icon inside of the jar
+    pub icon: Option<String>,
+    pub source: ModSource
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModDependency {
+    pub id: String,
+    /// The version (range) as written by the mod, in the syntax of its loader
+    pub version: Option<String>,
+    pub required: bool
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProvidedMod {
+    pub id: String,
+    pub version: Option<String>
+}
+
+
+// Handling "fabric.mod.json"
+#[derive(Debug, Deserialize)]
+struct FabricModJson {
+    id: String,
+    version: String,
+    name: Option<String>,
+    description: Option<String>,
+    #[serde(default)]
+    authors: Vec<FabricPerson>,
+    icon: Option<ModIcon>,
+    #[serde(default)]
+    depends: HashMap<String, FabricVersions>,
+    #[serde(default)]
+    recommends: HashMap<String, FabricVersions>,
+    #[serde(default)]
+    breaks: HashMap<String, FabricVersions>,
+    #[serde(default)]
+    conflicts: HashMap<String, FabricVersions>,
+    #[serde(default)]
+    provides: Vec<String>,
+    #[serde(default)]
+    jars: Vec<FabricJar>
+}
+
+#[derive(Debug, Deserialize)]
+struct FabricJar {
+    file: String
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum FabricPerson {
+    Name(String),
+    Object { name: String }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum FabricVersions {
+    One(String),
+    /// Any of these may match
+    Any(Vec<String>)
+}
+
+/// Either a single path or paths by their size in pixels
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum ModIcon {
+    Path(String),
+    Sizes(HashMap<String, String>)
+}
+
+// Handling "quilt.mod.json"
+#[derive(Debug, Deserialize)]
+struct QuiltModJson {
+    quilt_loader: QuiltLoader
+}
+
+#[derive(Debug, Deserialize)]
+struct QuiltLoader {
+    id: String,
+    version: String,
+    #[serde(default)]
+    metadata: QuiltMetadata,
+    #[serde(default)]
+    depends: Vec<QuiltDependency>,
+    #[serde(default)]
+    breaks: Vec<QuiltDependency>,
+    #[serde(default)]
+    provides: Vec<QuiltProvided>,
+    #[serde(default)]
+    jars: Vec<String>
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct QuiltMetadata {
+    name: Option<String>,
+    description: Option<String>,
+    /// Name -> role
+    #[serde(default)]
+    contributors: HashMap<String, Value>,
+    icon: Option<ModIcon>
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum QuiltDependency {
+    Id(String),
+    Object {
+        id: String,
+        /// A string, a list of strings or an object with `any`/`all`
+        versions: Option<Value>,
+        #[serde(default)]
+        optional: bool
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum QuiltProvided {
+    Id(String),
+    Object {
+        id: String,
+        version: Option<String>
+    }
+}
+
+// Handling "META-INF/mods.toml" and "META-INF/neoforge.mods.toml"
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ModsToml {
+    logo_file: Option<String>,
+    #[serde(default)]
+    mods: Vec<ModsTomlMod>,
+    /// Mod id -> its dependencies
+    #[serde(default)]
+    dependencies: HashMap<String, Vec<ModsTomlDependency>>
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ModsTomlMod {
+    mod_id: String,
+    version: Option<String>,
+    display_name: Option<String>,
+    description: Option<String>,
+    authors: Option<TomlAuthors>,
+    logo_file: Option<String>
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum TomlAuthors {
+    One(String),
+    Many(Vec<String>)
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ModsTomlDependency {
+    mod_id: String,
+    /// Forge
+    mandatory: Option<bool>,
+    /// NeoForge: "required", "optional", "incompatible" or "discouraged"
+    #[serde(rename = "type")]
+    typ: Option<String>,
+    version_range: Option<String>
+}
+
+// Handling "META-INF/jarjar/metadata.json"
+#[derive(Debug, Deserialize)]
+struct JarJarMetadata {
+    #[serde(default)]
+    jars: Vec<JarJarEntry>
+}
+
+#[derive(Debug, Deserialize)]
+struct JarJarEntry {
+    path: String
+}
+
+// Handling "mcmod.info"
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum McModInfoFile {
+    List(Vec<McModInfo>),
+    V2 {
+        #[serde(rename = "modList", alias = "modlist")]
+        mod_list: Vec<McModInfo>
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct McModInfo {
+    modid: String,
+    name: Option<String>,
+    version: Option<String>,
+    description: Option<String>,
+    #[serde(default, alias = "authors")]
+    author_list: Vec<String>,
+    logo_file: Option<String>,
+    /// Entries look like "Forge@[10.13.4,)"
+    #[serde(default)]
+    required_mods: Vec<String>
+}
+
+
+impl ModMetadata {
+    /// Parses the metadata out of `files`, which are the `METADATA_FILES` read from the jar at `jar_path`.
+    /// Returns `None` if there is none, Quilt jars often also contain Fabric metadata, so that one is preferred.
+    pub fn parse(jar_path: &Path, files: &HashMap<String, Vec<u8>>) -> Result<Option<Self>, ModError> {
+        let parse_failed = |file: &str, err: String| ModError::ParseFailed(jar_path.to_path_buf(), file.to_string(), err);
+
+        if let Some(contents) = files.get(QUILT_METADATA) {
+            let json: QuiltModJson = serde_json::from_str(&sanitize_json(contents)).map_err(|err| parse_failed(QUILT_METADATA, err.to_string()))?;
+            Ok(Some(Self::from_quilt(json.quilt_loader)))
+        } else if let Some(contents) = files.get(FABRIC_METADATA) {
+            let json: FabricModJson = serde_json::from_str(&sanitize_json(contents)).map_err(|err| parse_failed(FABRIC_METADATA, err.to_string()))?;
+            Ok(Some(Self::from_fabric(json)))
+        } else if let Some((file, contents)) = [NEOFORGE_METADATA, FORGE_METADATA].iter().find_map(|file| Some((*file, files.get(*file)?))) {
+            let toml: ModsToml = toml::from_str(&String::from_utf8_lossy(contents)).map_err(|err| parse_failed(file, err.to_string()))?;
+            let source = if file == NEOFORGE_METADATA { ModSource::NeoForge } else { ModSource::Forge };
+            let mut metadata = Self::from_mods_toml(toml, source, files.get(MANIFEST));
+            if let (Some(metadata), Some(contents)) = (metadata.as_mut(), files.get(JARJAR_METADATA)) {
+                let jarjar: JarJarMetadata = serde_json::from_slice(contents).map_err(|err| parse_failed(JARJAR_METADATA, err.to_string()))?;
+                metadata.nested_jars = jarjar.jars.into_iter().map(|jar| jar.path).collect();
+            }
+            Ok(metadata)
+        } else if let Some(contents) = files.get(LEGACY_METADATA) {
+            let info: McModInfoFile = serde_json::from_str(&sanitize_json(contents)).map_err(|err| parse_failed(LEGACY_METADATA, err.to_string()))?;
+            Ok(Self::from_mcmod_info(info))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn from_fabric(json: FabricModJson) -> Self {
+        let dependency = |required: bool| move |(id, versions): (String, FabricVersions)| ModDependency {
+            id,
+            version: Some(match versions {
+                FabricVersions::One(version) => version,
+                FabricVersions::Any(versions) => versions.join(" || ")
+            }),
+            required
+        };
+
+        Self {
+            provides: json.provides.into_iter().map(|id| ProvidedMod { id, version: Some(json.version.to_string()) }).collect(),
+            id: json.id,
+            name: json.name,
+            version: Some(json.version),
+            authors: json.authors.into_iter().map(|author| match author {
+                FabricPerson::Name(name) | FabricPerson::Object { name } => name
+            }).collect(),
+            description: json.description,
+            dependencies: json.depends.into_iter().map(dependency(true))
+                .chain(json.recommends.into_iter().map(dependency(false)))
+                .collect(),
+            breaks: json.breaks.into_iter().map(dependency(true))
+                .chain(json.conflicts.into_iter().map(dependency(false)))
+                .collect(),
+            nested_jars: json.jars.into_iter().map(|jar| jar.file).collect(),
+            icon: json.icon.and_then(ModIcon::get_largest),
+            source: ModSource::Fabric
+        }
+    }
+
+    fn from_quilt(loader: QuiltLoader) -> Self {
+        Self {
+            id: loader.id,
+            name: loader.metadata.name,
+            version: Some(loader.version.to_string()),
+            authors: loader.metadata.contributors.into_keys().collect(),
+            description: loader.metadata.description,
+            dependencies: loader.depends.into_iter().map(QuiltDependency::into_dependency).collect(),
+            breaks: loader.breaks.into_iter().map(QuiltDependency::into_dependency).collect(),
+            provides: loader.provides.into_iter().map(|provided| match provided {
+                QuiltProvided::Id(id) => ProvidedMod { id, version: Some(loader.version.to_string()) },
+                QuiltProvided::Object { id, version } => ProvidedMod { id, version: version.or_else(|| Some(loader.version.to_string())) }
+            }).collect(),
+            nested_jars: loader.jars,
+            icon: loader.metadata.icon.and_then(ModIcon::get_largest),
+            source: ModSource::Quilt
+        }
+    }
+
+    /// Only the first mod of the jar is used, jars with multiple mods are rare
+    fn from_mods_toml(mut toml: ModsToml, source: ModSource, manifest: Option<&Vec<u8>>) -> Option<Self> {
+        let first = toml.mods.into_iter().next()?;
+        let dependencies = toml.dependencies.remove(&first.mod_id).unwrap_or_default();
+
+        Some(Self {
+            version: first.version.map(|version| {
+                if version == "${file.jarVersion}" {
+                    manifest.and_then(|manifest| get_manifest_value(manifest, "Implementation-Version")).unwrap_or(version)
+                } else {
+                    version
+                }
+            }),
+            id: first.mod_id,
+            name: first.display_name,
+            authors: match first.authors {
+                Some(TomlAuthors::One(authors)) => vec![authors],
+                Some(TomlAuthors::Many(authors)) => authors,
+                None => Vec::new()
+            },
+            description: first.description.map(|description| description.trim().to_string()),
+            breaks: dependencies.iter()
+                .filter(|dependency| dependency.is_incompatibility())
+                .map(|dependency| ModDependency {
+                    required: dependency.typ.as_deref().map_or(false, |typ| typ.eq_ignore_ascii_case("incompatible")),
+                    id: dependency.mod_id.to_string(),
+                    version: dependency.version_range.clone()
+                }).collect(),
+            dependencies: dependencies.into_iter()
+                .filter(|dependency| !dependency.is_incompatibility())
+                .map(|dependency| ModDependency {
+                    required: dependency.mandatory.unwrap_or_else(
+                        || dependency.typ.as_deref().map_or(true, |typ| typ.eq_ignore_ascii_case("required"))
+                    ),
+                    id: dependency.mod_id,
+                    version: dependency.version_range
+                }).collect(),
+            provides: Vec::new(),
+            nested_jars: Vec::new(),
+            icon: first.logo_file.or(toml.logo_file),
+            source
+        })
+    }
+
+    fn from_mcmod_info(info: McModInfoFile) -> Option<Self> {
+        let first = match info {
+            McModInfoFile::List(list) | McModInfoFile::V2 { mod_list: list } => list.into_iter().next()?
+        };
+
+        Some(Self {
+            id: first.modid,
+            name: first.name,
+            version: first.version,
+            authors: first.author_list,
+            description: first.description,
+            dependencies: first.required_mods.into_iter().map(|required| {
+                let (id, version) = match required.split_once('@') {
+                    Some((id, version)) => (id.to_string(), Some(version.to_string())),
+                    None => (required, None)
+                };
+                ModDependency { id, version, required: true }
+            }).collect(),
+            breaks: Vec::new(),
+            provides: Vec::new(),
+            nested_jars: Vec::new(),
+            icon: first.logo_file.filter(|file| !file.is_empty()),
+            source: ModSource::McModInfo
+        })
+    }
+}
+
+impl QuiltDependency {
+    fn into_dependency(self) -> ModDependency {
+        match self {
+            QuiltDependency::Id(id) => ModDependency { id, version: None, required: true },
+            QuiltDependency::Object { id, versions, optional } => ModDependency {
+                id,
+                version: versions.map(|versions| match versions {
+                    Value::String(version) => version,
+                    // Any of these may match, like with Fabric
+                    Value::Array(versions) => versions.iter()
+                        .map(|version| version.as_str().map_or_else(|| version.to_string(), str::to_string))
+                        .collect::<Vec<_>>()
+                        .join(" || "),
+                    other => other.to_string()
+                }),
+                required: !optional
+            }
+        }
+    }
+}
+
+impl ModsTomlDependency {
+    /// NeoForge marks mods that must not be installed with the same table as dependencies
+    fn is_incompatibility(&self) -> bool {
+        self.typ.as_deref().map_or(false, |typ| typ.eq_ignore_ascii_case("incompatible") || typ.eq_ignore_ascii_case("discouraged"))
+    }
+}
+
+impl ModIcon {
+    fn get_largest(self) -> Option<String> {
+        match self {
+            ModIcon::Path(path) => Some(path),
+            ModIcon::Sizes(sizes) => sizes.into_iter()
+                .max_by_key(|(size, _)| size.parse::<u32>().unwrap_or(0))
+                .map(|(_, path)| path)
+        }
+    }
+}
+
+/// Quite a few mods have line breaks inside of strings, which isn't valid json
+fn sanitize_json(contents: &[u8]) -> String {
+    String::from_utf8_lossy(contents).replace(['\n', '\r', '\t'], " ")
+}
+
+fn get_manifest_value(manifest: &[u8], key: &str) -> Option<String> {
+    String::from_utf8_lossy(manifest).lines()
+        .find_map(|line| line.strip_prefix(key)?.strip_prefix(':'))
+        .map(|value| value.trim().to_string())
+}
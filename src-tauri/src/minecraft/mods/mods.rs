@@ -0,0 +1,312 @@
+use std::{collections::HashMap, fs::{self, File}, io::{Cursor, Read, Seek}, path::{Path, PathBuf}};
+
+use log::{*};
+use serde::{Serialize, Deserialize};
+use sha1_smol::Sha1;
+use tauri::{AppHandle, Manager};
+use zip::ZipArchive;
+
+use crate::{get_mod_icons_dir, read_jar_files, minecraft::{instances::instances::SimpleInstance, launching::launching::RunningInstances, worlds::worlds::is_folder_name}};
+
+use super::{
+    errors::ModError,
+    metadata::{ModMetadata, ModDependency, ModSource, ProvidedMod, METADATA_FILES}
+};
+
+/// Suffix of mods that are disabled, like MultiMC and Prism do it
+pub const DISABLED_SUFFIX: &str = ".disabled";
+const MOD_EXTENSIONS: [&str; 2] = [".jar", ".zip"];
+
+
+/// A mod in the mods folder of an instance
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModInfo {
+    /// The file name without `DISABLED_SUFFIX` if the jar has no metadata
+    pub id: String,
+    /// The file name if the jar has no metadata
+    pub name: String,
+    pub version: Option<String>,
+    pub authors: Vec<String>,
+    pub description: Option<String>,
+    pub dependencies: Vec<ModDependency>,
+    pub breaks: Vec<ModDependency>,
+    /// Mod ids this one counts as, including the mods bundled inside of it
+    pub provides: Vec<ProvidedMod>,
+    /// `None` for jars without (readable) metadata
+    pub source: Option<ModSource>,
+    /// The icon extracted into the mod icon cache
+    pub icon_path: Option<String>,
+    pub file_name: String,
+    /// Same hash as used by Modrinth, empty if only the metadata was read
+    pub sha1: String,
+    pub enabled: bool
+}
+
+
+#[tauri::command(async)]
+pub async fn list_mods(instance: SimpleInstance, app_handle: AppHandle) -> Result<Vec<ModInfo>, ModError> {
+    let icons_dir = get_mod_icons_dir();
+    fs::create_dir_all(&icons_dir).map_err(|err| ModError::DirectoryCreateFailed(icons_dir.clone(), err))?;
+    app_handle.asset_protocol_scope().allow_directory(&icons_dir, false).map_err(
+        |err| ModError::PathUnlockFailed(icons_dir.clone(), err)
+    )?;
+
+    let mods = ModInfo::get_all(&instance.minecraft_path.join("mods"))?;
+    info!("Found {} mods in {}", mods.len(), instance.name);
+    Ok(mods)
+}
+
+
+/// Enables or disables the mod `file_name` by renaming it to or from `DISABLED_SUFFIX`, returns the new file name
+#[tauri::command(async)]
+pub async fn set_mod_enabled(instance: SimpleInstance, file_name: String, enabled: bool, app_handle: AppHandle) -> Result<String, ModError> {
+    ensure_stopped(&instance, &app_handle)?;
+    let mods_dir = instance.minecraft_path.join("mods");
+    let base_name = file_name.trim_end_matches(DISABLED_SUFFIX);
+    let from = find_mod_file(&mods_dir, base_name).ok_or_else(|| ModError::ModNotFound(base_name.to_string()))?;
+    let to = mod_file_path(&mods_dir, base_name, enabled);
+
+    if from != to {
+        if to.exists() {
+            return Err(ModError::RenameConflict(from, to))
+        }
+        fs::rename(&from, &to).map_err(|err| ModError::RenameFailed(from, to.clone(), err))?;
+        info!("{} {base_name} in {}", if enabled { "Enabled" } else { "Disabled" }, instance.name);
+    }
+    Ok(to.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default())
+}
+
+
+impl ModInfo {
+    /// Reads all mods in `mods_dir`, mods that can't be read are listed with what is known from their file
+    pub fn get_all(mods_dir: &Path) -> Result<Vec<Self>, ModError> {
+        Self::read_all(mods_dir, |_| true, true)
+    }
+
+    /// Like [`ModInfo::get_all`] for only the enabled mods, but without hashing them or extracting their icons.
+    /// Only the metadata inside of the jars is read, which is all that checking the mods needs.
+    pub fn get_enabled_metadata(mods_dir: &Path) -> Result<Vec<Self>, ModError> {
+        Self::read_all(mods_dir, |path| !path.to_string_lossy().ends_with(DISABLED_SUFFIX), false)
+    }
+
+    pub fn get(path: &Path) -> Result<Self, ModError> {
+        Self::read(path, true)
+    }
+
+    fn read_all(mods_dir: &Path, include: impl Fn(&Path) -> bool, full: bool) -> Result<Vec<Self>, ModError> {
+        if !mods_dir.is_dir() {
+            return Ok(Vec::new())
+        }
+
+        let entries = fs::read_dir(mods_dir).map_err(|err| ModError::DirectoryReadFailed(mods_dir.to_path_buf(), err))?;
+        let mut mods: Vec<Self> = entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.is_file() && is_mod_file(path) && include(path))
+            .filter_map(|path| Self::read(&path, full).map_err(|err| warn!("{err}")).ok())
+            .collect();
+
+        mods.sort_unstable_by_key(|info| info.name.to_lowercase());
+        Ok(mods)
+    }
+
+    /// Reads the mod at `path`, only hashing it and extracting its icon if `full` is set
+    fn read(path: &Path, full: bool) -> Result<Self, ModError> {
+        let file_name = path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+        let enabled = !file_name.ends_with(DISABLED_SUFFIX);
+        let sha1 = if full {
+            let contents = fs::read(path).map_err(|err| ModError::FileReadFailed(path.to_path_buf(), err))?;
+            Sha1::from(&contents).digest().to_string()
+        } else {
+            String::new()
+        };
+
+        let metadata = read_jar_files(path, &METADATA_FILES)
+            .map_err(|err| ModError::JarReadFailed(path.to_path_buf(), err))
+            .and_then(|files| ModMetadata::parse(path, &files));
+        let metadata = match metadata {
+            Ok(metadata) => metadata,
+            Err(err) => {
+                warn!("{err}");
+                None
+            }
+        };
+
+        let icon_path = metadata.as_ref()
+            .filter(|_| full)
+            .and_then(|metadata| metadata.icon.as_deref())
+            .and_then(|icon| get_cached_icon(path, icon, &sha1));
+
+        Ok(match metadata {
+            Some(mut metadata) => Self {
+                provides: {
+                    let nested = get_nested_mods(path, &metadata.nested_jars);
+                    metadata.provides.extend(nested);
+                    metadata.provides
+                },
+                name: metadata.name.unwrap_or_else(|| metadata.id.to_string()),
+                id: metadata.id,
+                version: metadata.version,
+                authors: metadata.authors,
+                description: metadata.description,
+                dependencies: metadata.dependencies,
+                breaks: metadata.breaks,
+                source: Some(metadata.source),
+                icon_path,
+                file_name,
+                sha1,
+                enabled
+            },
+            None => Self {
+                id: file_name.trim_end_matches(DISABLED_SUFFIX).to_string(),
+                name: file_name.to_string(),
+                version: None,
+                authors: Vec::new(),
+                description: None,
+                dependencies: Vec::new(),
+                breaks: Vec::new(),
+                provides: Vec::new(),
+                source: None,
+                icon_path: None,
+                file_name,
+                sha1,
+                enabled
+            }
+        })
+    }
+}
+
+
+pub fn is_mod_file(path: &Path) -> bool {
+    let name = path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+    let name = name.trim_end_matches(DISABLED_SUFFIX);
+    MOD_EXTENSIONS.iter().any(|extension| name.ends_with(extension))
+}
+
+/// Where the mod `base_name` (its file name without `DISABLED_SUFFIX`) lives in `mods_dir` when it is `enabled` or not
+pub fn mod_file_path(mods_dir: &Path, base_name: &str, enabled: bool) -> PathBuf {
+    if enabled {
+        mods_dir.join(base_name)
+    } else {
+        mods_dir.join(format!("{base_name}{DISABLED_SUFFIX}"))
+    }
+}
+
+/// The current file of the mod `base_name` in `mods_dir`, whether it is enabled or not.
+/// Names that aren't a mod file directly in `mods_dir`, like "../options.txt", are never found.
+pub fn find_mod_file(mods_dir: &Path, base_name: &str) -> Option<PathBuf> {
+    if !is_folder_name(base_name) || !is_mod_file(Path::new(base_name)) {
+        return None
+    }
+    [true, false].iter()
+        .map(|&enabled| mod_file_path(mods_dir, base_name, enabled))
+        .find(|path| path.is_file())
+}
+
+/// Mods can't be touched while the game has them loaded
+pub fn ensure_stopped(instance: &SimpleInstance, app_handle: &AppHandle) -> Result<(), ModError> {
+    if app_handle.state::<RunningInstances>().contains(&instance.instance_path) {
+        Err(ModError::InstanceRunning(instance.name.to_string()))
+    } else {
+        Ok(())
+    }
+}
+
+/// The mods bundled at `nested_jars` inside of the jar at `jar_path`, and the ones bundled inside of those
+fn get_nested_mods(jar_path: &Path, nested_jars: &[String]) -> Vec<ProvidedMod> {
+    if nested_jars.is_empty() {
+        return Vec::new()
+    }
+    match File::open(jar_path).map_err(|err| err.to_string()).and_then(|file| ZipArchive::new(file).map_err(|err| err.to_string())) {
+        Ok(mut archive) => read_nested_mods(jar_path, &mut archive, nested_jars),
+        Err(err) => {
+            warn!("Failed to read the bundled mods of {jar_path:?}: {err}");
+            Vec::new()
+        }
+    }
+}
+
+fn read_nested_mods<R: Read + Seek>(jar_path: &Path, archive: &mut ZipArchive<R>, nested_jars: &[String]) -> Vec<ProvidedMod> {
+    let mut provided = Vec::new();
+    for nested in nested_jars {
+        let mut contents = Vec::new();
+        if let Err(err) = archive.by_name(nested.trim_start_matches('/')).map_err(|err| err.to_string())
+            .and_then(|mut file| file.read_to_end(&mut contents).map_err(|err| err.to_string())) {
+            debug!("Skipping bundled jar {nested} of {jar_path:?}: {err}");
+            continue
+        }
+        let mut nested_archive = match ZipArchive::new(Cursor::new(contents)) {
+            Ok(archive) => archive,
+            Err(err) => {
+                debug!("Skipping bundled jar {nested} of {jar_path:?}: {err}");
+                continue
+            }
+        };
+
+        let files: HashMap<String, Vec<u8>> = METADATA_FILES.iter().filter_map(|file| {
+            let mut contents = Vec::new();
+            nested_archive.by_name(file).ok()?.read_to_end(&mut contents).ok()?;
+            Some((file.to_string(), contents))
+        }).collect();
+        match ModMetadata::parse(jar_path, &files) {
+            Ok(Some(metadata)) => {
+                provided.push(ProvidedMod { id: metadata.id, version: metadata.version });
+                provided.extend(metadata.provides);
+                provided.extend(read_nested_mods(jar_path, &mut nested_archive, &metadata.nested_jars));
+            },
+            Ok(None) => {},
+            Err(err) => debug!("{err}")
+        }
+    }
+    provided
+}
+
+/// Extracts `icon` from the jar at `jar_path` into the mod icon cache, named after the hash of the jar
+fn get_cached_icon(jar_path: &Path, icon: &str, sha1: &str) -> Option<String> {
+    let extension = Path::new(icon).extension().map_or("png".into(), |ext| ext.to_string_lossy().to_string());
+    let cached = get_mod_icons_dir().join(format!("{sha1}.{extension}"));
+    if cached.is_file() {
+        return Some(cached.to_string_lossy().to_string())
+    }
+
+    let icon = icon.trim_start_matches('/');
+    let contents = read_jar_files(jar_path, &[icon]).ok()?.remove(icon)?;
+    match fs::write(&cached, contents) {
+        Ok(_) => Some(cached.to_string_lossy().to_string()),
+        Err(err) => {
+            warn!("{}", ModError::FileWriteFailed(cached, err));
+            None
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::tests::test_dir;
+
+    use super::*;
+
+    #[test]
+    fn finds_only_mod_files_in_the_mods_folder() {
+        let dir = test_dir("find-mod-file");
+        let mods_dir = dir.join("mods");
+        fs::create_dir_all(&mods_dir).unwrap();
+        fs::write(mods_dir.join("enabled.jar"), b"").unwrap();
+        fs::write(mods_dir.join("disabled.jar.disabled"), b"").unwrap();
+        fs::write(mods_dir.join("notes.txt"), b"").unwrap();
+        fs::write(dir.join("outside.jar"), b"").unwrap();
+
+        assert_eq!(find_mod_file(&mods_dir, "enabled.jar"), Some(mods_dir.join("enabled.jar")));
+        assert_eq!(find_mod_file(&mods_dir, "disabled.jar"), Some(mods_dir.join("disabled.jar.disabled")));
+        assert_eq!(find_mod_file(&mods_dir, "notes.txt"), None);
+        assert_eq!(find_mod_file(&mods_dir, "../outside.jar"), None);
+        assert_eq!(find_mod_file(&mods_dir, "../../options.txt"), None);
+        assert_eq!(find_mod_file(&mods_dir, &dir.join("outside.jar").to_string_lossy()), None);
+        assert_eq!(find_mod_file(&mods_dir, ""), None);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
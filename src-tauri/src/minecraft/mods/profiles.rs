@@ -0,0 +1,130 @@
+use std::{collections::{BTreeMap, HashSet}, fs, path::{Path, PathBuf}};
+
+use log::{*};
+use serde::{Serialize, Deserialize};
+use tauri::AppHandle;
+
+use crate::minecraft::instances::{instances::SimpleInstance, metadata::InstanceMetadata};
+
+use super::{errors::ModError, mods::{DISABLED_SUFFIX, ensure_stopped, find_mod_file, is_mod_file, mod_file_path}};
+
+
+/// A named set of enabled mods, stored in the `InstanceMetadata` of an instance
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ModProfile {
+    /// The file names of the enabled mods, without `DISABLED_SUFFIX`
+    pub enabled: Vec<String>
+}
+
+
+#[tauri::command(async)]
+pub async fn get_mod_profiles(instance: SimpleInstance) -> Result<BTreeMap<String, ModProfile>, ModError> {
+    Ok(InstanceMetadata::get(&instance.instance_path).await?.mod_profiles)
+}
+
+/// Saves the mods that are currently enabled as the profile `name`, replacing it if it already exists
+#[tauri::command(async)]
+pub async fn save_mod_profile(instance: SimpleInstance, name: String) -> Result<ModProfile, ModError> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(ModError::InvalidProfileName)
+    }
+
+    let enabled = get_mod_files(&instance.minecraft_path.join("mods"))?
+        .into_iter()
+        .filter(|file_name| !file_name.ends_with(DISABLED_SUFFIX))
+        .collect();
+    let profile = ModProfile { enabled };
+
+    let mut meta = InstanceMetadata::get(&instance.instance_path).await?;
+    meta.mod_profiles.insert(name.to_string(), profile.clone());
+    meta.save(&instance.instance_path).await?;
+    info!("Saved mod profile {name} of {} with {} mods", instance.name, profile.enabled.len());
+    Ok(profile)
+}
+
+#[tauri::command(async)]
+pub async fn delete_mod_profile(instance: SimpleInstance, name: String) -> Result<(), ModError> {
+    let mut meta = InstanceMetadata::get(&instance.instance_path).await?;
+    if meta.mod_profiles.remove(&name).is_none() {
+        return Err(ModError::ProfileNotFound(name))
+    }
+    Ok(meta.save(&instance.instance_path).await?)
+}
+
+/// Enables exactly the mods of the profile `name`
+#[tauri::command(async)]
+pub async fn apply_mod_profile(instance: SimpleInstance, name: String, app_handle: AppHandle) -> Result<(), ModError> {
+    ensure_stopped(&instance, &app_handle)?;
+    let meta = InstanceMetadata::get(&instance.instance_path).await?;
+    let profile = meta.mod_profiles.get(&name).ok_or_else(|| ModError::ProfileNotFound(name.to_string()))?;
+    let enabled: HashSet<&str> = profile.enabled.iter().map(String::as_str).collect();
+
+    let mods_dir = instance.minecraft_path.join("mods");
+    let missing = enabled.iter().filter(|base_name| find_mod_file(&mods_dir, base_name).is_none()).count();
+    if missing > 0 {
+        warn!("{missing} mods of profile {name} are no longer in {}", instance.name);
+    }
+
+    let changed = set_enabled_mods(&mods_dir, &enabled)?;
+    info!("Applied mod profile {name} to {} ({changed} mods changed)", instance.name);
+    Ok(())
+}
+
+
+/// Enables exactly the mods in `enabled` (file names without `DISABLED_SUFFIX`) and disables all others.
+/// Either all mods are renamed or none are, returns how many were.
+pub fn set_enabled_mods(mods_dir: &Path, enabled: &HashSet<&str>) -> Result<usize, ModError> {
+    let renames = get_renames(mods_dir, enabled)?;
+    rename_all(&renames)?;
+    Ok(renames.len())
+}
+
+
+/// The renames needed so that exactly the mods in `enabled` are enabled.
+/// Fails if a mod is there both enabled and disabled, since renaming one would replace the other.
+fn get_renames(mods_dir: &Path, enabled: &HashSet<&str>) -> Result<Vec<(PathBuf, PathBuf)>, ModError> {
+    let renames: Vec<(PathBuf, PathBuf)> = get_mod_files(mods_dir)?.into_iter().filter_map(|file_name| {
+        let base_name = file_name.trim_end_matches(DISABLED_SUFFIX);
+        let should_enable = enabled.contains(base_name);
+        if should_enable == (base_name == file_name) {
+            None
+        } else {
+            Some((mods_dir.join(&file_name), mod_file_path(mods_dir, base_name, should_enable)))
+        }
+    }).collect();
+
+    if let Some((from, to)) = renames.iter().find(|(_, to)| to.exists()) {
+        return Err(ModError::RenameConflict(from.to_path_buf(), to.to_path_buf()))
+    }
+    Ok(renames)
+}
+
+/// The file names of all mods in `mods_dir`, enabled or not
+fn get_mod_files(mods_dir: &Path) -> Result<Vec<String>, ModError> {
+    if !mods_dir.is_dir() {
+        return Ok(Vec::new())
+    }
+    let entries = fs::read_dir(mods_dir).map_err(|err| ModError::DirectoryReadFailed(mods_dir.to_path_buf(), err))?;
+    Ok(entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file() && is_mod_file(path))
+        .filter_map(|path| path.file_name().map(|name| name.to_string_lossy().to_string()))
+        .collect())
+}
+
+/// Performs all `renames`, undoing the ones that were done if one fails
+fn rename_all(renames: &[(PathBuf, PathBuf)]) -> Result<(), ModError> {
+    for (index, (from, to)) in renames.iter().enumerate() {
+        if let Err(err) = fs::rename(from, to) {
+            for (from, to) in renames[..index].iter().rev() {
+                if let Err(err) = fs::rename(to, from) {
+                    error!("{}", ModError::RenameFailed(to.to_path_buf(), from.to_path_buf(), err));
+                }
+            }
+            return Err(ModError::RenameFailed(from.to_path_buf(), to.to_path_buf(), err))
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,303 @@
+use std::cmp::Ordering;
+
+/// A loosely parsed version, good enough to compare the versions that mods, loaders and Minecraft use
+#[derive(Debug, Clone)]
+pub struct Version {
+    parts: Vec<VersionPart>,
+    /// Everything after the first `-`, a pre-release sorts before the release
+    pre_release: Option<String>
+}
+
+#[derive(Debug, Clone)]
+enum VersionPart {
+    Number(u64),
+    Text(String)
+}
+
+
+impl Version {
+    pub fn parse(version: &str) -> Self {
+        let version = version.trim().trim_start_matches(['v', 'V']);
+        // Build metadata doesn't count when comparing
+        let version = version.split('+').next().unwrap_or_default();
+        let (core, pre_release) = match version.split_once('-') {
+            Some((core, pre_release)) => (core, Some(pre_release.to_string())),
+            None => (version, None)
+        };
+
+        Self {
+            parts: core.split('.').map(|part| match part.parse() {
+                Ok(number) => VersionPart::Number(number),
+                Err(_) => VersionPart::Text(part.to_string())
+            }).collect(),
+            pre_release
+        }
+    }
+
+    /// Whether the first `count` parts of both versions are the same
+    fn same_prefix(&self, other: &Self, count: usize) -> bool {
+        (0..count).all(|index| self.part(index).cmp(&other.part(index)) == Ordering::Equal)
+    }
+
+    fn part(&self, index: usize) -> VersionPart {
+        self.parts.get(index).cloned().unwrap_or(VersionPart::Number(0))
+    }
+
+    /// How many parts `^` keeps the same: all up to the first one that isn't 0, so "^0.2.1" only allows "0.2.x"
+    fn caret_prefix(&self) -> usize {
+        self.parts.iter().position(|part| *part != VersionPart::Number(0)).map_or(self.parts.len(), |index| index + 1)
+    }
+}
+
+impl PartialEq for Version {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Version {}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Version {
+    /// Missing parts count as 0, so "1.20" is the same as "1.20.0"
+    fn cmp(&self, other: &Self) -> Ordering {
+        let length = self.parts.len().max(other.parts.len());
+        (0..length)
+            .map(|index| self.part(index).cmp(&other.part(index)))
+            .find(|ordering| *ordering != Ordering::Equal)
+            .unwrap_or_else(|| match (&self.pre_release, &other.pre_release) {
+                (None, None) => Ordering::Equal,
+                (None, Some(_)) => Ordering::Greater,
+                (Some(_), None) => Ordering::Less,
+                (Some(a), Some(b)) => a.cmp(b)
+            })
+    }
+}
+
+impl PartialEq for VersionPart {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for VersionPart {}
+
+impl PartialOrd for VersionPart {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for VersionPart {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (VersionPart::Number(a), VersionPart::Number(b)) => a.cmp(b),
+            (VersionPart::Number(_), VersionPart::Text(_)) => Ordering::Greater,
+            (VersionPart::Text(_), VersionPart::Number(_)) => Ordering::Less,
+            (VersionPart::Text(a), VersionPart::Text(b)) => a.cmp(b)
+        }
+    }
+}
+
+
+/// Whether `version` matches a Fabric/Quilt version predicate like ">=1.20 <1.21 || 1.19.x".
+/// Predicates that can't be understood always match, so they never cause false alarms.
+pub fn matches_semver(predicate: &str, version: &Version) -> bool {
+    predicate.split("||").any(|alternative| {
+        alternative.split_whitespace().all(|term| matches_semver_term(term, version))
+    })
+}
+
+fn matches_semver_term(term: &str, version: &Version) -> bool {
+    let operators = [">=", "<=", ">", "<", "=", "^", "~"];
+    let (operator, target) = match operators.iter().find(|operator| term.starts_with(*operator)) {
+        Some(operator) => (*operator, &term[operator.len()..]),
+        None => ("", term)
+    };
+
+    // "1.20.x" only compares the parts before the wildcard
+    let wildcard = target.split('.').position(|part| matches!(part, "x" | "X" | "*"));
+    let target_version = match wildcard {
+        Some(index) => Version::parse(&target.split('.').take(index).collect::<Vec<_>>().join(".")),
+        None => Version::parse(target)
+    };
+
+    match (operator, wildcard) {
+        (_, Some(0)) => true,
+        ("" | "=", Some(index)) => version.same_prefix(&target_version, index),
+        ("" | "=", None) => *version == target_version,
+        (">=", _) => *version >= target_version,
+        ("<=", _) => *version <= target_version,
+        (">", _) => *version > target_version,
+        ("<", _) => *version < target_version,
+        ("^", _) => *version >= target_version && version.same_prefix(&target_version, target_version.caret_prefix()),
+        // "~1" allows any 1.x, "~1.2" and "~1.2.3" only 1.2.x
+        ("~", _) => *version >= target_version && version.same_prefix(&target_version, target_version.parts.len().min(2)),
+        _ => true
+    }
+}
+
+/// Whether `version` is inside of a Maven version range like "[1.20,1.21)" or "[47,)", as used by (Neo)Forge.
+/// A plain version is only a recommendation, so it matches everything.
+pub fn matches_maven(range: &str, version: &Version) -> bool {
+    let range = range.trim();
+    if range.is_empty() || range == "*" || !range.starts_with(['[', '(']) {
+        return true
+    }
+
+    // Multiple ranges are separated by commas, any of them may match
+    let mut rest = range;
+    let mut any_parsed = false;
+    while let Some(start) = rest.find(['[', '(']) {
+        let end = match rest[start..].find([']', ')']) {
+            Some(end) => start + end,
+            None => break
+        };
+        any_parsed = true;
+        if matches_maven_range(&rest[start..=end], version) {
+            return true
+        }
+        rest = &rest[end + 1..];
+    }
+    !any_parsed
+}
+
+fn matches_maven_range(range: &str, version: &Version) -> bool {
+    let lower_inclusive = range.starts_with('[');
+    let upper_inclusive = range.ends_with(']');
+    let inner = &range[1..range.len() - 1];
+
+    match inner.split_once(',') {
+        Some((lower, upper)) => {
+            let (lower, upper) = (lower.trim(), upper.trim());
+            let above = lower.is_empty() || match version.cmp(&Version::parse(lower)) {
+                Ordering::Greater => true,
+                Ordering::Equal => lower_inclusive,
+                Ordering::Less => false
+            };
+            let below = upper.is_empty() || match version.cmp(&Version::parse(upper)) {
+                Ordering::Less => true,
+                Ordering::Equal => upper_inclusive,
+                Ordering::Greater => false
+            };
+            above && below
+        },
+        // "[1.20.1]" is exactly that version
+        None => *version == Version::parse(inner)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compares_versions() {
+        let ordered = ["1.19", "1.19.4", "1.20-", "1.20-pre1", "1.20-rc1", "1.20", "1.20.0.1", "1.20.1", "v1.21+build.5"];
+        for pair in ordered.windows(2) {
+            assert!(Version::parse(pair[0]) < Version::parse(pair[1]), "{} < {}", pair[0], pair[1]);
+        }
+        assert_eq!(Version::parse("1.20"), Version::parse("1.20.0"));
+        assert_eq!(Version::parse("1.20.1+build.3"), Version::parse("1.20.1"));
+    }
+
+    #[test]
+    fn matches_semver_predicates() {
+        let cases = [
+            ("1.20.1", "1.20.1", true),
+            ("=1.20.1", "1.20.2", false),
+            ("1.20", "1.20.0", true),
+            (">=1.20", "1.20.4", true),
+            (">=1.20", "1.19.4", false),
+            ("<1.20", "1.19.4", true),
+            ("<=1.20", "1.20.1", false),
+            (">1.20 <1.21", "1.20.6", true),
+            (">1.20 <1.21", "1.21", false),
+            // Tilde allows patch versions, or minor versions if there is no minor version
+            ("~1.20.1", "1.20.1", true),
+            ("~1.20.1", "1.20.4", true),
+            ("~1.20.1", "1.20", false),
+            ("~1.20.1", "1.21", false),
+            ("~1.20", "1.20.6", true),
+            ("~1", "1.5", true),
+            ("~1", "2.0", false),
+            // Caret allows everything up to the next version of the first part that isn't 0
+            ("^1.2.3", "1.2.3", true),
+            ("^1.2.3", "1.9.0", true),
+            ("^1.2.3", "1.2.2", false),
+            ("^1.2.3", "2.0.0", false),
+            ("^0.2.3", "0.2.9", true),
+            ("^0.2.3", "0.3.0", false),
+            ("^0.2.3", "1.0.0", false),
+            ("^0.0.3", "0.0.3", true),
+            ("^0.0.3", "0.0.4", false),
+            ("^0.0", "0.0.7", true),
+            ("^0.0", "0.1", false),
+            ("^0", "0.9", true),
+            ("^0", "1.0", false),
+            // Wildcards
+            ("1.20.x", "1.20.4", true),
+            ("1.20.x", "1.20", true),
+            ("1.20.x", "1.21", false),
+            ("1.X", "1.19.2", true),
+            ("1.*", "2.0", false),
+            ("*", "0.1", true),
+            ("x", "1.20.1", true),
+            (">=1.19.x", "1.19", true),
+            // Alternatives
+            ("1.19.x || 1.20.x", "1.20.1", true),
+            ("1.19.x || 1.20.x", "1.18.2", false),
+            (">=1.20 <1.21 || 1.18.2", "1.18.2", true),
+            (">=1.20 <1.21 || 1.18.2", "1.21", false),
+            // A trailing `-` includes the pre-releases of a version
+            (">=1.20-", "1.20-pre1", true),
+            (">=1.20-", "1.20", true),
+            (">=1.20-", "1.19.4", false),
+            (">=1.20", "1.20-pre1", false),
+            ("<1.20-", "1.20-rc1", false),
+            ("<1.20-", "1.19.4", true)
+        ];
+
+        for (predicate, version, expected) in cases {
+            assert_eq!(matches_semver(predicate, &Version::parse(version)), expected, "{predicate} against {version}");
+        }
+    }
+
+    #[test]
+    fn matches_maven_ranges() {
+        let cases = [
+            ("[1.20,1.21)", "1.20", true),
+            ("[1.20,1.21)", "1.20.6", true),
+            ("[1.20,1.21)", "1.21", false),
+            ("[1.20,1.21)", "1.19.4", false),
+            ("(1.20,1.21]", "1.20", false),
+            ("(1.20,1.21]", "1.21", true),
+            ("[47,)", "47.1.0", true),
+            ("[47,)", "46.9", false),
+            ("(,1.0]", "0.9", true),
+            ("(,1.0]", "1.0", true),
+            ("(,1.0]", "1.1", false),
+            ("(,1.0],[1.2,)", "1.0", true),
+            ("(,1.0],[1.2,)", "1.1", false),
+            ("(,1.0],[1.2,)", "1.2", true),
+            ("(,1.0],[1.2,)", "5", true),
+            ("[1.20.1]", "1.20.1", true),
+            ("[1.20.1]", "1.20.2", false),
+            ("[1.20.1]", "1.20", false),
+            // Plain versions are only recommendations
+            ("1.20.1", "1.19", true),
+            ("", "1.19", true),
+            ("*", "1.19", true)
+        ];
+
+        for (range, version, expected) in cases {
+            assert_eq!(matches_maven(range, &Version::parse(version)), expected, "{range} against {version}");
+        }
+    }
+}
@@ -0,0 +1,583 @@
+use std::io::{self, Read, Write};
+
+use flate2::{read::{GzDecoder, ZlibDecoder}, write::{GzEncoder, ZlibEncoder}};
+use thiserror::Error;
+
+/// Compounds nested deeper than this are refused, so broken files can't overflow the stack
+const MAX_DEPTH: usize = 512;
+
+pub const TAG_END: u8 = 0;
+pub const TAG_BYTE: u8 = 1;
+pub const TAG_SHORT: u8 = 2;
+pub const TAG_INT: u8 = 3;
+pub const TAG_LONG: u8 = 4;
+pub const TAG_FLOAT: u8 = 5;
+pub const TAG_DOUBLE: u8 = 6;
+pub const TAG_BYTE_ARRAY: u8 = 7;
+pub const TAG_STRING: u8 = 8;
+pub const TAG_LIST: u8 = 9;
+pub const TAG_COMPOUND: u8 = 10;
+pub const TAG_INT_ARRAY: u8 = 11;
+pub const TAG_LONG_ARRAY: u8 = 12;
+
+
+/// A value of Minecraft's big-endian "Named Binary Tag" format, as used by level.dat, servers.dat and others
+#[derive(Debug, Clone, PartialEq)]
+pub enum Tag {
+    Byte(i8),
+    Short(i16),
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    ByteArray(Vec<i8>),
+    String(String),
+    /// The type id of the elements is kept, since empty lists have one as well
+    List(u8, Vec<Tag>),
+    Compound(Compound),
+    IntArray(Vec<i32>),
+    LongArray(Vec<i64>)
+}
+
+/// Named tags, in the order they were read
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Compound(Vec<(String, Tag)>);
+
+#[derive(Debug, Error)]
+pub enum NbtError {
+    #[error("Failed to read NBT: {0}")]
+    Io(#[from] io::Error),
+    #[error("Unknown NBT tag type {0}")]
+    UnknownTag(u8),
+    #[error("The root of the NBT data is not a compound")]
+    InvalidRoot,
+    #[error("Negative length {0} in NBT data")]
+    NegativeLength(i32),
+    #[error("NBT data is nested too deeply")]
+    TooDeep,
+    #[error("The string \"{0}...\" is too long for NBT")]
+    StringTooLong(String),
+    #[error("The list or array of {0} elements is too long for NBT")]
+    ListTooLong(usize),
+    #[error("NBT list of type {0} contains a tag of type {1}")]
+    InvalidList(u8, u8)
+}
+
+/// How an NBT file is compressed
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Compression {
+    Gzip,
+    Zlib,
+    None
+}
+
+
+impl Compression {
+    /// Guesses the compression from the first bytes of `data`
+    pub fn detect(data: &[u8]) -> Self {
+        match data {
+            [0x1f, 0x8b, ..] => Compression::Gzip,
+            [0x78, ..] => Compression::Zlib,
+            _ => Compression::None
+        }
+    }
+}
+
+/// Reads a whole NBT file, compressed with gzip, zlib or not at all. Returns the name and contents of its root compound.
+pub fn read(data: &[u8]) -> Result<(String, Compound), NbtError> {
+    let mut decompressed = Vec::new();
+    match Compression::detect(data) {
+        Compression::Gzip => { GzDecoder::new(data).read_to_end(&mut decompressed)?; },
+        Compression::Zlib => { ZlibDecoder::new(data).read_to_end(&mut decompressed)?; },
+        Compression::None => decompressed.extend_from_slice(data)
+    }
+
+    let mut reader = decompressed.as_slice();
+    if read_u8(&mut reader)? != TAG_COMPOUND {
+        return Err(NbtError::InvalidRoot)
+    }
+    let name = read_string(&mut reader)?;
+    match read_payload(&mut reader, TAG_COMPOUND, 0)? {
+        Tag::Compound(root) => Ok((name, root)),
+        _ => Err(NbtError::InvalidRoot)
+    }
+}
+
+/// Writes `root` called `name` as a whole NBT file, the game compresses most of them with gzip
+pub fn write(name: &str, root: &Compound, compression: Compression) -> Result<Vec<u8>, NbtError> {
+    let mut data = vec![TAG_COMPOUND];
+    write_string(&mut data, name)?;
+    write_compound(&mut data, root)?;
+
+    Ok(match compression {
+        Compression::Gzip => {
+            let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(&data)?;
+            encoder.finish()?
+        },
+        Compression::Zlib => {
+            let mut encoder = ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(&data)?;
+            encoder.finish()?
+        },
+        Compression::None => data
+    })
+}
+
+
+impl Tag {
+    pub fn get_id(&self) -> u8 {
+        match self {
+            Tag::Byte(_) => TAG_BYTE,
+            Tag::Short(_) => TAG_SHORT,
+            Tag::Int(_) => TAG_INT,
+            Tag::Long(_) => TAG_LONG,
+            Tag::Float(_) => TAG_FLOAT,
+            Tag::Double(_) => TAG_DOUBLE,
+            Tag::ByteArray(_) => TAG_BYTE_ARRAY,
+            Tag::String(_) => TAG_STRING,
+            Tag::List(..) => TAG_LIST,
+            Tag::Compound(_) => TAG_COMPOUND,
+            Tag::IntArray(_) => TAG_INT_ARRAY,
+            Tag::LongArray(_) => TAG_LONG_ARRAY
+        }
+    }
+
+    /// Any of the integer types
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Tag::Byte(value) => Some(*value as i64),
+            Tag::Short(value) => Some(*value as i64),
+            Tag::Int(value) => Some(*value as i64),
+            Tag::Long(value) => Some(*value),
+            _ => None
+        }
+    }
+
+    /// Booleans are stored as bytes
+    pub fn as_bool(&self) -> Option<bool> {
+        self.as_i64().map(|value| value != 0)
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Tag::String(value) => Some(value),
+            _ => None
+        }
+    }
+
+    pub fn as_compound(&self) -> Option<&Compound> {
+        match self {
+            Tag::Compound(compound) => Some(compound),
+            _ => None
+        }
+    }
+
+    pub fn as_compound_mut(&mut self) -> Option<&mut Compound> {
+        match self {
+            Tag::Compound(compound) => Some(compound),
+            _ => None
+        }
+    }
+
+    pub fn as_list(&self) -> Option<&[Tag]> {
+        match self {
+            Tag::List(_, items) => Some(items),
+            _ => None
+        }
+    }
+}
+
+impl Compound {
+    pub fn get(&self, name: &str) -> Option<&Tag> {
+        self.0.iter().find(|(key, _)| key == name).map(|(_, tag)| tag)
+    }
+
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut Tag> {
+        self.0.iter_mut().find(|(key, _)| key == name).map(|(_, tag)| tag)
+    }
+
+    /// Follows `path` through nested compounds, like `["Data", "Version", "Name"]`
+    pub fn get_path(&self, path: &[&str]) -> Option<&Tag> {
+        let (last, parents) = path.split_last()?;
+        parents.iter()
+            .try_fold(self, |compound, name| compound.get(name)?.as_compound())?
+            .get(last)
+    }
+
+    pub fn get_compound(&self, name: &str) -> Option<&Compound> {
+        self.get(name)?.as_compound()
+    }
+
+    pub fn get_compound_mut(&mut self, name: &str) -> Option<&mut Compound> {
+        self.get_mut(name)?.as_compound_mut()
+    }
+
+    /// Replaces the tag called `name` where it is, or adds it at the end
+    pub fn insert(&mut self, name: &str, tag: Tag) {
+        match self.get_mut(name) {
+            Some(existing) => *existing = tag,
+            None => self.0.push((name.to_string(), tag))
+        }
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<Tag> {
+        let index = self.0.iter().position(|(key, _)| key == name)?;
+        Some(self.0.remove(index).1)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &Tag)> {
+        self.0.iter().map(|(key, tag)| (key, tag))
+    }
+}
+
+
+fn read_payload(reader: &mut &[u8], id: u8, depth: usize) -> Result<Tag, NbtError> {
+    if depth > MAX_DEPTH {
+        return Err(NbtError::TooDeep)
+    }
+
+    // Only lists and compounds recurse, everything else is read by another function,
+    // so that the stack used per level of nesting stays small enough for debug builds too
+    match id {
+        TAG_LIST => read_list(reader, depth),
+        TAG_COMPOUND => read_compound(reader, depth).map(Tag::Compound),
+        _ => read_value(reader, id)
+    }
+}
+
+fn read_list(reader: &mut &[u8], depth: usize) -> Result<Tag, NbtError> {
+    let element_id = read_u8(reader)?;
+    let length = read_length(reader)?;
+    let mut items = Vec::new();
+    for _ in 0..length {
+        items.push(read_payload(reader, element_id, depth + 1)?);
+    }
+    Ok(Tag::List(element_id, items))
+}
+
+fn read_compound(reader: &mut &[u8], depth: usize) -> Result<Compound, NbtError> {
+    let mut compound = Compound::default();
+    loop {
+        let element_id = read_u8(reader)?;
+        if element_id == TAG_END {
+            return Ok(compound)
+        }
+        let name = read_string(reader)?;
+        let tag = read_payload(reader, element_id, depth + 1)?;
+        compound.0.push((name, tag));
+    }
+}
+
+fn read_value(reader: &mut &[u8], id: u8) -> Result<Tag, NbtError> {
+    Ok(match id {
+        TAG_BYTE => Tag::Byte(read_u8(reader)? as i8),
+        TAG_SHORT => Tag::Short(i16::from_be_bytes(read_array(reader)?)),
+        TAG_INT => Tag::Int(read_i32(reader)?),
+        TAG_LONG => Tag::Long(i64::from_be_bytes(read_array(reader)?)),
+        TAG_FLOAT => Tag::Float(f32::from_be_bytes(read_array(reader)?)),
+        TAG_DOUBLE => Tag::Double(f64::from_be_bytes(read_array(reader)?)),
+        TAG_BYTE_ARRAY => {
+            let length = read_length(reader)?;
+            Tag::ByteArray(read_bytes(reader, length)?.iter().map(|byte| *byte as i8).collect())
+        },
+        TAG_STRING => Tag::String(read_string(reader)?),
+        TAG_INT_ARRAY => {
+            let length = read_length(reader)?;
+            Tag::IntArray((0..length).map(|_| read_i32(reader)).collect::<Result<_, _>>()?)
+        },
+        TAG_LONG_ARRAY => {
+            let length = read_length(reader)?;
+            Tag::LongArray((0..length).map(|_| Ok(i64::from_be_bytes(read_array(reader)?))).collect::<Result<_, NbtError>>()?)
+        },
+        other => return Err(NbtError::UnknownTag(other))
+    })
+}
+
+fn read_bytes<'a>(reader: &mut &'a [u8], length: usize) -> Result<&'a [u8], NbtError> {
+    if reader.len() < length {
+        return Err(NbtError::Io(io::ErrorKind::UnexpectedEof.into()))
+    }
+    let (bytes, rest) = reader.split_at(length);
+    *reader = rest;
+    Ok(bytes)
+}
+
+fn read_array<const N: usize>(reader: &mut &[u8]) -> Result<[u8; N], NbtError> {
+    let mut array = [0; N];
+    array.copy_from_slice(read_bytes(reader, N)?);
+    Ok(array)
+}
+
+fn read_u8(reader: &mut &[u8]) -> Result<u8, NbtError> {
+    Ok(read_array::<1>(reader)?[0])
+}
+
+fn read_i32(reader: &mut &[u8]) -> Result<i32, NbtError> {
+    Ok(i32::from_be_bytes(read_array(reader)?))
+}
+
+fn read_length(reader: &mut &[u8]) -> Result<usize, NbtError> {
+    let length = read_i32(reader)?;
+    if length < 0 {
+        return Err(NbtError::NegativeLength(length))
+    }
+    Ok(length as usize)
+}
+
+fn write_payload(writer: &mut Vec<u8>, tag: &Tag) -> Result<(), NbtError> {
+    match tag {
+        Tag::Byte(value) => writer.push(*value as u8),
+        Tag::Short(value) => writer.extend_from_slice(&value.to_be_bytes()),
+        Tag::Int(value) => writer.extend_from_slice(&value.to_be_bytes()),
+        Tag::Long(value) => writer.extend_from_slice(&value.to_be_bytes()),
+        Tag::Float(value) => writer.extend_from_slice(&value.to_be_bytes()),
+        Tag::Double(value) => writer.extend_from_slice(&value.to_be_bytes()),
+        Tag::ByteArray(values) => {
+            write_length(writer, values.len())?;
+            writer.extend(values.iter().map(|value| *value as u8));
+        },
+        Tag::String(value) => write_string(writer, value)?,
+        Tag::List(element_id, items) => {
+            writer.push(*element_id);
+            write_length(writer, items.len())?;
+            for item in items {
+                if item.get_id() != *element_id {
+                    return Err(NbtError::InvalidList(*element_id, item.get_id()))
+                }
+                write_payload(writer, item)?;
+            }
+        },
+        Tag::Compound(compound) => write_compound(writer, compound)?,
+        Tag::IntArray(values) => {
+            write_length(writer, values.len())?;
+            values.iter().for_each(|value| writer.extend_from_slice(&value.to_be_bytes()));
+        },
+        Tag::LongArray(values) => {
+            write_length(writer, values.len())?;
+            values.iter().for_each(|value| writer.extend_from_slice(&value.to_be_bytes()));
+        }
+    }
+    Ok(())
+}
+
+fn write_compound(writer: &mut Vec<u8>, compound: &Compound) -> Result<(), NbtError> {
+    for (name, tag) in compound.iter() {
+        writer.push(tag.get_id());
+        write_string(writer, name)?;
+        write_payload(writer, tag)?;
+    }
+    writer.push(TAG_END);
+    Ok(())
+}
+
+fn write_length(writer: &mut Vec<u8>, length: usize) -> Result<(), NbtError> {
+    let length = i32::try_from(length).map_err(|_| NbtError::ListTooLong(length))?;
+    writer.extend_from_slice(&length.to_be_bytes());
+    Ok(())
+}
+
+fn write_string(writer: &mut Vec<u8>, string: &str) -> Result<(), NbtError> {
+    let bytes = encode_modified_utf8(string);
+    let length = u16::try_from(bytes.len()).map_err(|_| NbtError::StringTooLong(string.chars().take(32).collect()))?;
+    writer.extend_from_slice(&length.to_be_bytes());
+    writer.extend_from_slice(&bytes);
+    Ok(())
+}
+
+/// Strings are prefixed with their length and encoded in Java's "modified UTF-8"
+fn read_string(reader: &mut &[u8]) -> Result<String, NbtError> {
+    let length = u16::from_be_bytes(read_array(reader)?) as usize;
+    let bytes = read_bytes(reader, length)?;
+    Ok(match std::str::from_utf8(bytes) {
+        Ok(string) => string.to_string(),
+        Err(_) => decode_modified_utf8(bytes)
+    })
+}
+
+/// Differs from UTF-8 in encoding null as two bytes and characters outside of the BMP as two surrogates of three bytes each
+fn decode_modified_utf8(bytes: &[u8]) -> String {
+    let mut units = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        let byte = bytes[index] as u16;
+        let next = |offset: usize| bytes.get(index + offset).map_or(0, |byte| (*byte & 0x3f) as u16);
+        if byte & 0x80 == 0 {
+            units.push(byte);
+            index += 1;
+        } else if byte & 0xe0 == 0xc0 {
+            units.push(((byte & 0x1f) << 6) | next(1));
+            index += 2;
+        } else {
+            units.push(((byte & 0x0f) << 12) | (next(1) << 6) | next(2));
+            index += 3;
+        }
+    }
+    String::from_utf16_lossy(&units)
+}
+
+fn encode_modified_utf8(string: &str) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(string.len());
+    for unit in string.encode_utf16() {
+        match unit {
+            0x01..=0x7f => bytes.push(unit as u8),
+            0x00 | 0x80..=0x7ff => bytes.extend_from_slice(&[0xc0 | (unit >> 6) as u8, 0x80 | (unit & 0x3f) as u8]),
+            _ => bytes.extend_from_slice(&[0xe0 | (unit >> 12) as u8, 0x80 | ((unit >> 6) & 0x3f) as u8, 0x80 | (unit & 0x3f) as u8])
+        }
+    }
+    bytes
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn compound(tags: Vec<(&str, Tag)>) -> Compound {
+        Compound(tags.into_iter().map(|(name, tag)| (name.to_string(), tag)).collect())
+    }
+
+    fn every_tag() -> Compound {
+        compound(vec![
+            ("byte", Tag::Byte(-128)),
+            ("short", Tag::Short(i16::MIN)),
+            ("int", Tag::Int(i32::MAX)),
+            ("long", Tag::Long(-4_611_686_018_427_387_904)),
+            ("float", Tag::Float(0.5)),
+            ("double", Tag::Double(-1.25e300)),
+            ("byte array", Tag::ByteArray(vec![-1, 0, 1])),
+            ("empty byte array", Tag::ByteArray(Vec::new())),
+            ("string", Tag::String("level name".into())),
+            ("empty string", Tag::String(String::new())),
+            ("list", Tag::List(TAG_DOUBLE, vec![Tag::Double(1.0), Tag::Double(2.0)])),
+            ("empty list", Tag::List(TAG_END, Vec::new())),
+            ("empty typed list", Tag::List(TAG_COMPOUND, Vec::new())),
+            ("list of lists", Tag::List(TAG_LIST, vec![Tag::List(TAG_INT, vec![Tag::Int(1)]), Tag::List(TAG_END, Vec::new())])),
+            ("list of compounds", Tag::List(TAG_COMPOUND, vec![Tag::Compound(compound(vec![("id", Tag::String("minecraft:stone".into()))]))])),
+            ("compound", Tag::Compound(compound(vec![("nested", Tag::Compound(Compound::default()))]))),
+            ("int array", Tag::IntArray(vec![i32::MIN, 0, i32::MAX])),
+            ("long array", Tag::LongArray(vec![i64::MIN, i64::MAX])),
+            ("empty long array", Tag::LongArray(Vec::new()))
+        ])
+    }
+
+    /// The uncompressed NBT of a root compound "hello world" with the string "name" set to "Bananrama"
+    fn hello_world() -> Vec<u8> {
+        let mut data = vec![TAG_COMPOUND, 0, 11];
+        data.extend_from_slice(b"hello world");
+        data.extend_from_slice(&[TAG_STRING, 0, 4]);
+        data.extend_from_slice(b"name");
+        data.extend_from_slice(&[0, 9]);
+        data.extend_from_slice(b"Bananrama");
+        data.push(TAG_END);
+        data
+    }
+
+    /// An uncompressed root compound with a byte array whose length is `length`, followed by nothing
+    fn with_array_length(id: u8, length: i32) -> Vec<u8> {
+        let mut data = vec![TAG_COMPOUND, 0, 0, id, 0, 1, b'a'];
+        data.extend_from_slice(&length.to_be_bytes());
+        data
+    }
+
+    #[test]
+    fn round_trips_every_tag() {
+        let root = every_tag();
+        for compression in [Compression::None, Compression::Gzip, Compression::Zlib] {
+            let data = write("root", &root, compression).unwrap();
+            assert_eq!(Compression::detect(&data), compression);
+            assert_eq!(read(&data).unwrap(), ("root".to_string(), root.clone()), "{compression:?}");
+        }
+    }
+
+    #[test]
+    fn reads_compressed_input() {
+        let expected = ("hello world".to_string(), compound(vec![("name", Tag::String("Bananrama".into()))]));
+        assert_eq!(read(&hello_world()).unwrap(), expected);
+
+        let mut gzip = GzEncoder::new(Vec::new(), flate2::Compression::best());
+        gzip.write_all(&hello_world()).unwrap();
+        assert_eq!(read(&gzip.finish().unwrap()).unwrap(), expected);
+
+        let mut zlib = ZlibEncoder::new(Vec::new(), flate2::Compression::fast());
+        zlib.write_all(&hello_world()).unwrap();
+        assert_eq!(read(&zlib.finish().unwrap()).unwrap(), expected);
+    }
+
+    #[test]
+    fn writes_modified_utf8() {
+        let name = "a\0b \u{e9} \u{1f3ae}";
+        let root = compound(vec![("text", Tag::String(name.into()))]);
+        let data = write("", &root, Compression::None).unwrap();
+
+        // Null is encoded as two bytes, the character outside of the BMP as two surrogates of three bytes each
+        let encoded: &[u8] = &[b'a', 0xc0, 0x80, b'b', b' ', 0xc3, 0xa9, b' ', 0xed, 0xa0, 0xbc, 0xed, 0xbe, 0xae];
+        let mut expected = vec![TAG_COMPOUND, 0, 0, TAG_STRING, 0, 4];
+        expected.extend_from_slice(b"text");
+        expected.extend_from_slice(&(encoded.len() as u16).to_be_bytes());
+        expected.extend_from_slice(encoded);
+        expected.push(TAG_END);
+        assert_eq!(data, expected);
+
+        assert_eq!(read(&data).unwrap().1.get("text"), Some(&Tag::String(name.into())));
+    }
+
+    #[test]
+    fn rejects_truncated_input() {
+        let data = write("root", &every_tag(), Compression::None).unwrap();
+        for length in 0..data.len() {
+            assert!(read(&data[..length]).is_err(), "read {length} of {} bytes", data.len());
+        }
+
+        let gzip = write("root", &every_tag(), Compression::Gzip).unwrap();
+        assert!(read(&gzip[..gzip.len() / 2]).is_err());
+    }
+
+    #[test]
+    fn rejects_negative_lengths() {
+        for id in [TAG_BYTE_ARRAY, TAG_INT_ARRAY, TAG_LONG_ARRAY] {
+            assert!(matches!(read(&with_array_length(id, -1)), Err(NbtError::NegativeLength(-1))));
+        }
+        let mut list = vec![TAG_COMPOUND, 0, 0, TAG_LIST, 0, 1, b'a', TAG_INT];
+        list.extend_from_slice(&i32::MIN.to_be_bytes());
+        assert!(matches!(read(&list), Err(NbtError::NegativeLength(i32::MIN))));
+    }
+
+    #[test]
+    fn rejects_huge_lengths_without_allocating() {
+        assert!(matches!(read(&with_array_length(TAG_LONG_ARRAY, i32::MAX)), Err(NbtError::Io(_))));
+        assert!(matches!(read(&with_array_length(TAG_BYTE_ARRAY, i32::MAX)), Err(NbtError::Io(_))));
+    }
+
+    #[test]
+    fn rejects_deep_nesting() {
+        let nested = |depth: usize| {
+            let mut data = vec![TAG_COMPOUND, 0, 0];
+            for _ in 0..depth {
+                data.extend_from_slice(&[TAG_COMPOUND, 0, 0]);
+            }
+            data.extend(std::iter::repeat(TAG_END).take(depth + 1));
+            data
+        };
+        assert!(read(&nested(MAX_DEPTH)).is_ok());
+        assert!(matches!(read(&nested(MAX_DEPTH + 1)), Err(NbtError::TooDeep)));
+        assert!(matches!(read(&nested(100_000)), Err(NbtError::TooDeep)));
+
+        // Lists of lists
+        let mut data = vec![TAG_COMPOUND, 0, 0, TAG_LIST, 0, 0];
+        for _ in 0..MAX_DEPTH + 1 {
+            data.extend_from_slice(&[TAG_LIST, 0, 0, 0, 1]);
+        }
+        assert!(matches!(read(&data), Err(NbtError::TooDeep)));
+    }
+
+    #[test]
+    fn rejects_invalid_data() {
+        assert!(matches!(read(&[TAG_INT, 0, 0, 0, 0, 0, 1]), Err(NbtError::InvalidRoot)));
+        assert!(matches!(read(&[TAG_COMPOUND, 0, 0, 13, 0, 0]), Err(NbtError::UnknownTag(13))));
+        assert!(matches!(read(&[]), Err(NbtError::Io(_))));
+
+        let mixed = compound(vec![("list", Tag::List(TAG_INT, vec![Tag::Int(1), Tag::Long(2)]))]);
+        assert!(matches!(write("", &mixed, Compression::None), Err(NbtError::InvalidList(TAG_INT, TAG_LONG))));
+    }
+}
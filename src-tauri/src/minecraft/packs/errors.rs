@@ -0,0 +1,48 @@
+use std::path::PathBuf;
+
+use log::error;
+use serde::Serialize;
+use thiserror::Error;
+use tokio::io;
+use zip::result::ZipError;
+
+use crate::minecraft::instances::errors::InstanceGatherError;
+
+
+#[derive(Debug, Error)]
+pub enum PackError {
+    #[error("Could not read the folder at {0:?}: {1}")]
+    DirectoryReadFailed(PathBuf, #[source] io::Error),
+    #[error("Failed to create directory at {0:?}: {1}")]
+    DirectoryCreateFailed(PathBuf, #[source] io::Error),
+    #[error("Failed to read file at {0:?}: {1}")]
+    FileReadFailed(PathBuf, #[source] io::Error),
+    #[error("Failed to write file at {0:?}: {1}")]
+    FileWriteFailed(PathBuf, #[source] io::Error),
+    #[error("Failed to open the pack at {0:?}: {1}")]
+    ArchiveReadFailed(PathBuf, #[source] ZipError),
+    #[error("Failed to parse {1} of {0:?}: {2}")]
+    ParseFailed(PathBuf, String, String),
+    #[error("{0:?} is not a valid {1}!")]
+    InvalidPack(PathBuf, String),
+    #[error("There already is a pack called {0}!")]
+    AlreadyExists(String),
+    #[error("There is no world called {0}!")]
+    WorldNotFound(String),
+    #[error("{0} is currently running!")]
+    InstanceRunning(String),
+    #[error("Failed to whitelist path {0:?} for the asset protocol!")]
+    PathUnlockFailed(PathBuf, #[source] tauri::Error),
+    #[error(transparent)]
+    Instance(#[from] InstanceGatherError),
+}
+
+
+impl Serialize for PackError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer {
+        error!("{self}");
+        serializer.serialize_str(&self.to_string())
+    }
+}
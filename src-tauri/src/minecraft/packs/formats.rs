@@ -0,0 +1,144 @@
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::{get_client_jar_dir, read_jar_files, minecraft::mods::versions::Version};
+
+/// The first Minecraft release of each resource pack format, only used if the client jar wasn't downloaded yet
+const RESOURCE_PACK_FORMATS: [(&str, u32); 18] = [
+    ("1.6.1", 1), ("1.9", 2), ("1.11", 3), ("1.13", 4), ("1.15", 5), ("1.16.2", 6), ("1.17", 7), ("1.18", 8), ("1.19", 9),
+    ("1.19.3", 12), ("1.19.4", 13), ("1.20", 15), ("1.20.2", 18), ("1.20.3", 22), ("1.20.5", 32), ("1.21", 34), ("1.21.2", 42), ("1.21.4", 46)
+];
+/// The first Minecraft release of each data pack format
+const DATA_PACK_FORMATS: [(&str, u32); 15] = [
+    ("1.13", 4), ("1.15", 5), ("1.16.2", 6), ("1.17", 7), ("1.18", 8), ("1.18.2", 9), ("1.19", 10), ("1.19.4", 12),
+    ("1.20", 15), ("1.20.2", 18), ("1.20.3", 26), ("1.20.5", 41), ("1.21", 48), ("1.21.2", 57), ("1.21.4", 61)
+];
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub enum PackCompatibility {
+    Compatible,
+    /// Made for an older version of Minecraft
+    TooOld,
+    /// Made for a newer version of Minecraft
+    TooNew,
+    /// Either the pack or the Minecraft version has no known format
+    Unknown
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FormatKind {
+    ResourcePack,
+    DataPack
+}
+
+
+impl FormatKind {
+    /// The pack format `mc_version` uses, read from its client jar if it was downloaded and looked up otherwise.
+    /// `None` for snapshots, versions before packs existed and versions newer than the tables.
+    pub fn get_format(&self, mc_version: &str) -> Option<u32> {
+        self.read_client_format(mc_version).or_else(|| self.get_known_format(mc_version))
+    }
+
+    fn get_known_format(&self, mc_version: &str) -> Option<u32> {
+        let table: &[(&str, u32)] = match self {
+            FormatKind::ResourcePack => &RESOURCE_PACK_FORMATS,
+            FormatKind::DataPack => &DATA_PACK_FORMATS
+        };
+        // Snapshots like "23w31a" can't be placed
+        if !mc_version.starts_with("1.") {
+            return None
+        }
+        let version = Version::parse(mc_version);
+        // Any release after the last one in the table may have changed the format
+        if table.last().map_or(true, |(last, _)| version > Version::parse(last)) {
+            return None
+        }
+        table.iter()
+            .take_while(|(first, _)| Version::parse(first) <= version)
+            .last()
+            .map(|(_, format)| *format)
+    }
+
+    /// The `pack_version` in the version.json of the client jar. It is a number in old versions,
+    /// an object with `resource` and `data` since 1.19.3, and with `resource_major` and `data_major` since 1.21.9.
+    fn read_client_format(&self, mc_version: &str) -> Option<u32> {
+        let jar = get_client_jar_dir().join(format!("{mc_version}.jar"));
+        if !jar.is_file() {
+            return None
+        }
+        let files = read_jar_files(&jar, &["version.json"]).ok()?;
+        let version: Value = serde_json::from_slice(files.get("version.json")?).ok()?;
+        let key = match self {
+            FormatKind::ResourcePack => "resource",
+            FormatKind::DataPack => "data"
+        };
+        let format = match version.get("pack_version")? {
+            Value::Object(formats) => formats.get(key).or_else(|| formats.get(&format!("{key}_major")))?,
+            format => format
+        };
+        format.as_u64().map(|format| format as u32)
+    }
+}
+
+/// Compares the formats a pack supports with `format`, the one of the Minecraft version.
+/// `supported` is the inclusive range of formats newer packs declare instead of only `pack_format`.
+pub fn get_compatibility(format: Option<u32>, pack_format: Option<u32>, supported: Option<(u32, u32)>) -> PackCompatibility {
+    let format = match format {
+        Some(format) => format,
+        None => return PackCompatibility::Unknown
+    };
+    match supported.or_else(|| pack_format.map(|pack_format| (pack_format, pack_format))) {
+        Some((min, _)) if format < min => PackCompatibility::TooNew,
+        Some((_, max)) if format > max => PackCompatibility::TooOld,
+        Some(_) => PackCompatibility::Compatible,
+        None => PackCompatibility::Unknown
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn looks_up_known_formats() {
+        let resource = |version| FormatKind::ResourcePack.get_known_format(version);
+        assert_eq!(resource("1.6.1"), Some(1));
+        assert_eq!(resource("1.12.2"), Some(3));
+        assert_eq!(resource("1.20.1"), Some(15));
+        assert_eq!(resource("1.20.2"), Some(18));
+        assert_eq!(resource("1.21.4"), Some(46));
+        assert_eq!(resource("1.5.2"), None);
+
+        let data = |version| FormatKind::DataPack.get_known_format(version);
+        assert_eq!(data("1.12.2"), None);
+        assert_eq!(data("1.13"), Some(4));
+        assert_eq!(data("1.20.4"), Some(26));
+        assert_eq!(data("1.21.1"), Some(48));
+    }
+
+    #[test]
+    fn does_not_guess_unknown_formats() {
+        for kind in [FormatKind::ResourcePack, FormatKind::DataPack] {
+            // Newer than the tables
+            assert_eq!(kind.get_known_format("1.21.5"), None);
+            assert_eq!(kind.get_known_format("1.22"), None);
+            // Snapshots
+            assert_eq!(kind.get_known_format("23w31a"), None);
+        }
+    }
+
+    #[test]
+    fn compares_formats() {
+        assert_eq!(get_compatibility(Some(15), Some(15), None), PackCompatibility::Compatible);
+        assert_eq!(get_compatibility(Some(15), Some(13), None), PackCompatibility::TooOld);
+        assert_eq!(get_compatibility(Some(15), Some(18), None), PackCompatibility::TooNew);
+        // The supported range counts instead of the pack format
+        assert_eq!(get_compatibility(Some(18), Some(15), Some((15, 22))), PackCompatibility::Compatible);
+        assert_eq!(get_compatibility(Some(32), Some(15), Some((15, 22))), PackCompatibility::TooOld);
+        assert_eq!(get_compatibility(Some(13), Some(15), Some((15, 22))), PackCompatibility::TooNew);
+        assert_eq!(get_compatibility(None, Some(15), None), PackCompatibility::Unknown);
+        assert_eq!(get_compatibility(Some(15), None, None), PackCompatibility::Unknown);
+    }
+}
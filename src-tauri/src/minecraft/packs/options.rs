@@ -0,0 +1,100 @@
+use std::{fs, io::ErrorKind, path::{Path, PathBuf}};
+
+use super::errors::PackError;
+
+
+/// Handling the "options.txt" file of the game, which has one `key:value` per line.
+/// Lines are kept as they are, so settings yamcl doesn't know about survive saving.
+#[derive(Debug)]
+pub struct GameOptions {
+    path: PathBuf,
+    lines: Vec<String>
+}
+
+impl GameOptions {
+    /// Reads "options.txt" from `minecraft_path`, empty if the game hasn't written one yet
+    pub fn get(minecraft_path: &Path) -> Result<Self, PackError> {
+        let path = minecraft_path.join("options.txt");
+        let lines = match fs::read_to_string(&path) {
+            Ok(contents) => contents.lines().map(str::to_string).collect(),
+            Err(err) if err.kind() == ErrorKind::NotFound => Vec::new(),
+            Err(err) => return Err(PackError::FileReadFailed(path, err))
+        };
+        Ok(Self { path, lines })
+    }
+
+    pub fn save(&self) -> Result<(), PackError> {
+        let mut contents = self.lines.join("\n");
+        contents.push('\n');
+        fs::write(&self.path, contents).map_err(|err| PackError::FileWriteFailed(self.path.to_path_buf(), err))
+    }
+
+    pub fn get_value(&self, key: &str) -> Option<&str> {
+        self.lines.iter().find_map(|line| line.strip_prefix(key)?.strip_prefix(':'))
+    }
+
+    pub fn set_value(&mut self, key: &str, value: &str) {
+        let line = format!("{key}:{value}");
+        match self.lines.iter_mut().find(|line| line.strip_prefix(key).map_or(false, |rest| rest.starts_with(':'))) {
+            Some(existing) => *existing = line,
+            None => self.lines.push(line)
+        }
+    }
+
+    /// Lists like `resourcePacks` are stored as json arrays
+    pub fn get_list(&self, key: &str) -> Vec<String> {
+        self.get_value(key)
+            .and_then(|value| serde_json::from_str(value).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn set_list(&mut self, key: &str, values: &[String]) {
+        self.set_value(key, &serde_json::to_string(values).unwrap(/* this cannot fail */));
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::test_dir;
+
+    use super::*;
+
+    #[test]
+    fn reads_missing_options_as_empty() {
+        let dir = test_dir("options-missing");
+        let mut options = GameOptions::get(&dir).unwrap();
+        assert_eq!(options.get_value("lang"), None);
+        assert!(options.get_list("resourcePacks").is_empty());
+
+        options.set_value("lang", "en_us");
+        options.save().unwrap();
+        assert_eq!(fs::read_to_string(dir.join("options.txt")).unwrap(), "lang:en_us\n");
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn keeps_unknown_lines_when_saving() {
+        let dir = test_dir("options-round-trip");
+        fs::write(dir.join("options.txt"), "version:3465\nincompatibleResourcePacks:[]\nresourcePacks:[\"vanilla\"]\nsome line\nfov:0.0").unwrap();
+
+        let mut options = GameOptions::get(&dir).unwrap();
+        assert_eq!(options.get_value("version"), Some("3465"));
+        assert_eq!(options.get_list("resourcePacks"), ["vanilla"]);
+        options.set_list("resourcePacks", &["vanilla".into(), "file/Faithful.zip".into()]);
+        options.set_value("fov", "0.5");
+        options.set_value("lang", "de_de");
+        options.save().unwrap();
+
+        let options = GameOptions::get(&dir).unwrap();
+        assert_eq!(options.get_list("resourcePacks"), ["vanilla", "file/Faithful.zip"]);
+        assert!(options.get_list("incompatibleResourcePacks").is_empty());
+        assert_eq!(options.get_value("fov"), Some("0.5"));
+        assert_eq!(options.get_value("lang"), Some("de_de"));
+        assert_eq!(
+            fs::read_to_string(dir.join("options.txt")).unwrap(),
+            "version:3465\nincompatibleResourcePacks:[]\nresourcePacks:[\"vanilla\",\"file/Faithful.zip\"]\nsome line\nfov:0.5\nlang:de_de\n"
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
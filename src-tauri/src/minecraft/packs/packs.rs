@@ -0,0 +1,489 @@
+use std::{collections::HashMap, fs::{self, File}, io::Read, path::{Path, PathBuf}, time::UNIX_EPOCH};
+
+use log::{*};
+use serde::{Serialize, Deserialize};
+use serde_json::Value;
+use sha1_smol::Sha1;
+use tauri::{AppHandle, Manager};
+use zip::ZipArchive;
+
+use crate::{
+    get_pack_icons_dir,
+    minecraft::{
+        instances::{instances::SimpleInstance, management::{copy_dir, CopyMode}},
+        launching::launching::RunningInstances,
+        mods::versions::Version,
+        nbt::Tag,
+        worlds::worlds::{self, read_level_dat}
+    }
+};
+
+use super::{errors::PackError, formats::{FormatKind, PackCompatibility, get_compatibility}, options::GameOptions};
+
+const PACK_METADATA: &str = "pack.mcmeta";
+const PACK_ICON: &str = "pack.png";
+const SHADER_PROPERTIES: &str = "shaders/shaders.properties";
+/// Since 1.13, `resourcePacks` in "options.txt" has packs from the folder prefixed with this, other entries are built in
+const FILE_PREFIX: &str = "file/";
+/// Shader packs that need Iris declare the Iris features they use
+const IRIS_FEATURES_KEY: &str = "iris.features.required";
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum PackKind {
+    ResourcePack,
+    ShaderPack,
+    DataPack
+}
+
+/// A resource pack or data pack
+#[derive(Debug, Clone, Serialize)]
+pub struct PackInfo {
+    pub file_name: String,
+    /// The description as plain text, without formatting codes
+    pub description: String,
+    pub pack_format: Option<u32>,
+    /// The inclusive range of formats the pack declares to support, if it does
+    pub supported_formats: Option<(u32, u32)>,
+    pub compatibility: PackCompatibility,
+    pub icon_path: Option<String>,
+    /// Data packs in the folder are enabled when the world is loaded, unless level.dat lists them as disabled in the game
+    pub enabled: bool,
+    pub is_folder: bool
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub enum ShaderFormat {
+    /// Works with both OptiFine and Iris
+    OptiFine,
+    /// Uses features only Iris has
+    Iris,
+    /// There is no "shaders" folder at the top, usually because the pack was zipped into another folder
+    Invalid
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ShaderPackInfo {
+    pub file_name: String,
+    pub format: ShaderFormat,
+    /// Whether this is the pack OptiFine or Iris currently uses
+    pub selected: bool,
+    pub is_folder: bool
+}
+
+// Handling "pack.mcmeta"
+#[derive(Debug, Deserialize)]
+struct PackMcmeta {
+    pack: PackSection
+}
+
+#[derive(Debug, Deserialize)]
+struct PackSection {
+    pack_format: Option<u32>,
+    #[serde(default)]
+    description: Value,
+    /// A number, a `[min, max]` list or an object with `min_inclusive` and `max_inclusive`
+    supported_formats: Option<Value>,
+    /// Replace the other formats since 1.21.9, a number or a `[major, minor]` list
+    min_format: Option<Value>,
+    max_format: Option<Value>
+}
+
+
+/// The resource packs of `instance`, the enabled ones first from the highest priority to the lowest
+#[tauri::command(async)]
+pub async fn list_resource_packs(instance: SimpleInstance, app_handle: AppHandle) -> Result<Vec<PackInfo>, PackError> {
+    unlock_icons(&app_handle)?;
+    let options = GameOptions::get(&instance.minecraft_path)?;
+    let enabled = get_enabled_resource_packs(&options, &instance.mc_version);
+
+    let mut packs = PackInfo::get_all(&instance.minecraft_path.join(PackKind::ResourcePack.get_dir()), FormatKind::ResourcePack, &instance.mc_version)?;
+    for pack in &mut packs {
+        pack.enabled = enabled.contains(&pack.file_name);
+    }
+    packs.sort_by_key(|pack| (
+        enabled.iter().position(|name| *name == pack.file_name).unwrap_or(usize::MAX),
+        pack.file_name.to_lowercase()
+    ));
+    Ok(packs)
+}
+
+/// Enables exactly the resource packs in `enabled`, ordered from the highest priority to the lowest, through "options.txt"
+#[tauri::command(async)]
+pub async fn set_resource_packs(instance: SimpleInstance, enabled: Vec<String>, app_handle: AppHandle) -> Result<(), PackError> {
+    // The game writes its options when it closes, which would undo this
+    if app_handle.state::<RunningInstances>().contains(&instance.instance_path) {
+        return Err(PackError::InstanceRunning(instance.name))
+    }
+
+    let packs = PackInfo::get_all(&instance.minecraft_path.join(PackKind::ResourcePack.get_dir()), FormatKind::ResourcePack, &instance.mc_version)?;
+    let prefix = get_file_prefix(&instance.mc_version);
+    let mut options = GameOptions::get(&instance.minecraft_path)?;
+
+    // Built in packs like "vanilla" stay where they are, at the bottom
+    let mut entries: Vec<String> = options.get_list("resourcePacks").into_iter()
+        .filter(|entry| !prefix.is_empty() && !entry.starts_with(prefix))
+        .collect();
+    entries.extend(enabled.iter().rev().map(|name| format!("{prefix}{name}")));
+
+    // Otherwise the game disables packs made for other versions on its own
+    let incompatible: Vec<String> = packs.iter()
+        .filter(|pack| enabled.contains(&pack.file_name) && pack.compatibility != PackCompatibility::Compatible)
+        .map(|pack| format!("{prefix}{}", pack.file_name))
+        .collect();
+
+    options.set_list("resourcePacks", &entries);
+    options.set_list("incompatibleResourcePacks", &incompatible);
+    options.save()?;
+    info!("Enabled {} resource packs in {}", enabled.len(), instance.name);
+    Ok(())
+}
+
+#[tauri::command(async)]
+pub async fn list_shader_packs(instance: SimpleInstance) -> Result<Vec<ShaderPackInfo>, PackError> {
+    let selected = get_selected_shader_pack(&instance.minecraft_path);
+    let mut packs: Vec<ShaderPackInfo> = get_pack_paths(&instance.minecraft_path.join(PackKind::ShaderPack.get_dir()))?
+        .into_iter()
+        .map(|path| {
+            let file_name = get_file_name(&path);
+            ShaderPackInfo {
+                format: get_shader_format(&path).unwrap_or_else(|err| {
+                    warn!("{err}");
+                    ShaderFormat::Invalid
+                }),
+                selected: selected.as_deref() == Some(file_name.as_str()),
+                is_folder: path.is_dir(),
+                file_name
+            }
+        })
+        .collect();
+    packs.sort_by_key(|pack| pack.file_name.to_lowercase());
+    Ok(packs)
+}
+
+/// The data packs in the world folder `world` of `instance`
+#[tauri::command(async)]
+pub async fn list_data_packs(instance: SimpleInstance, world: String, app_handle: AppHandle) -> Result<Vec<PackInfo>, PackError> {
+    unlock_icons(&app_handle)?;
+    let world_path = get_world_path(&instance, &world)?;
+    let mut packs = PackInfo::get_all(&world_path.join(PackKind::DataPack.get_dir()), FormatKind::DataPack, &instance.mc_version)?;
+    let disabled = get_disabled_data_packs(&world_path);
+    for pack in &mut packs {
+        pack.enabled = !disabled.contains(&format!("{FILE_PREFIX}{}", pack.file_name));
+    }
+    packs.sort_by_key(|pack| pack.file_name.to_lowercase());
+    Ok(packs)
+}
+
+/// Copies the pack at `path`, a zip file or a folder, into the instance. Data packs go into the world folder `world`.
+/// Returns the name of the copy.
+#[tauri::command(async)]
+pub async fn import_pack(instance: SimpleInstance, kind: PackKind, path: PathBuf, world: Option<String>) -> Result<String, PackError> {
+    let target_dir = match kind {
+        PackKind::DataPack => get_world_path(&instance, world.as_deref().unwrap_or_default())?.join(kind.get_dir()),
+        _ => instance.minecraft_path.join(kind.get_dir())
+    };
+    let file_name = get_file_name(&path);
+    if !path.is_dir() && !file_name.to_lowercase().ends_with(".zip") {
+        return Err(PackError::InvalidPack(path, kind.get_label().into()))
+    }
+
+    let valid = match kind {
+        PackKind::ShaderPack => get_shader_format(&path)? != ShaderFormat::Invalid,
+        _ => read_pack_files(&path, &[PACK_METADATA])?.contains_key(PACK_METADATA)
+    };
+    if !valid {
+        return Err(PackError::InvalidPack(path, kind.get_label().into()))
+    }
+
+    let target = target_dir.join(&file_name);
+    if target.exists() {
+        return Err(PackError::AlreadyExists(file_name))
+    }
+    fs::create_dir_all(&target_dir).map_err(|err| PackError::DirectoryCreateFailed(target_dir.to_path_buf(), err))?;
+    if path.is_dir() {
+        copy_dir(&path, &target, &|_| CopyMode::Copy, &|_| false)?;
+    } else {
+        fs::copy(&path, &target).map_err(|err| PackError::FileWriteFailed(target.to_path_buf(), err))?;
+    }
+    info!("Imported {} {file_name} into {}", kind.get_label(), instance.name);
+    Ok(file_name)
+}
+
+
+impl PackKind {
+    pub fn get_dir(&self) -> &'static str {
+        match self {
+            PackKind::ResourcePack => "resourcepacks",
+            PackKind::ShaderPack => "shaderpacks",
+            PackKind::DataPack => "datapacks"
+        }
+    }
+
+    fn get_label(&self) -> &'static str {
+        match self {
+            PackKind::ResourcePack => "resource pack",
+            PackKind::ShaderPack => "shader pack",
+            PackKind::DataPack => "data pack"
+        }
+    }
+}
+
+impl PackInfo {
+    /// Reads all packs in `dir`, packs that can't be read are listed with what is known from their file
+    fn get_all(dir: &Path, kind: FormatKind, mc_version: &str) -> Result<Vec<Self>, PackError> {
+        let format = kind.get_format(mc_version);
+        Ok(get_pack_paths(dir)?.into_iter().map(|path| Self::get(&path, format)).collect())
+    }
+
+    /// Reads the pack at `path`, `format` is the pack format of the Minecraft version
+    fn get(path: &Path, format: Option<u32>) -> Self {
+        let files = read_pack_files(path, &[PACK_METADATA, PACK_ICON]).unwrap_or_else(|err| {
+            warn!("{err}");
+            HashMap::new()
+        });
+        let metadata = files.get(PACK_METADATA).and_then(|contents| {
+            let contents = String::from_utf8_lossy(contents);
+            serde_json::from_str::<PackMcmeta>(contents.trim_start_matches('\u{feff}'))
+                .map_err(|err| warn!("{}", PackError::ParseFailed(path.to_path_buf(), PACK_METADATA.into(), err.to_string())))
+                .ok()
+        });
+
+        let (pack_format, supported_formats, description) = match metadata {
+            Some(metadata) => (
+                metadata.pack.pack_format,
+                metadata.pack.get_supported_formats(),
+                strip_formatting(&get_text(&metadata.pack.description))
+            ),
+            None => (None, None, String::new())
+        };
+
+        Self {
+            file_name: get_file_name(path),
+            description,
+            pack_format,
+            supported_formats,
+            compatibility: get_compatibility(format, pack_format, supported_formats),
+            icon_path: files.get(PACK_ICON).and_then(|icon| get_cached_icon(path, icon)),
+            enabled: true,
+            is_folder: path.is_dir()
+        }
+    }
+}
+
+impl PackSection {
+    /// The inclusive range of major formats the pack supports, minor formats don't break compatibility
+    fn get_supported_formats(&self) -> Option<(u32, u32)> {
+        let major = |value: &Value| match value {
+            Value::Array(format) => format.first()?.as_u64(),
+            format => format.as_u64()
+        }.map(|major| major as u32);
+
+        match (self.min_format.as_ref().and_then(major), self.max_format.as_ref().and_then(major)) {
+            (Some(min), Some(max)) => Some((min, max)),
+            _ => self.supported_formats.as_ref().and_then(parse_supported_formats)
+        }
+    }
+}
+
+
+/// Zip files and folders in `dir`, empty if there is no such folder
+fn get_pack_paths(dir: &Path) -> Result<Vec<PathBuf>, PackError> {
+    if !dir.is_dir() {
+        return Ok(Vec::new())
+    }
+    let entries = fs::read_dir(dir).map_err(|err| PackError::DirectoryReadFailed(dir.to_path_buf(), err))?;
+    Ok(entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir() || get_file_name(path).to_lowercase().ends_with(".zip"))
+        .collect())
+}
+
+/// Reads those of `files` that exist from the pack at `path`, which is either a folder or a zip file
+fn read_pack_files(path: &Path, files: &[&str]) -> Result<HashMap<String, Vec<u8>>, PackError> {
+    if path.is_dir() {
+        return Ok(files.iter()
+            .filter_map(|file| Some((file.to_string(), fs::read(path.join(file)).ok()?)))
+            .collect())
+    }
+
+    let file = File::open(path).map_err(|err| PackError::FileReadFailed(path.to_path_buf(), err))?;
+    let mut archive = ZipArchive::new(file).map_err(|err| PackError::ArchiveReadFailed(path.to_path_buf(), err))?;
+    Ok(files.iter().filter_map(|file| {
+        let mut contents = Vec::new();
+        archive.by_name(file).ok()?.read_to_end(&mut contents).ok()?;
+        Some((file.to_string(), contents))
+    }).collect())
+}
+
+fn get_shader_format(path: &Path) -> Result<ShaderFormat, PackError> {
+    let has_shaders = if path.is_dir() {
+        path.join("shaders").is_dir()
+    } else {
+        let file = File::open(path).map_err(|err| PackError::FileReadFailed(path.to_path_buf(), err))?;
+        let archive = ZipArchive::new(file).map_err(|err| PackError::ArchiveReadFailed(path.to_path_buf(), err))?;
+        let has_shaders = archive.file_names().any(|name| name.starts_with("shaders/"));
+        has_shaders
+    };
+    if !has_shaders {
+        return Ok(ShaderFormat::Invalid)
+    }
+
+    let properties = read_pack_files(path, &[SHADER_PROPERTIES])?;
+    let needs_iris = properties.get(SHADER_PROPERTIES).map_or(false, |contents| {
+        String::from_utf8_lossy(contents).lines().any(|line| line.trim_start().starts_with(IRIS_FEATURES_KEY))
+    });
+    Ok(if needs_iris { ShaderFormat::Iris } else { ShaderFormat::OptiFine })
+}
+
+/// The shader pack in the config of Iris, or of OptiFine if there is none
+fn get_selected_shader_pack(minecraft_path: &Path) -> Option<String> {
+    [minecraft_path.join("config").join("iris.properties"), minecraft_path.join("optionsshaders.txt")].iter()
+        .filter_map(|path| fs::read_to_string(path).ok())
+        .find_map(|contents| contents.lines().find_map(|line| {
+            line.strip_prefix("shaderPack=").map(|pack| pack.trim().to_string())
+        }))
+        .filter(|pack| !pack.is_empty() && pack != "OFF")
+}
+
+/// The data packs that were disabled in the game, as `DataPacks.Disabled` in level.dat lists them, like "file/pack.zip"
+fn get_disabled_data_packs(world_path: &Path) -> Vec<String> {
+    let data = match read_level_dat(world_path) {
+        Ok(data) => data,
+        Err(err) => {
+            warn!("{err}");
+            return Vec::new()
+        }
+    };
+    data.get_path(&["DataPacks", "Disabled"])
+        .and_then(Tag::as_list)
+        .map(|packs| packs.iter().filter_map(Tag::as_str).map(str::to_string).collect())
+        .unwrap_or_default()
+}
+
+/// The packs in `resourcePacks` of `options` that come from the folder, from the highest priority to the lowest
+fn get_enabled_resource_packs(options: &GameOptions, mc_version: &str) -> Vec<String> {
+    let prefix = get_file_prefix(mc_version);
+    options.get_list("resourcePacks").into_iter()
+        .rev()
+        .filter_map(|entry| entry.strip_prefix(prefix).map(str::to_string))
+        .collect()
+}
+
+fn get_file_prefix(mc_version: &str) -> &'static str {
+    if mc_version.starts_with("1.") && Version::parse(mc_version) < Version::parse("1.13") { "" } else { FILE_PREFIX }
+}
+
+fn get_world_path(instance: &SimpleInstance, world: &str) -> Result<PathBuf, PackError> {
+    // Fails only if there is no such world
+    worlds::get_world_path(instance, world).map_err(|_| PackError::WorldNotFound(world.to_string()))
+}
+
+fn parse_supported_formats(value: &Value) -> Option<(u32, u32)> {
+    let as_u32 = |value: &Value| value.as_u64().map(|number| number as u32);
+    match value {
+        Value::Number(_) => as_u32(value).map(|format| (format, format)),
+        Value::Array(range) => Some((as_u32(range.first()?)?, as_u32(range.get(1)?)?)),
+        Value::Object(range) => Some((as_u32(range.get("min_inclusive")?)?, as_u32(range.get("max_inclusive")?)?)),
+        _ => None
+    }
+}
+
+/// The plain text of a text component, which is a string, a list of components or an object with `text` and `extra`
+fn get_text(component: &Value) -> String {
+    match component {
+        Value::String(text) => text.to_string(),
+        Value::Array(components) => components.iter().map(get_text).collect(),
+        Value::Object(object) => {
+            let text = object.get("text").or_else(|| object.get("translate")).map(get_text).unwrap_or_default();
+            let extra: String = object.get("extra").map(get_text).unwrap_or_default();
+            text + &extra
+        },
+        Value::Number(number) => number.to_string(),
+        _ => String::new()
+    }
+}
+
+/// Removes the `§` formatting codes
+fn strip_formatting(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '§' {
+            chars.next();
+        } else {
+            result.push(c);
+        }
+    }
+    result.trim().to_string()
+}
+
+fn get_file_name(path: &Path) -> String {
+    path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default()
+}
+
+/// Writes `icon` of the pack at `path` into the pack icon cache, named after the path and last change of the pack
+fn get_cached_icon(path: &Path, icon: &[u8]) -> Option<String> {
+    let modified = fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+        .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |modified| modified.as_secs());
+    let key = Sha1::from(format!("{path:?}{modified}")).digest().to_string();
+    let cached = get_pack_icons_dir().join(format!("{key}.png"));
+    if cached.is_file() {
+        return Some(cached.to_string_lossy().to_string())
+    }
+    match fs::write(&cached, icon) {
+        Ok(_) => Some(cached.to_string_lossy().to_string()),
+        Err(err) => {
+            warn!("{}", PackError::FileWriteFailed(cached, err));
+            None
+        }
+    }
+}
+
+fn unlock_icons(app_handle: &AppHandle) -> Result<(), PackError> {
+    let icons_dir = get_pack_icons_dir();
+    fs::create_dir_all(&icons_dir).map_err(|err| PackError::DirectoryCreateFailed(icons_dir.clone(), err))?;
+    app_handle.asset_protocol_scope().allow_directory(&icons_dir, false).map_err(
+        |err| PackError::PathUnlockFailed(icons_dir.clone(), err)
+    )
+}
+
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn supported_formats(pack: Value) -> Option<(u32, u32)> {
+        serde_json::from_value::<PackSection>(pack).unwrap().get_supported_formats()
+    }
+
+    #[test]
+    fn parses_supported_formats() {
+        assert_eq!(supported_formats(json!({ "pack_format": 15 })), None);
+        assert_eq!(supported_formats(json!({ "pack_format": 15, "supported_formats": 22 })), Some((22, 22)));
+        assert_eq!(supported_formats(json!({ "pack_format": 15, "supported_formats": [15, 18] })), Some((15, 18)));
+        assert_eq!(
+            supported_formats(json!({ "pack_format": 15, "supported_formats": { "min_inclusive": 16, "max_inclusive": 20 } })),
+            Some((16, 20))
+        );
+        assert_eq!(supported_formats(json!({ "pack_format": 15, "supported_formats": [15] })), None);
+        assert_eq!(supported_formats(json!({ "pack_format": 15, "supported_formats": "15" })), None);
+    }
+
+    #[test]
+    fn prefers_min_and_max_format() {
+        assert_eq!(supported_formats(json!({ "min_format": 69, "max_format": 75 })), Some((69, 75)));
+        // Minor formats don't matter
+        assert_eq!(supported_formats(json!({ "min_format": [69, 0], "max_format": [75, 3] })), Some((69, 75)));
+        assert_eq!(
+            supported_formats(json!({ "pack_format": 64, "supported_formats": [55, 64], "min_format": [64, 2], "max_format": 70 })),
+            Some((64, 70))
+        );
+        // Both are needed
+        assert_eq!(supported_formats(json!({ "supported_formats": [55, 64], "min_format": 64 })), Some((55, 64)));
+    }
+}
@@ -0,0 +1,539 @@
+use std::{cmp::Reverse, collections::HashSet, fs::{self, File}, io, path::{Path, PathBuf}, thread, time::{Duration, Instant}};
+
+use chrono::{DateTime, Datelike, Local, NaiveDateTime};
+use log::{*};
+use serde::{Serialize, Deserialize};
+use tauri::{AppHandle, Manager};
+use zip::{ZipArchive, ZipWriter, write::FileOptions};
+
+use crate::{
+    get_world_backups_dir,
+    minecraft::{
+        instances::{instances::SimpleInstance, metadata::InstanceMetadata},
+        launching::launching::RunningInstances,
+        modpacks::modpacks::is_safe_path
+    },
+    Notif, NotificationState, Notifier
+};
+
+use super::{errors::WorldError, worlds::{LEVEL_DAT, SESSION_LOCK, get_world_path, is_folder_name, is_world_in_use}};
+
+/// Backups are named after the local time they were made at, like "2024-01-31_18-30-00.zip"
+const TIME_FORMAT: &str = "%Y-%m-%d_%H-%M-%S";
+const TIME_FORMAT_LENGTH: usize = 19;
+/// How often a world that is open in the game is checked again before backing it up
+const IN_USE_POLL_INTERVAL: Duration = Duration::from_secs(2);
+/// How long a world that is open in the game is waited for before giving up on backing it up
+const IN_USE_MAX_WAIT: Duration = Duration::from_secs(60 * 60);
+
+
+/// When the worlds of an instance are backed up automatically and which of their backups are kept.
+/// A backup is kept if any of the policies keeps it, without any of them all backups are kept.
+/// The newest backup is always kept, even by policies that keep none.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BackupSettings {
+    /// Back up the worlds that changed since their last backup before the game starts
+    pub before_launch: bool,
+    /// Back up the worlds that changed since their last backup after the game has exited
+    pub after_exit: bool,
+    /// Keep this many of the most recent backups of each world
+    pub keep_last: Option<u32>,
+    /// Keep the most recent backup of each of the last this many days a world was backed up on
+    pub keep_daily: Option<u32>,
+    /// Keep the most recent backup of each of the last this many weeks a world was backed up in
+    pub keep_weekly: Option<u32>
+}
+
+/// A zip file with a copy of a world folder
+#[derive(Debug, Clone, Serialize)]
+pub struct BackupInfo {
+    /// The folder name of the world
+    pub world: String,
+    pub file_name: String,
+    pub created: NaiveDateTime,
+    pub size_bytes: u64
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BackupTrigger {
+    BeforeLaunch,
+    AfterExit
+}
+
+
+/// The backups of `world` or of all worlds of `instance`, the newest first
+#[tauri::command(async)]
+pub async fn list_backups(instance: SimpleInstance, world: Option<String>) -> Result<Vec<BackupInfo>, WorldError> {
+    let backups_dir = get_instance_backups_dir(&instance).await?;
+    let worlds = match world {
+        Some(world) => vec![check_name(world)?],
+        None => get_backed_up_worlds(&backups_dir)?
+    };
+
+    let mut backups = Vec::new();
+    for world in worlds {
+        backups.extend(BackupInfo::get_all(&backups_dir, &world)?);
+    }
+    backups.sort_by_key(|backup| Reverse(backup.created));
+    Ok(backups)
+}
+
+/// Zips the world folder `world` into the backups of `instance`, then removes the backups the retention policies don't keep.
+/// If the world is open in the game, this waits until it has been closed, for at most [`IN_USE_MAX_WAIT`].
+#[tauri::command(async)]
+pub async fn backup_world(instance: SimpleInstance, world: String, app_handle: AppHandle) -> Result<BackupInfo, WorldError> {
+    let world_path = get_world_path(&instance, &world)?;
+    let notifier = Notifier::new(&format!("{}_world_backup", instance.id), app_handle.clone());
+
+    if is_world_in_use(&instance, &world_path, app_handle.state::<RunningInstances>().inner()) {
+        notifier.notify(&format!("{world} is open in the game, it will be backed up once it is closed."), NotificationState::Warning);
+        if let Err(err) = wait_until_closed(&instance, &world, &world_path, &app_handle).await {
+            notifier.notify(&err.to_string(), NotificationState::Error);
+            return Err(err)
+        }
+    }
+
+    let result = create_backup(&instance, &world, &world_path, &notifier).await;
+    match &result {
+        Ok(_) => notifier.notify(&format!("Backed up {world}!"), NotificationState::Success),
+        Err(err) => notifier.notify(&err.to_string(), NotificationState::Error)
+    }
+    result
+}
+
+/// Extracts the backup `file_name` of `world` into the world folder `new_name`, or replaces `world` with it if there is none.
+/// Returns the folder name of the restored world.
+#[tauri::command(async)]
+pub async fn restore_backup(instance: SimpleInstance, world: String, file_name: String, new_name: Option<String>, app_handle: AppHandle) -> Result<String, WorldError> {
+    let backup_path = get_backup_path(&instance, &world, &file_name).await?;
+    let target_name = check_name(new_name.unwrap_or_else(|| world.to_string()))?;
+    let saves_dir = instance.minecraft_path.join("saves");
+    let target = saves_dir.join(&target_name);
+
+    if target.exists() {
+        if target_name != world {
+            return Err(WorldError::AlreadyExists(target_name))
+        }
+        if is_world_in_use(&instance, &target, app_handle.state::<RunningInstances>().inner()) {
+            return Err(WorldError::WorldInUse(target_name))
+        }
+    }
+
+    let notifier = Notifier::new(&format!("{}_world_backup", instance.id), app_handle);
+    // Extracted next to the world first, so a failure leaves it as it was
+    let extracted = saves_dir.join(format!(".{target_name}.restoring"));
+    if extracted.exists() {
+        remove_dir(&extracted);
+    }
+    if let Err(err) = extract_backup(&backup_path, &extracted, &notifier) {
+        remove_dir(&extracted);
+        return Err(err)
+    }
+
+    if target.exists() {
+        let replaced = saves_dir.join(format!(".{target_name}.replaced"));
+        fs::rename(&target, &replaced).map_err(|err| WorldError::RenameFailed(target.to_path_buf(), replaced.to_path_buf(), err))?;
+        if let Err(err) = fs::rename(&extracted, &target) {
+            if let Err(err) = fs::rename(&replaced, &target) {
+                error!("Failed to move the world back from {replaced:?}: {err}");
+            }
+            remove_dir(&extracted);
+            return Err(WorldError::RenameFailed(extracted, target, err))
+        }
+        remove_dir(&replaced);
+    } else {
+        fs::rename(&extracted, &target).map_err(|err| WorldError::RenameFailed(extracted.to_path_buf(), target.to_path_buf(), err))?;
+    }
+
+    info!("Restored {file_name} of {world} into {target:?}");
+    notifier.notify(&format!("Restored {target_name}!"), NotificationState::Success);
+    Ok(target_name)
+}
+
+#[tauri::command(async)]
+pub async fn delete_backup(instance: SimpleInstance, world: String, file_name: String) -> Result<(), WorldError> {
+    let path = get_backup_path(&instance, &world, &file_name).await?;
+    fs::remove_file(&path).map_err(|err| WorldError::FileWriteFailed(path, err))?;
+    info!("Deleted the backup {file_name} of {world}");
+    Ok(())
+}
+
+#[tauri::command(async)]
+pub async fn get_backup_settings(instance: SimpleInstance) -> Result<BackupSettings, WorldError> {
+    Ok(InstanceMetadata::get(&instance.instance_path).await?.world_backups.unwrap_or_default())
+}
+
+/// Saves `settings` and removes the backups they don't keep anymore
+#[tauri::command(async)]
+pub async fn save_backup_settings(instance: SimpleInstance, settings: BackupSettings) -> Result<(), WorldError> {
+    let mut meta = InstanceMetadata::get(&instance.instance_path).await?;
+    meta.world_backups = if settings == BackupSettings::default() { None } else { Some(settings.clone()) };
+    meta.save(&instance.instance_path).await?;
+
+    let backups_dir = get_world_backups_dir().join(&meta.id);
+    for world in get_backed_up_worlds(&backups_dir)? {
+        settings.prune(&backups_dir, &BackupInfo::get_all(&backups_dir, &world)?)?;
+    }
+    Ok(())
+}
+
+
+/// Backs up the worlds of `instance` that changed since their last backup, if its settings ask for that at `trigger`.
+/// Failures are only shown through `notifier`, so they don't keep the game from launching.
+pub async fn auto_backup(instance: &SimpleInstance, trigger: BackupTrigger, notifier: &Notifier) {
+    let settings = match InstanceMetadata::get(&instance.instance_path).await {
+        Ok(meta) => meta.world_backups.unwrap_or_default(),
+        Err(err) => {
+            warn!("Failed to read the backup settings of {}: {err}", instance.name);
+            return
+        }
+    };
+    let enabled = match trigger {
+        BackupTrigger::BeforeLaunch => settings.before_launch,
+        BackupTrigger::AfterExit => settings.after_exit
+    };
+    let saves_dir = instance.minecraft_path.join("saves");
+    if !enabled || !saves_dir.is_dir() {
+        return
+    }
+
+    let worlds: Vec<PathBuf> = match fs::read_dir(&saves_dir) {
+        Ok(entries) => entries.filter_map(|entry| entry.ok()).map(|entry| entry.path()).filter(|path| path.is_dir()).collect(),
+        Err(err) => {
+            warn!("{}", WorldError::DirectoryReadFailed(saves_dir, err));
+            return
+        }
+    };
+    for world_path in worlds {
+        let world = world_path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+        if world.starts_with('.') || !has_changed(instance, &world, &world_path).await {
+            continue
+        }
+        if let Err(err) = create_backup(instance, &world, &world_path, notifier).await {
+            notifier.notify(&format!("Failed to back up {world}: {err}"), NotificationState::Warning);
+        }
+    }
+}
+
+
+impl BackupInfo {
+    /// The backups of `world` in `backups_dir`, the newest first
+    fn get_all(backups_dir: &Path, world: &str) -> Result<Vec<Self>, WorldError> {
+        let dir = backups_dir.join(world);
+        if !dir.is_dir() {
+            return Ok(Vec::new())
+        }
+        let entries = fs::read_dir(&dir).map_err(|err| WorldError::DirectoryReadFailed(dir.to_path_buf(), err))?;
+        let mut backups: Vec<Self> = entries
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| Self::get(world, &entry.path()))
+            .collect();
+        backups.sort_by(|a, b| b.created.cmp(&a.created).then_with(|| b.file_name.cmp(&a.file_name)));
+        Ok(backups)
+    }
+
+    /// `None` if `path` is not a backup
+    fn get(world: &str, path: &Path) -> Option<Self> {
+        let file_name = path.file_name()?.to_string_lossy().to_string();
+        if !file_name.ends_with(".zip") {
+            return None
+        }
+        Some(Self {
+            world: world.to_string(),
+            created: NaiveDateTime::parse_from_str(file_name.get(..TIME_FORMAT_LENGTH)?, TIME_FORMAT).ok()?,
+            size_bytes: fs::metadata(path).ok()?.len(),
+            file_name
+        })
+    }
+}
+
+impl BackupSettings {
+    /// Removes those of `backups` of a world in `backups_dir`, sorted from the newest to the oldest, that are not kept.
+    /// Returns how many were removed.
+    fn prune(&self, backups_dir: &Path, backups: &[BackupInfo]) -> Result<usize, WorldError> {
+        let kept = self.get_kept(backups);
+        let mut removed = 0;
+        for (index, backup) in backups.iter().enumerate() {
+            if kept.contains(&index) {
+                continue
+            }
+            let path = backups_dir.join(&backup.world).join(&backup.file_name);
+            debug!("Removing the old backup {path:?}");
+            fs::remove_file(&path).map_err(|err| WorldError::FileWriteFailed(path, err))?;
+            removed += 1;
+        }
+        if removed > 0 {
+            info!("Removed {removed} old backups of {}", backups[0].world);
+        }
+        Ok(removed)
+    }
+
+    /// The indices of the backups to keep of `backups`, which are sorted from the newest to the oldest
+    fn get_kept(&self, backups: &[BackupInfo]) -> HashSet<usize> {
+        if self.keep_last.is_none() && self.keep_daily.is_none() && self.keep_weekly.is_none() {
+            return (0..backups.len()).collect()
+        }
+        // The newest one is the backup that was just made
+        let keep_last = self.keep_last.unwrap_or(0).max(1) as usize;
+        let mut kept: HashSet<usize> = (0..backups.len()).take(keep_last).collect();
+        kept.extend(get_newest_per(backups, self.keep_daily, |created| (created.year(), created.ordinal())));
+        kept.extend(get_newest_per(backups, self.keep_weekly, |created| (created.iso_week().year(), created.iso_week().week())));
+        kept
+    }
+}
+
+
+/// The indices of the newest backup of each of the `count` most recent periods, as told apart by `get_period`
+fn get_newest_per(backups: &[BackupInfo], count: Option<u32>, get_period: impl Fn(&NaiveDateTime) -> (i32, u32)) -> Vec<usize> {
+    let mut periods = HashSet::new();
+    backups.iter()
+        .enumerate()
+        .filter(|(_, backup)| periods.insert(get_period(&backup.created)))
+        .map(|(index, _)| index)
+        .take(count.unwrap_or(0) as usize)
+        .collect()
+}
+
+async fn create_backup(instance: &SimpleInstance, world: &str, world_path: &Path, notifier: &Notifier) -> Result<BackupInfo, WorldError> {
+    let meta = InstanceMetadata::get(&instance.instance_path).await?;
+    let backups_dir = get_world_backups_dir().join(&meta.id);
+    let dir = backups_dir.join(world);
+    fs::create_dir_all(&dir).map_err(|err| WorldError::DirectoryCreateFailed(dir.to_path_buf(), err))?;
+
+    let name = Local::now().format(TIME_FORMAT).to_string();
+    let mut path = dir.join(format!("{name}.zip"));
+    let mut copy = 1;
+    while path.exists() {
+        copy += 1;
+        path = dir.join(format!("{name}_{copy}.zip"));
+    }
+
+    info!("Backing up {world_path:?} to {path:?}");
+    if let Err(err) = write_backup(world, world_path, &path, notifier) {
+        if let Err(err) = fs::remove_file(&path) {
+            warn!("Failed to remove the incomplete backup at {path:?}: {err}");
+        }
+        return Err(err)
+    }
+
+    let backups = BackupInfo::get_all(&backups_dir, world)?;
+    meta.world_backups.unwrap_or_default().prune(&backups_dir, &backups)?;
+    BackupInfo::get(world, &path).ok_or_else(|| WorldError::BackupNotFound(world.to_string(), path.to_string_lossy().to_string()))
+}
+
+fn write_backup(world: &str, world_path: &Path, target: &Path, notifier: &Notifier) -> Result<(), WorldError> {
+    let map_err = |err| WorldError::ArchiveWriteFailed(target.to_path_buf(), err);
+    let mut files = Vec::new();
+    collect_files(world_path, "", &mut files)?;
+
+    let mut writer = ZipWriter::new(File::create(target).map_err(|err| WorldError::FileWriteFailed(target.to_path_buf(), err))?);
+    let total = files.len() as u32;
+    for (i, (path, relative)) in files.into_iter().enumerate() {
+        notifier.notify_status(Notif::new(&format!("Backing up {world}"), i as u32, total, NotificationState::Running));
+        let mut file = File::open(&path).map_err(|err| WorldError::FileReadFailed(path.to_path_buf(), err))?;
+        let size = file.metadata().map_or(0, |metadata| metadata.len());
+        writer.start_file(format!("{world}/{relative}"), FileOptions::default().large_file(size >= u32::MAX as u64)).map_err(map_err)?;
+        io::copy(&mut file, &mut writer).map_err(|err| WorldError::FileReadFailed(path, err))?;
+    }
+    writer.finish().map_err(map_err)?;
+    Ok(())
+}
+
+/// Adds the files in `dir` with their paths relative to the world folder, except for the session.lock of the game
+fn collect_files(dir: &Path, relative: &str, files: &mut Vec<(PathBuf, String)>) -> Result<(), WorldError> {
+    let entries = fs::read_dir(dir).map_err(|err| WorldError::DirectoryReadFailed(dir.to_path_buf(), err))?;
+    for entry in entries {
+        let entry = entry.map_err(|err| WorldError::DirectoryReadFailed(dir.to_path_buf(), err))?;
+        let name = entry.file_name().to_string_lossy().to_string();
+        let path = entry.path();
+        if path.is_dir() {
+            collect_files(&path, &format!("{relative}{name}/"), files)?;
+        } else if !(relative.is_empty() && name == SESSION_LOCK) {
+            files.push((path, format!("{relative}{name}")));
+        }
+    }
+    Ok(())
+}
+
+/// Extracts the world folder inside of the backup at `backup_path` to `target`, whatever the folder is called
+fn extract_backup(backup_path: &Path, target: &Path, notifier: &Notifier) -> Result<(), WorldError> {
+    let map_err = |err| WorldError::ArchiveReadFailed(backup_path.to_path_buf(), err);
+    let file = File::open(backup_path).map_err(|err| WorldError::FileReadFailed(backup_path.to_path_buf(), err))?;
+    let mut archive = ZipArchive::new(file).map_err(map_err)?;
+
+    let total = archive.len() as u32;
+    for index in 0..archive.len() {
+        let mut entry = archive.by_index(index).map_err(map_err)?;
+        let relative = match entry.name().split_once('/') {
+            Some((_, relative)) if !relative.is_empty() => relative.trim_end_matches('/').to_string(),
+            _ => continue
+        };
+        if !is_safe_path(&relative) {
+            return Err(WorldError::UnsafePath(entry.name().to_string()))
+        }
+        notifier.notify_status(Notif::new("Restoring the world", index as u32, total, NotificationState::Running));
+
+        let path = target.join(&relative);
+        let result = if entry.is_dir() {
+            fs::create_dir_all(&path)
+        } else {
+            path.parent().map_or(Ok(()), fs::create_dir_all)
+                .and_then(|_| File::create(&path))
+                .and_then(|mut file| io::copy(&mut entry, &mut file))
+                .map(|_| ())
+        };
+        result.map_err(|err| WorldError::FileWriteFailed(path, err))?;
+    }
+    Ok(())
+}
+
+/// The folder names of the worlds with backups in `backups_dir`
+fn get_backed_up_worlds(backups_dir: &Path) -> Result<Vec<String>, WorldError> {
+    if !backups_dir.is_dir() {
+        return Ok(Vec::new())
+    }
+    let entries = fs::read_dir(backups_dir).map_err(|err| WorldError::DirectoryReadFailed(backups_dir.to_path_buf(), err))?;
+    Ok(entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().is_dir())
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .collect())
+}
+
+/// Whether level.dat of the world, which the game saves regularly, changed since the last backup of the world
+async fn has_changed(instance: &SimpleInstance, world: &str, world_path: &Path) -> bool {
+    let modified = match fs::metadata(world_path.join(LEVEL_DAT)).and_then(|metadata| metadata.modified()) {
+        Ok(modified) => DateTime::<Local>::from(modified).naive_local(),
+        Err(_) => return true
+    };
+    let backups = match get_instance_backups_dir(instance).await {
+        Ok(backups_dir) => BackupInfo::get_all(&backups_dir, world).unwrap_or_default(),
+        Err(_) => Vec::new()
+    };
+    backups.first().map_or(true, |newest| modified >= newest.created)
+}
+
+/// Waits until the world at `world_path` is not open in the game anymore.
+/// Fails if it is still open after [`IN_USE_MAX_WAIT`].
+async fn wait_until_closed(instance: &SimpleInstance, world: &str, world_path: &Path, app_handle: &AppHandle) -> Result<(), WorldError> {
+    let (instance, world_path, app_handle) = (instance.clone(), world_path.to_path_buf(), app_handle.clone());
+    let waited = tokio::task::spawn_blocking(move || {
+        let start = Instant::now();
+        while is_world_in_use(&instance, &world_path, app_handle.state::<RunningInstances>().inner()) {
+            if start.elapsed() >= IN_USE_MAX_WAIT {
+                return false
+            }
+            thread::sleep(IN_USE_POLL_INTERVAL);
+        }
+        true
+    }).await;
+    match waited {
+        Ok(true) => Ok(()),
+        Ok(false) => Err(WorldError::WorldInUse(world.to_string())),
+        Err(err) => {
+            warn!("Failed to wait for the world to be closed: {err}");
+            Ok(())
+        }
+    }
+}
+
+async fn get_instance_backups_dir(instance: &SimpleInstance) -> Result<PathBuf, WorldError> {
+    Ok(get_world_backups_dir().join(InstanceMetadata::get(&instance.instance_path).await?.id))
+}
+
+async fn get_backup_path(instance: &SimpleInstance, world: &str, file_name: &str) -> Result<PathBuf, WorldError> {
+    let not_found = || WorldError::BackupNotFound(world.to_string(), file_name.to_string());
+    if !is_folder_name(world) || !is_folder_name(file_name) {
+        return Err(not_found())
+    }
+    let path = get_instance_backups_dir(instance).await?.join(world).join(file_name);
+    if path.is_file() { Ok(path) } else { Err(not_found()) }
+}
+
+/// Makes sure `name` can be used as the name of a world folder
+fn check_name(name: String) -> Result<String, WorldError> {
+    if is_folder_name(&name) && !name.starts_with('.') { Ok(name) } else { Err(WorldError::InvalidName(name)) }
+}
+
+fn remove_dir(path: &Path) {
+    if let Err(err) = fs::remove_dir_all(path) {
+        warn!("Failed to remove {path:?}: {err}");
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Backups created at `times`, which are sorted from the newest to the oldest like [`BackupInfo::get_all`] does
+    fn backups(times: &[&str]) -> Vec<BackupInfo> {
+        times.iter().map(|time| {
+            let created = NaiveDateTime::parse_from_str(time, "%Y-%m-%d %H:%M").unwrap();
+            BackupInfo { world: "world".into(), file_name: format!("{}.zip", created.format(TIME_FORMAT)), created, size_bytes: 0 }
+        }).collect()
+    }
+
+    fn kept(settings: BackupSettings, backups: &[BackupInfo]) -> Vec<usize> {
+        let mut kept: Vec<usize> = settings.get_kept(backups).into_iter().collect();
+        kept.sort_unstable();
+        kept
+    }
+
+    #[test]
+    fn keeps_everything_without_policies() {
+        let backups = backups(&["2024-01-03 12:00", "2024-01-02 12:00", "2024-01-01 12:00"]);
+        assert_eq!(kept(BackupSettings::default(), &backups), [0, 1, 2]);
+    }
+
+    #[test]
+    fn keeps_the_last_backups() {
+        let backups = backups(&["2024-01-03 12:00", "2024-01-03 11:00", "2024-01-02 12:00", "2024-01-01 12:00"]);
+        let settings = |keep_last| BackupSettings { keep_last: Some(keep_last), ..Default::default() };
+        assert_eq!(kept(settings(2), &backups), [0, 1]);
+        assert_eq!(kept(settings(10), &backups), [0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn always_keeps_the_newest_backup() {
+        let backups = backups(&["2024-01-03 12:00", "2024-01-02 12:00"]);
+        assert_eq!(kept(BackupSettings { keep_last: Some(0), ..Default::default() }, &backups), [0]);
+        assert_eq!(kept(BackupSettings { keep_daily: Some(0), ..Default::default() }, &backups), [0]);
+        assert_eq!(kept(BackupSettings { keep_weekly: Some(0), ..Default::default() }, &backups), [0]);
+        assert!(kept(BackupSettings { keep_last: Some(0), ..Default::default() }, &[]).is_empty());
+    }
+
+    #[test]
+    fn keeps_the_newest_backup_of_each_day() {
+        let backups = backups(&["2024-01-03 18:00", "2024-01-03 09:00", "2024-01-02 12:00", "2024-01-01 23:00", "2024-01-01 01:00"]);
+        let settings = |keep_daily| BackupSettings { keep_daily: Some(keep_daily), ..Default::default() };
+        assert_eq!(kept(settings(2), &backups), [0, 2]);
+        assert_eq!(kept(settings(7), &backups), [0, 2, 3]);
+    }
+
+    #[test]
+    fn keeps_the_newest_backup_of_each_iso_week() {
+        let settings = BackupSettings { keep_weekly: Some(3), ..Default::default() };
+        // 2021-01-03 and 2020-12-28 are both in week 53 of 2020
+        let backups_2021 = backups(&["2021-01-04 10:00", "2021-01-03 10:00", "2020-12-28 10:00", "2020-12-27 10:00"]);
+        assert_eq!(kept(settings.clone(), &backups_2021), [0, 1, 3]);
+        // 2019-12-30 is in week 1 of 2020
+        let backups_2020 = backups(&["2020-01-06 10:00", "2020-01-02 10:00", "2019-12-30 10:00", "2019-12-29 10:00"]);
+        assert_eq!(kept(settings, &backups_2020), [0, 1, 3]);
+    }
+
+    #[test]
+    fn keeps_the_backups_any_policy_keeps() {
+        let backups = backups(&[
+            "2024-01-10 12:00",
+            "2024-01-10 08:00",
+            "2024-01-09 12:00",
+            "2024-01-05 12:00",
+            "2024-01-01 12:00",
+            "2023-12-31 12:00"
+        ]);
+        let settings = BackupSettings { keep_last: Some(1), keep_daily: Some(2), keep_weekly: Some(3), ..Default::default() };
+        assert_eq!(kept(settings, &backups), [0, 2, 3, 5]);
+    }
+}
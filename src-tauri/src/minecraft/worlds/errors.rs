@@ -0,0 +1,65 @@
+use std::path::PathBuf;
+
+use log::error;
+use serde::Serialize;
+use thiserror::Error;
+use tokio::io;
+
+use zip::result::ZipError;
+
+use crate::minecraft::{instances::errors::InstanceGatherError, nbt::NbtError};
+
+
+#[derive(Debug, Error)]
+pub enum WorldError {
+    #[error("Could not read the folder at {0:?}: {1}")]
+    DirectoryReadFailed(PathBuf, #[source] io::Error),
+    #[error("Failed to create directory at {0:?}: {1}")]
+    DirectoryCreateFailed(PathBuf, #[source] io::Error),
+    #[error("Failed to read file at {0:?}: {1}")]
+    FileReadFailed(PathBuf, #[source] io::Error),
+    #[error("Failed to write file at {0:?}: {1}")]
+    FileWriteFailed(PathBuf, #[source] io::Error),
+    #[error("Failed to move {0:?} to {1:?}: {2}")]
+    RenameFailed(PathBuf, PathBuf, #[source] io::Error),
+    #[error("Failed to read the backup at {0:?}: {1}")]
+    ArchiveReadFailed(PathBuf, #[source] ZipError),
+    #[error("Failed to write the backup at {0:?}: {1}")]
+    ArchiveWriteFailed(PathBuf, #[source] ZipError),
+    #[error("The backup contains the unsafe path {0}!")]
+    UnsafePath(String),
+    #[error("Failed to parse {0:?}: {1}")]
+    NbtParseFailed(PathBuf, #[source] NbtError),
+    #[error("Failed to write {0:?}: {1}")]
+    NbtWriteFailed(PathBuf, #[source] NbtError),
+    #[error("Invalid value for {0}: {1}")]
+    InvalidValue(String, String),
+    #[error("There is no player {0} in this world!")]
+    PlayerNotFound(String),
+    #[error("{0:?} has no {1}!")]
+    MissingField(PathBuf, String),
+    #[error("There is no world called {0}!")]
+    WorldNotFound(String),
+    #[error("There is no backup {1} of {0}!")]
+    BackupNotFound(String, String),
+    #[error("{0} is not a valid name for a world folder!")]
+    InvalidName(String),
+    #[error("There already is a world called {0}!")]
+    AlreadyExists(String),
+    #[error("{0} is currently open in the game!")]
+    WorldInUse(String),
+    #[error("Failed to whitelist path {0:?} for the asset protocol!")]
+    PathUnlockFailed(PathBuf, #[source] tauri::Error),
+    #[error(transparent)]
+    Instance(#[from] InstanceGatherError),
+}
+
+
+impl Serialize for WorldError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer {
+        error!("{self}");
+        serializer.serialize_str(&self.to_string())
+    }
+}
@@ -0,0 +1,204 @@
+use std::{collections::BTreeMap, fs, path::Path};
+
+use log::{*};
+use serde::Deserialize;
+use tauri::{AppHandle, Manager};
+
+use crate::minecraft::{
+    instances::instances::SimpleInstance,
+    launching::launching::RunningInstances,
+    nbt::{Compound, Tag, TAG_COMPOUND, TAG_DOUBLE}
+};
+
+use super::{
+    errors::WorldError,
+    worlds::{Difficulty, GameMode, WorldInfo, get_world_path, is_folder_name, is_world_in_use, read_level_dat, read_level_root, read_nbt_file, write_level_dat, write_nbt_file}
+};
+
+/// The players of worlds played on a server, by UUID
+const PLAYER_DATA: &str = "playerdata";
+
+
+/// Changes to the settings in the level.dat of a world, those that are `None` stay as they are
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct WorldChanges {
+    pub name: Option<String>,
+    pub game_mode: Option<GameMode>,
+    pub difficulty: Option<Difficulty>,
+    pub hardcore: Option<bool>,
+    pub allow_commands: Option<bool>,
+    /// Game rule -> new value, like "true" or "3". Rules set to an empty string are removed, so the game uses their default.
+    pub game_rules: BTreeMap<String, String>
+}
+
+/// What to reset of a player that is stuck
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct PlayerReset {
+    /// Moves the player to the world spawn in the overworld
+    pub position: bool,
+    pub inventory: bool
+}
+
+
+/// The game rules that are set in the world folder `world`, all values are strings
+#[tauri::command(async)]
+pub async fn get_game_rules(instance: SimpleInstance, world: String) -> Result<BTreeMap<String, String>, WorldError> {
+    let data = read_level_dat(&get_world_path(&instance, &world)?)?;
+    Ok(data.get_compound("GameRules").map(|rules| rules.iter()
+        .filter_map(|(rule, value)| Some((rule.to_string(), value.as_str()?.to_string())))
+        .collect()
+    ).unwrap_or_default())
+}
+
+/// Applies `changes` to the level.dat of the world folder `world`, which is kept as level.dat_old.
+/// Returns the changed world.
+#[tauri::command(async)]
+pub async fn edit_world(instance: SimpleInstance, world: String, changes: WorldChanges, app_handle: AppHandle) -> Result<WorldInfo, WorldError> {
+    let world_path = get_world_path(&instance, &world)?;
+    ensure_closed(&instance, &world, &world_path, &app_handle)?;
+
+    let (name, mut root) = read_level_root(&world_path)?;
+    let data = root.get_compound_mut("Data").ok_or_else(|| WorldError::MissingField(world_path.to_path_buf(), "Data".into()))?;
+    changes.apply(data)?;
+    write_level_dat(&world_path, &name, &root)?;
+
+    info!("Changed the settings of {world} in {}: {changes:?}", instance.name);
+    Ok(WorldInfo::get(&world_path))
+}
+
+/// The UUIDs of the players with data in the world folder `world`, apart from the singleplayer player in level.dat
+#[tauri::command(async)]
+pub async fn list_players(instance: SimpleInstance, world: String) -> Result<Vec<String>, WorldError> {
+    let dir = get_world_path(&instance, &world)?.join(PLAYER_DATA);
+    if !dir.is_dir() {
+        return Ok(Vec::new())
+    }
+    let entries = fs::read_dir(&dir).map_err(|err| WorldError::DirectoryReadFailed(dir.to_path_buf(), err))?;
+    let mut players: Vec<String> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| entry.file_name().to_string_lossy().strip_suffix(".dat").map(str::to_string))
+        .collect();
+    players.sort();
+    Ok(players)
+}
+
+/// Resets the position or inventory of the singleplayer player, or of the player with the UUID `player`
+#[tauri::command(async)]
+pub async fn reset_player(instance: SimpleInstance, world: String, player: Option<String>, reset: PlayerReset, app_handle: AppHandle) -> Result<(), WorldError> {
+    let world_path = get_world_path(&instance, &world)?;
+    ensure_closed(&instance, &world, &world_path, &app_handle)?;
+
+    match &player {
+        None => {
+            let (name, mut root) = read_level_root(&world_path)?;
+            let data = root.get_compound_mut("Data").ok_or_else(|| WorldError::MissingField(world_path.to_path_buf(), "Data".into()))?;
+            let spawn = get_spawn(data);
+            let player = data.get_compound_mut("Player").ok_or_else(|| WorldError::PlayerNotFound("of singleplayer".into()))?;
+            reset.apply(player, spawn);
+            write_level_dat(&world_path, &name, &root)?;
+        },
+        Some(uuid) => {
+            let path = world_path.join(PLAYER_DATA).join(format!("{uuid}.dat"));
+            if !is_folder_name(uuid) || !path.is_file() {
+                return Err(WorldError::PlayerNotFound(uuid.to_string()))
+            }
+            let spawn = get_spawn(&read_level_dat(&world_path)?);
+            let (name, mut player) = read_nbt_file(&path)?;
+            reset.apply(&mut player, spawn);
+            write_nbt_file(&path, &name, &player)?;
+        }
+    }
+
+    info!("Reset the player {} of {world} in {}: {reset:?}", player.as_deref().unwrap_or("of singleplayer"), instance.name);
+    Ok(())
+}
+
+
+impl WorldChanges {
+    fn apply(&self, data: &mut Compound) -> Result<(), WorldError> {
+        if let Some(name) = &self.name {
+            if name.trim().is_empty() {
+                return Err(WorldError::InvalidValue("the name".into(), name.to_string()))
+            }
+            data.insert("LevelName", Tag::String(name.to_string()));
+        }
+        if let Some(game_mode) = self.game_mode {
+            data.insert("GameType", Tag::Int(game_mode.get_id()));
+            // Singleplayer worlds load the game mode of the player instead
+            if let Some(player) = data.get_compound_mut("Player") {
+                player.insert("playerGameType", Tag::Int(game_mode.get_id()));
+            }
+        }
+        if let Some(difficulty) = self.difficulty {
+            data.insert("Difficulty", Tag::Byte(difficulty.get_id()));
+        }
+        if let Some(hardcore) = self.hardcore {
+            data.insert("hardcore", Tag::Byte(hardcore as i8));
+        }
+        if let Some(allow_commands) = self.allow_commands {
+            data.insert("allowCommands", Tag::Byte(allow_commands as i8));
+        }
+
+        if !self.game_rules.is_empty() {
+            if data.get_compound("GameRules").is_none() {
+                data.insert("GameRules", Tag::Compound(Compound::default()));
+            }
+            let rules = data.get_compound_mut("GameRules").ok_or_else(|| WorldError::InvalidValue("GameRules".into(), "not a compound".into()))?;
+            for (rule, value) in &self.game_rules {
+                if value.trim().is_empty() {
+                    rules.remove(rule);
+                } else {
+                    rules.insert(rule, Tag::String(value.trim().to_string()));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl PlayerReset {
+    fn apply(&self, player: &mut Compound, (x, y, z): (i64, i64, i64)) {
+        if self.position {
+            player.insert("Pos", Tag::List(TAG_DOUBLE, vec![Tag::Double(x as f64 + 0.5), Tag::Double(y as f64), Tag::Double(z as f64 + 0.5)]));
+            player.insert("Motion", Tag::List(TAG_DOUBLE, vec![Tag::Double(0.0); 3]));
+            // Dimensions were numbers before 1.16
+            let dimension = match player.get("Dimension") {
+                Some(Tag::Int(_)) => Tag::Int(0),
+                _ => Tag::String("minecraft:overworld".into())
+            };
+            player.insert("Dimension", dimension);
+            if player.get("FallDistance").is_some() {
+                player.insert("FallDistance", Tag::Float(0.0));
+            }
+            // Otherwise the player is put back onto its vehicle
+            player.remove("RootVehicle");
+        }
+        if self.inventory {
+            player.insert("Inventory", Tag::List(TAG_COMPOUND, Vec::new()));
+            // Armor and the offhand are kept here since 1.21.5
+            player.remove("equipment");
+        }
+    }
+}
+
+
+/// The world spawn point, old worlds without one spawn players around 0 0
+fn get_spawn(data: &Compound) -> (i64, i64, i64) {
+    // Stored as `spawn: { pos: [I; x, y, z] }` since 1.21.9
+    if let Some(Tag::IntArray(pos)) = data.get_path(&["spawn", "pos"]) {
+        if let [x, y, z] = pos[..] {
+            return (x as i64, y as i64, z as i64)
+        }
+    }
+    let get = |name: &str, default: i64| data.get(name).and_then(Tag::as_i64).unwrap_or(default);
+    (get("SpawnX", 0), get("SpawnY", 64), get("SpawnZ", 0))
+}
+
+fn ensure_closed(instance: &SimpleInstance, world: &str, world_path: &Path, app_handle: &AppHandle) -> Result<(), WorldError> {
+    if is_world_in_use(instance, world_path, app_handle.state::<RunningInstances>().inner()) {
+        return Err(WorldError::WorldInUse(world.to_string()))
+    }
+    Ok(())
+}
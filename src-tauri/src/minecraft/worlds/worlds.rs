@@ -0,0 +1,300 @@
+use std::{fs, io::ErrorKind, path::{Component, Path, PathBuf}};
+
+use chrono::NaiveDateTime;
+use log::{*};
+use serde::{Serialize, Deserialize};
+use tauri::{AppHandle, Manager};
+
+use crate::minecraft::{instances::instances::SimpleInstance, launching::launching::RunningInstances, nbt::{self, Compound}};
+
+use super::errors::WorldError;
+
+pub const LEVEL_DAT: &str = "level.dat";
+/// The previous level.dat, which the game also falls back to if level.dat is broken
+const LEVEL_DAT_OLD: &str = "level.dat_old";
+const WORLD_ICON: &str = "icon.png";
+/// Locked by the game while the world is open
+pub const SESSION_LOCK: &str = "session.lock";
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum GameMode {
+    Survival,
+    Creative,
+    Adventure,
+    Spectator
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Difficulty {
+    Peaceful,
+    Easy,
+    Normal,
+    Hard
+}
+
+/// A world in the "saves" folder of an instance, read from its level.dat
+#[derive(Debug, Clone, Serialize)]
+pub struct WorldInfo {
+    /// The name of the world folder, which identifies it
+    pub folder_name: String,
+    /// The name shown in the game, the folder name if level.dat could not be read
+    pub name: String,
+    pub game_mode: Option<GameMode>,
+    pub hardcore: bool,
+    /// Worlds before 1.8 only have a global difficulty
+    pub difficulty: Option<Difficulty>,
+    /// Whether cheats are enabled
+    pub allow_commands: bool,
+    pub last_played: Option<NaiveDateTime>,
+    /// Like "1.20.4", only written since 1.9
+    pub version_name: Option<String>,
+    pub data_version: Option<i32>,
+    /// As a string, since JavaScript numbers can't hold all 64 bit seeds
+    pub seed: Option<String>,
+    pub size_bytes: u64,
+    pub icon_path: Option<String>,
+    /// Why level.dat could not be read, if it couldn't
+    pub error: Option<String>
+}
+
+
+/// The worlds of `instance`, the most recently played first
+#[tauri::command(async)]
+pub async fn list_worlds(instance: SimpleInstance, app_handle: AppHandle) -> Result<Vec<WorldInfo>, WorldError> {
+    let saves_dir = instance.minecraft_path.join("saves");
+    if !saves_dir.is_dir() {
+        return Ok(Vec::new())
+    }
+
+    let entries = fs::read_dir(&saves_dir).map_err(|err| WorldError::DirectoryReadFailed(saves_dir.to_path_buf(), err))?;
+    let mut worlds: Vec<WorldInfo> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir())
+        .map(|path| WorldInfo::get(&path))
+        .collect();
+
+    for world in &worlds {
+        if let Some(icon_path) = &world.icon_path {
+            app_handle.asset_protocol_scope().allow_file(icon_path).map_err(
+                |err| WorldError::PathUnlockFailed(PathBuf::from(icon_path), err)
+            )?;
+        }
+    }
+    worlds.sort_by(|a, b| b.last_played.cmp(&a.last_played).then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase())));
+    Ok(worlds)
+}
+
+
+/// The path of the world folder `world` in "saves"
+pub fn get_world_path(instance: &SimpleInstance, world: &str) -> Result<PathBuf, WorldError> {
+    let path = instance.minecraft_path.join("saves").join(world);
+    if is_folder_name(world) && path.is_dir() { Ok(path) } else { Err(WorldError::WorldNotFound(world.to_string())) }
+}
+
+/// Whether `name` is the name of a single file or folder, without any other path components
+pub fn is_folder_name(name: &str) -> bool {
+    let mut components = Path::new(name).components();
+    matches!((components.next(), components.next()), (Some(Component::Normal(_)), None))
+}
+
+/// Whether the world at `world_path` may be open in the game. That is the case while its instance is running,
+/// or while the game holds the lock on its session.lock.
+pub fn is_world_in_use(instance: &SimpleInstance, world_path: &Path, running: &RunningInstances) -> bool {
+    if running.contains(&instance.instance_path) {
+        return true
+    }
+    is_session_locked(&world_path.join(SESSION_LOCK))
+}
+
+/// Locks on Linux and macOS are only advisory, so the file can still be read.
+/// Tries to lock it like Java's `FileChannel.tryLock` does, which fails while the game holds the lock.
+#[cfg(unix)]
+fn is_session_locked(path: &Path) -> bool {
+    use std::os::unix::io::AsRawFd;
+
+    let file = match fs::OpenOptions::new().read(true).write(true).open(path) {
+        Ok(file) => file,
+        Err(err) => return err.kind() != ErrorKind::NotFound
+    };
+    let mut lock: libc::flock = unsafe { std::mem::zeroed() };
+    lock.l_type = libc::F_WRLCK as _;
+    lock.l_whence = libc::SEEK_SET as _;
+    // The lock is released when the file is closed
+    unsafe { libc::fcntl(file.as_raw_fd(), libc::F_SETLK, &lock) == -1 }
+}
+
+/// Locked files can't be read on Windows
+#[cfg(not(unix))]
+fn is_session_locked(path: &Path) -> bool {
+    match fs::read(path) {
+        Ok(_) => false,
+        Err(err) => err.kind() != ErrorKind::NotFound
+    }
+}
+
+/// Reads the level.dat of the world at `path`, or level.dat_old if it is broken. Returns the `Data` compound.
+pub fn read_level_dat(path: &Path) -> Result<Compound, WorldError> {
+    let (_, mut root) = read_level_root(path)?;
+    match root.remove("Data") {
+        Some(nbt::Tag::Compound(data)) => Ok(data),
+        _ => Err(WorldError::MissingField(path.join(LEVEL_DAT), "Data".into()))
+    }
+}
+
+/// Like [`read_level_dat`], but returns the name and root compound of the file, which has the `Data` compound
+pub fn read_level_root(path: &Path) -> Result<(String, Compound), WorldError> {
+    read_level_file(&path.join(LEVEL_DAT)).or_else(|err| {
+        let old = path.join(LEVEL_DAT_OLD);
+        if !old.is_file() {
+            return Err(err)
+        }
+        warn!("{err}, using {LEVEL_DAT_OLD} instead");
+        read_level_file(&old)
+    })
+}
+
+/// Replaces the level.dat of the world at `path`, keeping the current one as level.dat_old
+pub fn write_level_dat(path: &Path, name: &str, root: &Compound) -> Result<(), WorldError> {
+    write_nbt_file(&path.join(LEVEL_DAT), name, root)
+}
+
+pub fn read_nbt_file(path: &Path) -> Result<(String, Compound), WorldError> {
+    let contents = fs::read(path).map_err(|err| WorldError::FileReadFailed(path.to_path_buf(), err))?;
+    nbt::read(&contents).map_err(|err| WorldError::NbtParseFailed(path.to_path_buf(), err))
+}
+
+/// Writes a gzip compressed NBT file the way the game does: into "<file>_new" first,
+/// which replaces the file once it is complete, while the previous version is kept as "<file>_old"
+pub fn write_nbt_file(path: &Path, name: &str, root: &Compound) -> Result<(), WorldError> {
+    let contents = nbt::write(name, root, nbt::Compression::Gzip).map_err(|err| WorldError::NbtWriteFailed(path.to_path_buf(), err))?;
+    let file_name = path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+    let new = path.with_file_name(format!("{file_name}_new"));
+    let old = path.with_file_name(format!("{file_name}_old"));
+
+    fs::write(&new, contents).map_err(|err| WorldError::FileWriteFailed(new.to_path_buf(), err))?;
+    if path.is_file() {
+        // Renaming onto an existing file fails on Windows
+        if old.is_file() {
+            fs::remove_file(&old).map_err(|err| WorldError::FileWriteFailed(old.to_path_buf(), err))?;
+        }
+        fs::rename(path, &old).map_err(|err| WorldError::RenameFailed(path.to_path_buf(), old.to_path_buf(), err))?;
+    }
+    fs::rename(&new, path).map_err(|err| WorldError::RenameFailed(new, path.to_path_buf(), err))
+}
+
+fn read_level_file(path: &Path) -> Result<(String, Compound), WorldError> {
+    let (name, root) = read_nbt_file(path)?;
+    if root.get_compound("Data").is_none() {
+        return Err(WorldError::MissingField(path.to_path_buf(), "Data".into()))
+    }
+    Ok((name, root))
+}
+
+
+impl WorldInfo {
+    /// Reads the world at `path`, worlds whose level.dat can't be read are listed with what is known from their folder
+    pub fn get(path: &Path) -> Self {
+        let folder_name = path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+        let icon = path.join(WORLD_ICON);
+        let mut world = Self {
+            name: folder_name.to_string(),
+            folder_name,
+            game_mode: None,
+            hardcore: false,
+            difficulty: None,
+            allow_commands: false,
+            last_played: None,
+            version_name: None,
+            data_version: None,
+            seed: None,
+            size_bytes: get_size(path),
+            icon_path: if icon.is_file() { Some(icon.to_string_lossy().to_string()) } else { None },
+            error: None
+        };
+
+        match read_level_dat(path) {
+            Ok(data) => world.apply_level_data(&data),
+            Err(err) => {
+                warn!("{err}");
+                world.error = Some(err.to_string());
+            }
+        }
+        world
+    }
+
+    fn apply_level_data(&mut self, data: &Compound) {
+        let get_int = |path: &[&str]| data.get_path(path).and_then(|tag| tag.as_i64());
+        let get_bool = |name: &str| data.get(name).and_then(|tag| tag.as_bool()).unwrap_or(false);
+
+        if let Some(name) = data.get("LevelName").and_then(|tag| tag.as_str()).filter(|name| !name.trim().is_empty()) {
+            self.name = name.to_string();
+        }
+        self.game_mode = get_int(&["GameType"]).and_then(GameMode::from_id);
+        self.hardcore = get_bool("hardcore");
+        self.difficulty = get_int(&["Difficulty"]).and_then(Difficulty::from_id);
+        self.allow_commands = get_bool("allowCommands");
+        self.last_played = get_int(&["LastPlayed"]).and_then(NaiveDateTime::from_timestamp_millis);
+        self.version_name = data.get_path(&["Version", "Name"]).and_then(|tag| tag.as_str()).map(str::to_string);
+        self.data_version = get_int(&["DataVersion"]).map(|version| version as i32);
+        // Moved into the world generation settings in 1.16
+        self.seed = get_int(&["WorldGenSettings", "seed"]).or_else(|| get_int(&["RandomSeed"])).map(|seed| seed.to_string());
+    }
+}
+
+impl GameMode {
+    pub fn from_id(id: i64) -> Option<Self> {
+        match id {
+            0 => Some(GameMode::Survival),
+            1 => Some(GameMode::Creative),
+            2 => Some(GameMode::Adventure),
+            3 => Some(GameMode::Spectator),
+            _ => None
+        }
+    }
+
+    pub fn get_id(&self) -> i32 {
+        match self {
+            GameMode::Survival => 0,
+            GameMode::Creative => 1,
+            GameMode::Adventure => 2,
+            GameMode::Spectator => 3
+        }
+    }
+}
+
+impl Difficulty {
+    pub fn from_id(id: i64) -> Option<Self> {
+        match id {
+            0 => Some(Difficulty::Peaceful),
+            1 => Some(Difficulty::Easy),
+            2 => Some(Difficulty::Normal),
+            3 => Some(Difficulty::Hard),
+            _ => None
+        }
+    }
+
+    pub fn get_id(&self) -> i8 {
+        match self {
+            Difficulty::Peaceful => 0,
+            Difficulty::Easy => 1,
+            Difficulty::Normal => 2,
+            Difficulty::Hard => 3
+        }
+    }
+}
+
+
+/// The size of all files in `path` and its subfolders, files that can't be read are left out
+fn get_size(path: &Path) -> u64 {
+    fs::read_dir(path).map_or(0, |entries| entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| match entry.file_type() {
+            Ok(file_type) if file_type.is_dir() => get_size(&entry.path()),
+            Ok(file_type) if file_type.is_file() => entry.metadata().map_or(0, |metadata| metadata.len()),
+            _ => 0
+        })
+        .sum()
+    )
+}
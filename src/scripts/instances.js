@@ -9,31 +9,229 @@ import { getJavaForVersion } from "./javas"
  * @type {import("svelte/store").Writable<SimpleInstance[]>}
  */
 export const instanceStore = writable([])
+/**
+ * Instance folders that could not be read during the last gathering
+ * @type {import("svelte/store").Writable<BrokenInstance[]>}
+ */
+export const brokenInstanceStore = writable([])
 export let instancesFinished = false
 
-export async function gatherInstances() {
+/**
+ * Unchanged instances are taken from the scan cache of the backend, unless `forceRescan` is set
+ * @param {Boolean} forceRescan
+ */
+export async function gatherInstances(forceRescan = false) {
     createNotification('instance_gather', 'Gathering Instances...')
 
-    await invoke('get_instances').then( /** @param {SimpleInstance[]} instances */ instances => {
-        console.warn(instances.length)
-        onInstanceFinish(instances)
+    await invoke('get_instances', { forceRescan }).then( /** @param {InstanceList} list */ list => {
+        console.warn(list.instances.length)
+        onInstanceFinish(list)
     }).catch(err => {
         finishNotification('instance_gather', `Failed to read instances: ${err}`, 'error')
     })
 }
 
 /**
- * @param {SimpleInstance[]} instances
+ * @param {InstanceList} list
  */
-function onInstanceFinish(instances) {
-    instances.forEach(i => {
-        if(i.icon_path !== "default_instance.png") i.icon_path = convertFileSrc(i.icon_path)
-    })
-    finishNotification('instance_gather', `Finished gathering <b class="font-semibold mx-1">${instances.length}</b> Instances!`, 'success')
+function onInstanceFinish({ instances, errors }) {
+    instances.forEach(convertIcon)
+    if(errors.length) {
+        finishNotification('instance_gather', `Finished gathering <b class="font-semibold mx-1">${instances.length}</b> Instances, <b class="font-semibold mx-1">${errors.length}</b> could not be read!`, 'warning')
+    } else {
+        finishNotification('instance_gather', `Finished gathering <b class="font-semibold mx-1">${instances.length}</b> Instances!`, 'success')
+    }
     instancesFinished = true
+    brokenInstanceStore.set(errors)
     instanceStore.set(instances)
 }
 
+/**
+ * @param {SimpleInstance} instance
+ */
+function convertIcon(instance) {
+    if(instance.icon_path !== "default_instance.png") instance.icon_path = convertFileSrc(instance.icon_path)
+}
+
+// The backend watches the instance folders after the first gathering and reports changes made by other launchers
+listen('instance_added', /** @param {{ payload: SimpleInstance }} event */ event => {
+    convertIcon(event.payload)
+    instanceStore.update(instances => [...instances.filter(i => i.instance_path !== event.payload.instance_path), event.payload])
+})
+listen('instance_changed', /** @param {{ payload: SimpleInstance }} event */ event => {
+    convertIcon(event.payload)
+    instanceStore.update(instances => instances.map(i => i.instance_path === event.payload.instance_path ? event.payload : i))
+})
+listen('instance_removed', /** @param {{ payload: SimpleInstance }} event */ event => {
+    instanceStore.update(instances => instances.filter(i => i.instance_path !== event.payload.instance_path))
+})
+
+/**
+ * Creates a new native yamcl instance in the instance folder
+ * @param {String} name
+ * @param {String} mcVersion
+ * @param {ModLoaders} loader
+ * @param {String | null} loaderVersion
+ * @param {String | null} icon Path of an image to use as the icon
+ */
+export async function createInstance(name, mcVersion, loader, loaderVersion, icon) {
+    createNotification(`instance_create_${name}`, `Creating '${name}'...`)
+    await invoke('create_instance', { name, mcVersion, loader, loaderVersion, icon }).then(() => {
+        finishNotification(`instance_create_${name}`, `Created instance '${name}'!`, 'success')
+        gatherInstances()
+    }).catch(err => {
+        finishNotification(`instance_create_${name}`, `Failed to create instance '${name}': ${err}`, 'error')
+    })
+}
+
+/**
+ * Imports a Modrinth (.mrpack) or CurseForge (.zip) modpack as a new native yamcl instance
+ * @param {String} path Path of the modpack file
+ */
+export async function importModpack(path) {
+    createNotification('modpack_import', 'Importing modpack...')
+    const unlisten = await listen('modpack_import_status', event => {
+        if(event.payload.status === 'running') {
+            createNotification('modpack_import', `${event.payload.text} (${event.payload.progress}/${event.payload.max_progress})`)
+        } else if(event.payload.status === 'warning' && event.payload.text.endsWith('downloaded manually!')) {
+            finishNotification(`modpack_import_${event.payload.text}`, event.payload.text, 'warning')
+        } else {
+            finishNotification('modpack_import', event.payload.text, event.payload.status)
+        }
+    })
+    const command = path.endsWith('.mrpack') ? 'import_mrpack' : 'import_cf_pack'
+    await invoke(command, { path }).then(result => {
+        /** @type {{ name: String, file_name: String, path: String, url: String | null }[]} */
+        const missing = result.missing_files ?? []
+        missing.forEach(file => console.warn(`Download ${file.file_name} from ${file.url} into ${file.path}`))
+        gatherInstances()
+    }).catch(err => {
+        finishNotification('modpack_import', `Failed to import modpack: ${err}`, 'error')
+    }).finally(unlisten)
+}
+
+/**
+ * @param {SimpleInstance} instance
+ * @param {String} name Name of the copy
+ * @param {'Copy' | 'Reflink' | 'Hardlink'} mode
+ * @param {Boolean} includeSaves
+ */
+export async function cloneInstance(instance, name, mode, includeSaves) {
+    const id = `instance_clone_${instance.id}`
+    createNotification(id, `Cloning '${instance.name}'...`)
+    await invoke('clone_instance', { instance, name, mode, includeSaves }).then(() => {
+        finishNotification(id, `Cloned '${instance.name}' to '${name}'!`, 'success')
+        gatherInstances()
+    }).catch(err => {
+        finishNotification(id, `Failed to clone '${instance.name}': ${err}`, 'error')
+    })
+}
+
+/**
+ * @param {SimpleInstance} instance
+ * @param {String} name
+ */
+export async function renameInstance(instance, name) {
+    await invoke('rename_instance', { instance, name }).then(() => {
+        gatherInstances()
+    }).catch(err => {
+        finishNotification(`instance_rename_${instance.id}`, `Failed to rename '${instance.name}': ${err}`, 'error')
+    })
+}
+
+/**
+ * Moves an instance to the trash, it can be restored from the settings
+ * @param {SimpleInstance} instance
+ */
+export async function deleteInstance(instance) {
+    const id = `instance_delete_${instance.id}`
+    await invoke('delete_instance', { instance }).then(() => {
+        finishNotification(id, `Moved '${instance.name}' to the trash.`, 'success')
+        gatherInstances()
+    }).catch(err => {
+        finishNotification(id, `Failed to delete '${instance.name}': ${err}`, 'error')
+    })
+}
+
+/**
+ * Also moves the instance into the same group in Prism/MultiMC
+ * @param {SimpleInstance} instance
+ * @param {String | null} group
+ */
+export async function setInstanceGroup(instance, group) {
+    await invoke('set_instance_group', { instance, group }).then(() => {
+        gatherInstances()
+    }).catch(err => {
+        finishNotification(`instance_group_${instance.id}`, `Failed to change the group of '${instance.name}': ${err}`, 'error')
+    })
+}
+
+/**
+ * @param {SimpleInstance} instance
+ * @param {String[]} tags
+ */
+export async function setInstanceTags(instance, tags) {
+    await invoke('set_instance_tags', { instance, tags }).then(() => {
+        gatherInstances()
+    }).catch(err => {
+        finishNotification(`instance_tags_${instance.id}`, `Failed to change the tags of '${instance.name}': ${err}`, 'error')
+    })
+}
+
+/**
+ * @param {SimpleInstance} instance
+ * @param {Boolean} favorite
+ */
+export async function setInstanceFavorite(instance, favorite) {
+    await invoke('set_instance_favorite', { instance, favorite }).then(() => {
+        gatherInstances()
+    }).catch(err => {
+        finishNotification(`instance_favorite_${instance.id}`, `Failed to change '${instance.name}': ${err}`, 'error')
+    })
+}
+
+/**
+ * @returns {Promise<TrashEntry[]>}
+ */
+export async function getTrash() {
+    return await invoke('get_trash')
+}
+
+/**
+ * @param {TrashEntry} entry
+ */
+export async function restoreInstance(entry) {
+    await invoke('restore_instance', { id: entry.id }).then(() => {
+        finishNotification(`instance_restore_${entry.id}`, `Restored '${entry.name}'!`, 'success')
+        gatherInstances()
+    }).catch(err => {
+        finishNotification(`instance_restore_${entry.id}`, `Failed to restore '${entry.name}': ${err}`, 'error')
+    })
+}
+
+/**
+ * Exports an instance as a modpack
+ * @param {SimpleInstance} instance
+ * @param {'Modrinth' | 'CurseForge' | 'MultiMC'} format
+ * @param {String[]} folders Files and folders of the game directory to include
+ * @param {String} version Version of the exported pack
+ * @param {String} target Path of the file to write
+ */
+export async function exportInstance(instance, format, folders, version, target) {
+    const id = `instance_export_${instance.id}`
+    createNotification(id, `Exporting '${instance.name}'...`)
+    const unlisten = await listen('modpack_export_status', event => {
+        if(event.payload.status === 'running') {
+            createNotification(id, `${event.payload.text} (${event.payload.progress}/${event.payload.max_progress})`)
+        } else {
+            finishNotification(id, event.payload.text, event.payload.status)
+        }
+    })
+    await invoke('export_instance', { instance, format, folders, version, target }).catch(err => {
+        finishNotification(id, `Failed to export '${instance.name}': ${err}`, 'error')
+    }).finally(unlisten)
+}
+
 /**
  * @param {SimpleInstance} instance
  */
@@ -41,11 +239,16 @@ export async function launchInstance(instance) {
     let { name, id, mc_version } = instance
     console.log(`Launching instance: ${name}...`)
     createNotification(`instance_launch_${id}`, `Launching '${name}'...`)
-    getJavaForVersion(mc_version).then(async (java) => {
+    getJavaForVersion(instance).then(async (java) => {
         console.log(`Using java path: ${java.path}, with args ${java.args}`)
+        let warnings = 0
         const unlisten = await listen(`${id}_status`, event => {
             console.warn(event)
-            finishNotification(`instance_launch_${id}`, event.payload.text, event.payload.status)
+            if(event.payload.status === 'warning') {
+                finishNotification(`instance_warning_${id}_${warnings++}`, event.payload.text, 'warning')
+            } else {
+                finishNotification(`instance_launch_${id}`, event.payload.text, event.payload.status)
+            }
         })
         await invoke('launch_instance',
             { instance, java }
@@ -59,3 +262,28 @@ export async function launchInstance(instance) {
     })
 }
 
+/**
+ * Starts the instance and stops it again once it reached the title screen, to check that it works
+ * @param {SimpleInstance} instance
+ * @returns {Promise<RunReport | null>}
+ */
+export async function smokeTestInstance(instance) {
+    const { name, id } = instance
+    createNotification(`instance_smoke_test_${id}`, `Testing '${name}'...`)
+    const unlisten = await listen(`${id}_smoke_test`, event => {
+        if(event.payload.status === 'running') {
+            createNotification(`instance_smoke_test_${id}`, `Testing '${name}': ${event.payload.text}`)
+        } else {
+            finishNotification(`instance_smoke_test_${id}`, `Testing '${name}': ${event.payload.text}`, event.payload.status)
+        }
+    })
+    const report = await getJavaForVersion(instance).then(java => {
+        return invoke('smoke_test', { instance, java, options: null })
+    }).catch(err => {
+        finishNotification(`instance_smoke_test_${id}`, `Failed to test '${name}': ${err}`, 'error')
+        return null
+    })
+    unlisten()
+    return report
+}
+
@@ -1,4 +1,5 @@
 import { invoke } from "@tauri-apps/api";
+import { listen } from "@tauri-apps/api/event";
 import { open, confirm } from "@tauri-apps/api/dialog";
 import { writable } from "svelte/store";
 import { getMinecraftVersions } from "./versions";
@@ -116,10 +117,11 @@ export async function getJavaVersion(path, args) {
 }
 
 /**
- * @param {String} mcVer The requested Minecraft version
- * @returns {Promise<JavaDetails>} The Java version to use for mc_ver
+ * @param {SimpleInstance} instance The instance that is about to be started
+ * @returns {Promise<JavaDetails>} The Java version to use for its Minecraft version
  */
-export async function getJavaForVersion(mcVer) {
+export async function getJavaForVersion(instance) {
+    const mcVer = instance.mc_version
     await getJavaSettings()
     let mcVersions = await getMinecraftVersions()
     let releaseTime = new Date(mcVersions.versions.find(v => v.id == mcVer)?.releaseTime ?? 0).getTime()
@@ -135,7 +137,34 @@ export async function getJavaForVersion(mcVer) {
     if(java) {
         return Promise.resolve(java)
     } else {
-        return Promise.reject("Could not find Java version to use for this instance!")
+        return installJavaRuntime(instance)
+    }
+}
+
+/**
+ * Downloads the Java runtime Mojang provides for the Minecraft version of an instance and adds it to the Java settings
+ * @param {SimpleInstance} instance The instance that needs the runtime
+ * @returns {Promise<JavaDetails>} The installed Java runtime
+ */
+export async function installJavaRuntime(instance) {
+    const mcVer = instance.mc_version
+    createNotification('java_runtime', `Installing Java for Minecraft ${mcVer}...`)
+    const unlisten = await listen('java_runtime_status', event => {
+        if(event.payload.status === 'running') {
+            createNotification('java_runtime', `${event.payload.text} (${event.payload.progress}/${event.payload.max_progress})`)
+        } else {
+            finishNotification('java_runtime', event.payload.text, event.payload.status)
+        }
+    })
+    try {
+        /** @type {JavaDetails} */
+        let java = await invoke('install_java_runtime', { instance })
+        await getJavaSettings()
+        return java
+    } catch(e) {
+        return Promise.reject(`Could not find or install Java for this instance: ${e}`)
+    } finally {
+        unlisten()
     }
 }
 
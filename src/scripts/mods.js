@@ -0,0 +1,127 @@
+import { invoke } from "@tauri-apps/api"
+import { convertFileSrc } from "@tauri-apps/api/tauri"
+import { listen } from "@tauri-apps/api/event"
+import { createNotification, finishNotification } from "./notificationSystem"
+import { getJavaForVersion } from "./javas"
+
+/**
+ * Reads the mods folder of an instance
+ * @param {SimpleInstance} instance
+ * @returns {Promise<ModInfo[]>}
+ */
+export async function listMods(instance) {
+    return await invoke('list_mods', { instance }).then(/** @param {ModInfo[]} mods */ mods => {
+        mods.forEach(mod => {
+            if(mod.icon_path) mod.icon_path = convertFileSrc(mod.icon_path)
+        })
+        return mods
+    }).catch(err => {
+        finishNotification(`mods_list_${instance.id}`, `Failed to read the mods of '${instance.name}': ${err}`, 'error')
+        return []
+    })
+}
+
+/**
+ * Enables or disables a mod by renaming it to or from `.disabled`
+ * @param {SimpleInstance} instance
+ * @param {ModInfo} mod
+ * @param {Boolean} enabled
+ * @returns {Promise<String | null>} The new file name of the mod
+ */
+export async function setModEnabled(instance, mod, enabled) {
+    return await invoke('set_mod_enabled', { instance, fileName: mod.file_name, enabled }).then(/** @param {String} fileName */ fileName => {
+        return fileName
+    }).catch(err => {
+        finishNotification(`mod_toggle_${instance.id}`, `Failed to change '${mod.name}': ${err}`, 'error')
+        return null
+    })
+}
+
+/**
+ * @param {SimpleInstance} instance
+ * @returns {Promise<Record<String, ModProfile>>}
+ */
+export async function getModProfiles(instance) {
+    return await invoke('get_mod_profiles', { instance }).catch(err => {
+        finishNotification(`mod_profiles_${instance.id}`, `Failed to read the mod profiles of '${instance.name}': ${err}`, 'error')
+        return {}
+    })
+}
+
+/**
+ * Saves the currently enabled mods as a profile
+ * @param {SimpleInstance} instance
+ * @param {String} name
+ */
+export async function saveModProfile(instance, name) {
+    await invoke('save_mod_profile', { instance, name }).catch(err => {
+        finishNotification(`mod_profiles_${instance.id}`, `Failed to save mod profile '${name}': ${err}`, 'error')
+    })
+}
+
+/**
+ * @param {SimpleInstance} instance
+ * @param {String} name
+ */
+export async function deleteModProfile(instance, name) {
+    await invoke('delete_mod_profile', { instance, name }).catch(err => {
+        finishNotification(`mod_profiles_${instance.id}`, `Failed to delete mod profile '${name}': ${err}`, 'error')
+    })
+}
+
+/**
+ * Enables exactly the mods of a profile, refused while the instance is running
+ * @param {SimpleInstance} instance
+ * @param {String} name
+ */
+export async function applyModProfile(instance, name) {
+    await invoke('apply_mod_profile', { instance, name }).catch(err => {
+        finishNotification(`mod_profiles_${instance.id}`, `Failed to apply mod profile '${name}': ${err}`, 'error')
+    })
+}
+
+/**
+ * Checks the enabled mods for missing dependencies, incompatibilities and duplicates
+ * @param {SimpleInstance} instance
+ * @returns {Promise<ModIssue[]>}
+ */
+export async function checkMods(instance) {
+    return await invoke('check_mods', { instance, java: null }).catch(err => {
+        finishNotification(`mods_check_${instance.id}`, `Failed to check the mods of '${instance.name}': ${err}`, 'error')
+        return []
+    })
+}
+
+/**
+ * Launches the instance over and over with fewer mods until it finds the ones that make it crash on startup.
+ * The enabled mods are restored afterwards.
+ * @param {SimpleInstance} instance
+ * @returns {Promise<BisectResult | null>}
+ */
+export async function bisectMods(instance) {
+    const { id, name } = instance
+    createNotification(`mods_bisect_${id}`, `Bisecting the mods of '${name}'...`)
+    const unlisten = await listen(`${id}_bisect`, event => {
+        if(event.payload.status === 'running') {
+            createNotification(`mods_bisect_${id}`, `Bisecting '${name}': ${event.payload.text}`)
+        } else {
+            finishNotification(`mods_bisect_${id}`, `Bisecting '${name}': ${event.payload.text}`, event.payload.status)
+        }
+    })
+    const result = await getJavaForVersion(instance).then(java => {
+        return invoke('bisect_mods', { instance, java, options: null })
+    }).catch(err => {
+        finishNotification(`mods_bisect_${id}`, `Failed to bisect the mods of '${name}': ${err}`, 'error')
+        return null
+    })
+    unlisten()
+    return result
+}
+
+/**
+ * Stops bisecting after the current run, the mods are restored
+ * @param {SimpleInstance} instance
+ */
+export async function cancelBisect(instance) {
+    await invoke('cancel_bisect', { instance })
+}
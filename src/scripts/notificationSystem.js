@@ -2,12 +2,12 @@ import { writable } from "svelte/store";
 
 
 /**
- * @type {import("svelte/store").Writable<{id: string, contents: string, status: 'running' | 'error' | 'success'}[]>}
+ * @type {import("svelte/store").Writable<{id: string, contents: string, status: 'running' | 'warning' | 'error' | 'success'}[]>}
  */
 export const notificationStore = writable([])
 
 /**
- * @type {Object.<string,{contents: string, status: 'running' | 'error' | 'success'}>}
+ * @type {Object.<string,{contents: string, status: 'running' | 'warning' | 'error' | 'success'}>}
  */
 let notifs = {}
 
@@ -16,7 +16,7 @@ let notifs = {}
 /**
  * @param {string} id
  * @param {string} contents
- * @param {'running' | 'error' | 'success'} [status='running']
+ * @param {'running' | 'warning' | 'error' | 'success'} [status='running']
  */
 export function createNotification(id, contents, status) {
     notifs[id] = { contents, status: status ?? 'running'}
@@ -26,7 +26,7 @@ export function createNotification(id, contents, status) {
 /**
  * @param {string} id
  * @param {string} newContents
- * @param {'error' | 'success' | undefined} newStatus
+ * @param {'warning' | 'error' | 'success' | undefined} newStatus
  */
 export function finishNotification(id, newContents, newStatus) {
     let status = newStatus ?? 'success'
@@ -42,12 +42,12 @@ export function getNotification(id) {
 }
 
 /**
- * @param {'running' | 'error' | 'success'} status
+ * @param {'running' | 'warning' | 'error' | 'success'} status
  * @param {string} id
  */
 function updateNotifs(status, id) {
     updateNotifStore()
-    if(status === 'success' || status === 'error') {
+    if(status !== 'running') {
         setTimeout(() => {
             delete notifs[id]
             updateNotifStore()
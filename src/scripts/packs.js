@@ -0,0 +1,74 @@
+import { invoke } from "@tauri-apps/api"
+import { convertFileSrc } from "@tauri-apps/api/tauri"
+import { finishNotification } from "./notificationSystem"
+
+/**
+ * @param {SimpleInstance} instance
+ * @returns {Promise<PackInfo[]>} The enabled packs first, from the highest priority to the lowest
+ */
+export async function listResourcePacks(instance) {
+    return await invoke('list_resource_packs', { instance }).then(/** @param {PackInfo[]} packs */ packs => {
+        return packs.map(convertPackIcon)
+    }).catch(err => {
+        finishNotification(`packs_list_${instance.id}`, `Failed to read the resource packs of '${instance.name}': ${err}`, 'error')
+        return []
+    })
+}
+
+/**
+ * @param {SimpleInstance} instance
+ * @param {String[]} enabled File names of the enabled packs, from the highest priority to the lowest
+ */
+export async function setResourcePacks(instance, enabled) {
+    await invoke('set_resource_packs', { instance, enabled }).catch(err => {
+        finishNotification(`packs_set_${instance.id}`, `Failed to change the resource packs of '${instance.name}': ${err}`, 'error')
+    })
+}
+
+/**
+ * @param {SimpleInstance} instance
+ * @returns {Promise<ShaderPackInfo[]>}
+ */
+export async function listShaderPacks(instance) {
+    return await invoke('list_shader_packs', { instance }).catch(err => {
+        finishNotification(`packs_list_${instance.id}`, `Failed to read the shader packs of '${instance.name}': ${err}`, 'error')
+        return []
+    })
+}
+
+/**
+ * @param {SimpleInstance} instance
+ * @param {String} world The folder name of the world
+ * @returns {Promise<PackInfo[]>}
+ */
+export async function listDataPacks(instance, world) {
+    return await invoke('list_data_packs', { instance, world }).then(/** @param {PackInfo[]} packs */ packs => {
+        return packs.map(convertPackIcon)
+    }).catch(err => {
+        finishNotification(`packs_list_${instance.id}`, `Failed to read the data packs of '${world}': ${err}`, 'error')
+        return []
+    })
+}
+
+/**
+ * Copies a zip file or folder into the instance
+ * @param {SimpleInstance} instance
+ * @param {PackKind} kind
+ * @param {String} path
+ * @param {String | null} world Where data packs go
+ * @returns {Promise<String | null>} The name of the imported pack
+ */
+export async function importPack(instance, kind, path, world = null) {
+    return await invoke('import_pack', { instance, kind, path, world }).catch(err => {
+        finishNotification(`packs_import_${instance.id}`, `Failed to import '${path}': ${err}`, 'error')
+        return null
+    })
+}
+
+/**
+ * @param {PackInfo} pack
+ */
+function convertPackIcon(pack) {
+    if(pack.icon_path) pack.icon_path = convertFileSrc(pack.icon_path)
+    return pack
+}
@@ -2,17 +2,29 @@
  * AppSettings Struct
  * @typedef {{
  *  instance_size: Number,
- *  instance_path: String | null,
- *  icon_path: String | null,
- *  java_settings: JavaDetails[]
+ *  instance_roots: InstanceRoot[],
+ *  java_settings: JavaDetails[],
+ *  curseforge_api_url: String,
+ *  curseforge_api_key: String | null,
+ *  trash_retention_days: Number
  * }} AppSettings
 */
 
+/**
+ * InstanceRoot Struct
+ * @typedef {{
+ *  label: String,
+ *  path: String,
+ *  enabled: Boolean,
+ *  icon_path: String | null
+ * }} InstanceRoot
+*/
+
 
 
 /**
  * InstanceType Enum
- * @typedef { "MultiMC" | "CurseForge" } MCInstanceType
+ * @typedef { "MultiMC" | "CurseForge" | "Yamcl" | "Modrinth" | "ATLauncher" | "GDLauncher" | "Technic" } MCInstanceType
 */
 
 /**
@@ -36,14 +48,167 @@
  *  icon_path: String,
  *  instance_path: String,
 *   minecraft_path: String,
- *  id: Number,
+ *  id: String,
  *  mc_version: String,
  *  modloader: ModLoader,
  *  last_played: String | null,
- *  instance_type: MCInstanceType
+ *  instance_type: MCInstanceType,
+ *  group: String | null,
+ *  tags: String[],
+ *  favorite: Boolean,
+ *  root: String
  * }} SimpleInstance
  */
 
+/**
+ * BrokenInstance Struct, an instance folder that could not be read
+ * @typedef {{
+ *  path: String,
+ *  error: String
+ * }} BrokenInstance
+ */
+
+/**
+ * InstanceList Struct
+ * @typedef {{
+ *  instances: SimpleInstance[],
+ *  errors: BrokenInstance[]
+ * }} InstanceList
+ */
+
+/**
+ * ModInfo Struct
+ * @typedef {{
+ *  id: String,
+ *  name: String,
+ *  version: String | null,
+ *  authors: String[],
+ *  description: String | null,
+ *  dependencies: { id: String, version: String | null, required: Boolean }[],
+ *  breaks: { id: String, version: String | null, required: Boolean }[],
+ *  provides: { id: String, version: String | null }[],
+ *  source: "Quilt" | "Fabric" | "NeoForge" | "Forge" | "McModInfo" | null,
+ *  icon_path: String | null,
+ *  file_name: String,
+ *  sha1: String,
+ *  enabled: Boolean
+ * }} ModInfo
+ */
+
+/**
+ * ModProfile Struct
+ * @typedef {{
+ *  enabled: String[]
+ * }} ModProfile
+ */
+
+/**
+ * ModIssue Struct
+ * @typedef {{
+ *  kind: "MissingDependency" | "VersionMismatch" | "Incompatible" | "DuplicateId" | "WrongLoader" | "WrongMinecraftVersion",
+ *  file_name: String,
+ *  message: String
+ * }} ModIssue
+ */
+
+/**
+ * RunReport Struct, the outcome is serialized as an externally tagged enum
+ * @typedef {{
+ *  outcome: { Passed: { marker: String } } | { Crashed: { reason: String } } | { ErrorLogged: { line: String } } | "TimedOut" | "Cancelled",
+ *  passed: Boolean,
+ *  duration_ms: Number,
+ *  log: String[]
+ * }} RunReport
+ */
+
+/**
+ * @typedef {"ResourcePack" | "ShaderPack" | "DataPack"} PackKind
+ */
+
+/**
+ * PackInfo Struct
+ * @typedef {{
+ *  file_name: String,
+ *  description: String,
+ *  pack_format: Number | null,
+ *  supported_formats: [Number, Number] | null,
+ *  compatibility: "Compatible" | "TooOld" | "TooNew" | "Unknown",
+ *  icon_path: String | null,
+ *  enabled: Boolean,
+ *  is_folder: Boolean
+ * }} PackInfo
+ */
+
+/**
+ * ShaderPackInfo Struct
+ * @typedef {{
+ *  file_name: String,
+ *  format: "OptiFine" | "Iris" | "Invalid",
+ *  selected: Boolean,
+ *  is_folder: Boolean
+ * }} ShaderPackInfo
+ */
+
+/**
+ * WorldInfo Struct
+ * @typedef {{
+ *  folder_name: String,
+ *  name: String,
+ *  game_mode: "Survival" | "Creative" | "Adventure" | "Spectator" | null,
+ *  hardcore: Boolean,
+ *  difficulty: "Peaceful" | "Easy" | "Normal" | "Hard" | null,
+ *  allow_commands: Boolean,
+ *  last_played: String | null,
+ *  version_name: String | null,
+ *  data_version: Number | null,
+ *  seed: String | null,
+ *  size_bytes: Number,
+ *  icon_path: String | null,
+ *  error: String | null
+ * }} WorldInfo
+ */
+
+/**
+ * WorldChanges Struct, settings that are left out stay as they are
+ * @typedef {{
+ *  name?: String,
+ *  game_mode?: "Survival" | "Creative" | "Adventure" | "Spectator",
+ *  difficulty?: "Peaceful" | "Easy" | "Normal" | "Hard",
+ *  hardcore?: Boolean,
+ *  allow_commands?: Boolean,
+ *  game_rules?: Record<String, String>
+ * }} WorldChanges
+ */
+
+/**
+ * BackupInfo Struct
+ * @typedef {{
+ *  world: String,
+ *  file_name: String,
+ *  created: String,
+ *  size_bytes: Number
+ * }} BackupInfo
+ */
+
+/**
+ * BackupSettings Struct
+ * @typedef {{
+ *  before_launch: Boolean,
+ *  after_exit: Boolean,
+ *  keep_last: Number | null,
+ *  keep_daily: Number | null,
+ *  keep_weekly: Number | null
+ * }} BackupSettings
+ */
+
+/**
+ * BisectResult Struct
+ * @typedef {{
+ *  culprits: String[],
+ *  runs: Number
+ * }} BisectResult
+ */
+
 
 /**
  * JavaDetails Struct
@@ -85,4 +250,15 @@
  *      "sha1": String,
  *      "complianceLevel": Number
  * }} MCVersion
- */
\ No newline at end of file
+ */
+
+/**
+ * A deleted instance
+ * @typedef {{
+ *  id: String,
+ *  name: String,
+ *  original_path: String,
+ *  instance_type: MCInstanceType,
+ *  deleted: String
+ * }} TrashEntry
+*/
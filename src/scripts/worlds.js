@@ -0,0 +1,171 @@
+import { invoke } from "@tauri-apps/api"
+import { listen } from "@tauri-apps/api/event"
+import { convertFileSrc } from "@tauri-apps/api/tauri"
+import { createNotification, finishNotification } from "./notificationSystem"
+
+/**
+ * @param {SimpleInstance} instance
+ * @returns {Promise<WorldInfo[]>} The most recently played first
+ */
+export async function listWorlds(instance) {
+    return await invoke('list_worlds', { instance }).then(/** @param {WorldInfo[]} worlds */ worlds => {
+        return worlds.map(convertWorldIcon)
+    }).catch(err => {
+        finishNotification(`worlds_list_${instance.id}`, `Failed to read the worlds of '${instance.name}': ${err}`, 'error')
+        return []
+    })
+}
+
+/**
+ * @param {SimpleInstance} instance
+ * @param {String | null} world The folder name of the world, `null` for the backups of all worlds
+ * @returns {Promise<BackupInfo[]>} The newest first
+ */
+export async function listBackups(instance, world = null) {
+    return await invoke('list_backups', { instance, world }).catch(err => {
+        finishNotification(`worlds_backups_${instance.id}`, `Failed to read the backups of '${instance.name}': ${err}`, 'error')
+        return []
+    })
+}
+
+/**
+ * Zips the world into the backups of the instance, waiting until it is closed if it is open in the game
+ * @param {SimpleInstance} instance
+ * @param {String} world The folder name of the world
+ * @returns {Promise<BackupInfo | null>}
+ */
+export async function backupWorld(instance, world) {
+    return await withBackupNotifications(instance, `Backing up '${world}'...`, () => invoke('backup_world', { instance, world }))
+}
+
+/**
+ * @param {SimpleInstance} instance
+ * @param {BackupInfo} backup
+ * @param {String | null} newName Folder name of a new world to restore into, `null` to replace the world
+ * @returns {Promise<String | null>} The folder name of the restored world
+ */
+export async function restoreBackup(instance, backup, newName = null) {
+    return await withBackupNotifications(instance, `Restoring '${backup.world}'...`, () => {
+        return invoke('restore_backup', { instance, world: backup.world, fileName: backup.file_name, newName })
+    })
+}
+
+/**
+ * @param {SimpleInstance} instance
+ * @param {BackupInfo} backup
+ */
+export async function deleteBackup(instance, backup) {
+    await invoke('delete_backup', { instance, world: backup.world, fileName: backup.file_name }).catch(err => {
+        finishNotification(`worlds_backups_${instance.id}`, `Failed to delete the backup '${backup.file_name}': ${err}`, 'error')
+    })
+}
+
+/**
+ * @param {SimpleInstance} instance
+ * @returns {Promise<BackupSettings | null>}
+ */
+export async function getBackupSettings(instance) {
+    return await invoke('get_backup_settings', { instance }).catch(err => {
+        finishNotification(`worlds_backups_${instance.id}`, `Failed to read the backup settings of '${instance.name}': ${err}`, 'error')
+        return null
+    })
+}
+
+/**
+ * Saves the settings and removes the backups they don't keep
+ * @param {SimpleInstance} instance
+ * @param {BackupSettings} settings
+ */
+export async function saveBackupSettings(instance, settings) {
+    await invoke('save_backup_settings', { instance, settings }).catch(err => {
+        finishNotification(`worlds_backups_${instance.id}`, `Failed to save the backup settings of '${instance.name}': ${err}`, 'error')
+    })
+}
+
+/**
+ * @param {SimpleInstance} instance
+ * @param {String} world The folder name of the world
+ * @returns {Promise<Record<String, String>>} Game rule -> value
+ */
+export async function getGameRules(instance, world) {
+    return await invoke('get_game_rules', { instance, world }).catch(err => {
+        finishNotification(`worlds_edit_${instance.id}`, `Failed to read the game rules of '${world}': ${err}`, 'error')
+        return {}
+    })
+}
+
+/**
+ * Changes the settings in level.dat, the previous one is kept as level.dat_old. Fails while the world is open in the game.
+ * @param {SimpleInstance} instance
+ * @param {String} world The folder name of the world
+ * @param {WorldChanges} changes
+ * @returns {Promise<WorldInfo | null>} The changed world
+ */
+export async function editWorld(instance, world, changes) {
+    return await invoke('edit_world', { instance, world, changes }).then(/** @param {WorldInfo} changed */ changed => {
+        finishNotification(`worlds_edit_${instance.id}`, `Saved the settings of '${world}'!`, 'success')
+        return convertWorldIcon(changed)
+    }).catch(err => {
+        finishNotification(`worlds_edit_${instance.id}`, `Failed to change '${world}': ${err}`, 'error')
+        return null
+    })
+}
+
+/**
+ * @param {SimpleInstance} instance
+ * @param {String} world The folder name of the world
+ * @returns {Promise<String[]>} UUIDs of the players with data in the world, without the singleplayer player
+ */
+export async function listPlayers(instance, world) {
+    return await invoke('list_players', { instance, world }).catch(err => {
+        finishNotification(`worlds_edit_${instance.id}`, `Failed to read the players of '${world}': ${err}`, 'error')
+        return []
+    })
+}
+
+/**
+ * Moves a stuck player to the world spawn or clears their inventory
+ * @param {SimpleInstance} instance
+ * @param {String} world The folder name of the world
+ * @param {String | null} player UUID of the player, `null` for the singleplayer player
+ * @param {{ position: Boolean, inventory: Boolean }} reset
+ */
+export async function resetPlayer(instance, world, player, reset) {
+    await invoke('reset_player', { instance, world, player, reset }).then(() => {
+        finishNotification(`worlds_edit_${instance.id}`, `Reset the player in '${world}'!`, 'success')
+    }).catch(err => {
+        finishNotification(`worlds_edit_${instance.id}`, `Failed to reset the player in '${world}': ${err}`, 'error')
+    })
+}
+
+/**
+ * Shows the progress of a backup or restore until `action` is done
+ * @template T
+ * @param {SimpleInstance} instance
+ * @param {String} text
+ * @param {() => Promise<T>} action
+ * @returns {Promise<T | null>}
+ */
+async function withBackupNotifications(instance, text, action) {
+    const id = `worlds_backup_${instance.id}`
+    createNotification(id, text)
+    const unlisten = await listen(`${instance.id}_world_backup`, event => {
+        if(event.payload.status === 'running') {
+            createNotification(id, `${event.payload.text} (${event.payload.progress}/${event.payload.max_progress})`)
+        } else {
+            finishNotification(id, event.payload.text, event.payload.status)
+        }
+    })
+    return await action().catch(err => {
+        finishNotification(id, `${err}`, 'error')
+        return null
+    }).finally(unlisten)
+}
+
+/**
+ * @param {WorldInfo} world
+ */
+function convertWorldIcon(world) {
+    if(world.icon_path) world.icon_path = convertFileSrc(world.icon_path)
+    return world
+}